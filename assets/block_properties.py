@@ -0,0 +1,166 @@
+#!/usr/bin/env python3
+"""Writes block_properties.json, how each 1.18.1 block state interacts with light and the heightmaps, in the same order as the
+states in blocks.json.
+
+For every block it lists `light_block`, vanilla's `BlockState.getLightBlock` (how much light is lost passing through, from 0 to 15),
+`light_emission` (`BlockBehaviour.Properties.lightLevel`), and `motion_blocking`, whether the state counts for the MOTION_BLOCKING
+heightmap (its material blocks motion, or it holds a fluid), with one value per state. `propagatesSkylightDown` isn't listed,
+since in 1.18.1 it holds for exactly the states whose `light_block` is 0.
+
+The data reports don't include these, so they're worked out here the way vanilla's `BlockBehaviour` does:
+`getLightBlock` is 15 for states that are solid to render (they can occlude, and their shape is a full cube), and otherwise 0 if
+skylight passes straight down (the shape isn't a full cube and there's no fluid) or 1 if not. Blocks that override this
+(leaves, glass, barriers, tinted glass) are listed separately, as are the shapes, materials, and light levels, which come from the
+block classes and the `Blocks` registrations.
+"""
+import json
+from pathlib import Path
+
+ASSETS = Path(__file__).parent
+
+# blocks whose shape isn't a full cube in any state, besides the ones matched by suffix below
+PARTIAL = set(
+	"""
+	air cave_air void_air moving_piston piston_head structure_void light barrier
+	grass fern dead_bush seagrass tall_seagrass dandelion poppy blue_orchid allium azure_bluet oxeye_daisy cornflower wither_rose
+	lily_of_the_valley brown_mushroom red_mushroom sunflower lilac rose_bush peony tall_grass large_fern
+	torch wall_torch fire soul_fire chest trapped_chest ender_chest redstone_wire wheat carrots potatoes beetroots farmland dirt_path
+	ladder lever cactus sugar_cane cake repeater comparator iron_bars chain pumpkin_stem melon_stem attached_pumpkin_stem
+	attached_melon_stem vine glow_lichen lily_pad nether_wart enchanting_table brewing_stand cauldron water_cauldron lava_cauldron
+	powder_snow_cauldron end_portal end_portal_frame dragon_egg cocoa tripwire_hook tripwire flower_pot hopper daylight_detector
+	anvil chipped_anvil damaged_anvil end_rod chorus_plant kelp kelp_plant turtle_egg sea_pickle conduit bamboo_sapling bamboo
+	bubble_column scaffolding grindstone lectern stonecutter bell lantern soul_lantern campfire soul_campfire sweet_berry_bush
+	weeping_vines weeping_vines_plant twisting_vines twisting_vines_plant crimson_roots warped_roots nether_sprouts crimson_fungus
+	warped_fungus composter candle candle_cake amethyst_cluster lightning_rod pointed_dripstone cave_vines cave_vines_plant
+	spore_blossom azalea flowering_azalea moss_carpet big_dripleaf big_dripleaf_stem small_dripleaf hanging_roots sculk_sensor
+	nether_portal water lava glass_pane rail
+	""".split()
+)
+PARTIAL_SUFFIXES = (
+	"_sapling", "_bed", "_rail", "_tulip", "_stairs", "_wall", "_fence", "_fence_gate", "_door", "_trapdoor", "_pressure_plate",
+	"_button", "_sign", "_banner", "_carpet", "_torch", "_glass_pane", "_candle", "_candle_cake", "_coral", "_coral_fan",
+	"_coral_wall_fan", "_head", "_skull", "_amethyst_bud",
+)
+# blocks with a full shape that are registered with `noOcclusion` (or `noCollission`), so they aren't solid to render
+NOT_OCCLUDING = set("ice frosted_ice spawner beacon cobweb slime_block honey_block chorus_flower end_gateway shulker_box".split())
+# always hold water, whether or not they have a `waterlogged` property
+FLUID = set("water lava seagrass tall_seagrass kelp kelp_plant bubble_column".split())
+# blocks made of a material that doesn't block motion (air, portals, plants, fluids, fire, snow layers, decorations, and cobwebs),
+# besides the ones matched by prefix or suffix below
+NO_MOTION = set(
+	"""
+	air cave_air void_air light structure_void nether_portal end_portal end_gateway water lava bubble_column fire soul_fire snow cobweb
+	grass fern dead_bush seagrass tall_seagrass dandelion poppy blue_orchid allium azure_bluet oxeye_daisy cornflower wither_rose
+	lily_of_the_valley brown_mushroom red_mushroom sunflower lilac rose_bush peony tall_grass large_fern wheat carrots potatoes beetroots
+	sugar_cane lily_pad cocoa nether_wart pumpkin_stem melon_stem attached_pumpkin_stem attached_melon_stem sweet_berry_bush vine
+	glow_lichen chorus_plant chorus_flower azalea flowering_azalea spore_blossom big_dripleaf big_dripleaf_stem small_dripleaf
+	hanging_roots cave_vines cave_vines_plant crimson_fungus warped_fungus crimson_roots warped_roots nether_sprouts weeping_vines
+	weeping_vines_plant twisting_vines twisting_vines_plant kelp kelp_plant sea_pickle bamboo_sapling
+	torch wall_torch redstone_wire ladder rail lever tripwire tripwire_hook flower_pot repeater comparator end_rod scaffolding candle
+	""".split()
+)
+NO_MOTION_SUFFIXES = ("_sapling", "_tulip", "_carpet", "_torch", "_rail", "_button", "_candle", "_skull", "_wall_head")
+
+# light levels that don't depend on the state
+EMISSION = {
+	"beacon": 15, "conduit": 15, "end_gateway": 15, "end_portal": 15, "fire": 15, "glowstone": 15, "jack_o_lantern": 15,
+	"lantern": 15, "lava": 15, "lava_cauldron": 15, "sea_lantern": 15, "shroomlight": 15,
+	"end_rod": 14, "torch": 14, "wall_torch": 14,
+	"nether_portal": 11,
+	"crying_obsidian": 10, "soul_fire": 10, "soul_lantern": 10, "soul_torch": 10, "soul_wall_torch": 10,
+	"enchanting_table": 7, "ender_chest": 7, "glow_lichen": 7,
+	"amethyst_cluster": 5, "large_amethyst_bud": 4, "magma_block": 3, "medium_amethyst_bud": 2,
+	"brewing_stand": 1, "brown_mushroom": 1, "dragon_egg": 1, "end_portal_frame": 1, "small_amethyst_bud": 1,
+}
+
+
+def is_full(name, properties):
+	if name in ("piston", "sticky_piston"):
+		return properties["extended"] == "false"
+	if name == "snow":
+		return properties["layers"] == "8"
+	if name.endswith("_slab"):
+		return properties["type"] == "double"
+	return name not in PARTIAL and not name.endswith(PARTIAL_SUFFIXES) and not name.startswith("potted_")
+
+
+def light_block(name, properties):
+	if name == "tinted_glass":
+		return 15
+	if name.endswith("_leaves"):
+		return 1
+	if name in ("glass", "barrier") or name.endswith("_stained_glass"):
+		return 0
+	full = is_full(name, properties)
+	if full and name not in NOT_OCCLUDING and not name.endswith("_shulker_box"):
+		return 15
+	fluid = name in FLUID or properties.get("waterlogged") == "true"
+	return 0 if not full and not fluid else 1
+
+
+def motion_blocking(name, properties):
+	if name in FLUID or properties.get("waterlogged") == "true":
+		return True
+	if name in NO_MOTION or name.endswith(NO_MOTION_SUFFIXES) or name.startswith("potted_"):
+		return False
+	# heads are decorations, but the piston head isn't
+	if name.endswith("_head") and name != "piston_head":
+		return False
+	# living coral is a water plant, and dead coral is stone
+	if name.endswith(("_coral", "_coral_fan", "_coral_wall_fan")):
+		return name.startswith("dead_")
+	return True
+
+
+def light_emission(name, properties):
+	lit = properties.get("lit") == "true"
+	if name in EMISSION:
+		# glow lichen with no faces is only in the registry, not the world
+		if name == "glow_lichen" and not any(value == "true" for key, value in properties.items() if key != "waterlogged"):
+			return 0
+		return EMISSION[name]
+	if name in ("furnace", "smoker", "blast_furnace"):
+		return 13 if lit else 0
+	if name in ("campfire", "soul_campfire"):
+		return (15 if name == "campfire" else 10) if lit else 0
+	if name == "redstone_lamp":
+		return 15 if lit else 0
+	if name in ("redstone_ore", "deepslate_redstone_ore"):
+		return 9 if lit else 0
+	if name in ("redstone_torch", "redstone_wall_torch"):
+		return 7 if lit else 0
+	if name == "candle" or name.endswith("_candle"):
+		return 3 * int(properties["candles"]) if lit else 0
+	if name == "candle_cake" or name.endswith("_candle_cake"):
+		return 3 if lit else 0
+	if name in ("cave_vines", "cave_vines_plant"):
+		return 14 if properties["berries"] == "true" else 0
+	if name == "sea_pickle":
+		# dead pickles out of water don't glow
+		return 3 + 3 * int(properties["pickles"]) if properties["waterlogged"] == "true" else 0
+	if name == "respawn_anchor":
+		return int(properties["charges"]) * 15 // 4
+	if name == "light":
+		return int(properties["level"])
+	if name == "sculk_sensor":
+		return 1 if properties["sculk_sensor_phase"] == "active" else 0
+	return 0
+
+
+def main():
+	report = json.loads((ASSETS / "blocks.json").read_text())
+	lines = []
+	for name, block in report.items():
+		short = name.removeprefix("minecraft:")
+		states = [state.get("properties", {}) for state in block["states"]]
+		entry = {
+			"light_block": [light_block(short, properties) for properties in states],
+			"light_emission": [light_emission(short, properties) for properties in states],
+			"motion_blocking": [motion_blocking(short, properties) for properties in states],
+		}
+		lines.append(f"  {json.dumps(name)}: {json.dumps(entry, separators=(',', ':'))}")
+	(ASSETS / "block_properties.json").write_text("{\n" + ",\n".join(lines) + "\n}\n")
+
+
+if __name__ == "__main__":
+	main()
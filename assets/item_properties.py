@@ -0,0 +1,47 @@
+#!/usr/bin/env python3
+"""Writes item_properties.json, the `maxStackSize` of every 1.18.1 item in the `minecraft:item` registry of registries.json.
+
+The data reports don't include stack sizes, so they're taken here from the `Items` registrations: items with durability and
+the ones registered with `stacksTo(1)` hold one per slot, the ones registered with `stacksTo(16)` hold sixteen, and everything
+else holds 64.
+"""
+import json
+from pathlib import Path
+
+ASSETS = Path(__file__).parent
+
+# items with durability, which can't stack
+DAMAGEABLE_SUFFIXES = ("_sword", "_shovel", "_pickaxe", "_axe", "_hoe", "_helmet", "_chestplate", "_leggings", "_boots", "_on_a_stick")
+DAMAGEABLE = set("bow crossbow trident shield elytra fishing_rod flint_and_steel shears".split())
+# registered with `stacksTo(1)`
+SINGLE_SUFFIXES = ("_bucket", "_boat", "_minecart", "_bed", "_shulker_box", "_horse_armor", "_stew", "_soup", "_banner_pattern")
+SINGLE = set(
+	"""
+	potion splash_potion lingering_potion totem_of_undying minecart saddle shulker_box cake spyglass bundle writable_book
+	enchanted_book knowledge_book debug_stick
+	""".split()
+)
+# registered with `stacksTo(16)`
+SIXTEEN_SUFFIXES = ("_sign", "_banner")
+SIXTEEN = set("bucket snowball egg ender_pearl honey_bottle armor_stand written_book".split())
+
+
+def max_stack_size(name):
+	if name in DAMAGEABLE or name.endswith(DAMAGEABLE_SUFFIXES):
+		return 1
+	if name in SINGLE or name.endswith(SINGLE_SUFFIXES) or name.startswith("music_disc_"):
+		return 1
+	if name in SIXTEEN or name.endswith(SIXTEEN_SUFFIXES):
+		return 16
+	return 64
+
+
+def main():
+	report = json.loads((ASSETS / "registries.json").read_text())
+	entries = sorted(report["minecraft:item"]["entries"].items(), key=lambda entry: entry[1]["protocol_id"])
+	lines = [f"  {json.dumps(name)}: {{\"max_stack_size\":{max_stack_size(name.removeprefix('minecraft:'))}}}" for name, _ in entries]
+	(ASSETS / "item_properties.json").write_text("{\n" + ",\n".join(lines) + "\n}\n")
+
+
+if __name__ == "__main__":
+	main()
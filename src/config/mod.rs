@@ -9,6 +9,26 @@ fn default_port() -> u16 {
 	25565
 }
 
+fn default_compression_threshold() -> i32 {
+	256
+}
+
+fn default_view_distance() -> u8 {
+	10
+}
+
+fn default_keep_alive_timeout() -> u64 {
+	30
+}
+
+fn default_autosave_interval() -> u64 {
+	300
+}
+
+fn default_max_players() -> usize {
+	20
+}
+
 #[derive(Deserialize)]
 pub struct Config {
 	pub address: std::net::IpAddr,
@@ -17,6 +37,33 @@ pub struct Config {
 	pub logging: Vec<LoggingConfig>,
 	#[serde(default)]
 	pub listing: ListingConfig,
+	/// Players who try to join once this many are online are turned away
+	#[serde(default = "default_max_players")]
+	pub max_players: usize,
+	#[serde(default)]
+	pub auth: AuthConfig,
+	/// Packets at least this many bytes long are compressed. Negative values disable compression, like in vanilla.
+	#[serde(default = "default_compression_threshold")]
+	pub compression_threshold: i32,
+	/// The maximum view distance, in chunks. Clients that ask for less get less.
+	#[serde(default = "default_view_distance")]
+	pub view_distance: u8,
+	/// Seconds to wait for a response to a keep-alive before disconnecting the client
+	#[serde(default = "default_keep_alive_timeout")]
+	pub keep_alive_timeout: u64,
+	/// A vanilla world to load chunks from and save them to (the directory containing `level.dat`). Only the overworld is used.
+	/// If absent, the world is not saved.
+	#[serde(default)]
+	pub world_directory: Option<PathBuf>,
+	/// Seconds between saves of the world; 0 disables autosaving. The world is also saved on shutdown.
+	#[serde(default = "default_autosave_interval")]
+	pub autosave_interval: u64,
+	/// How to create chunks that aren't in the world directory
+	#[serde(default)]
+	pub generator: GeneratorConfig,
+	/// The world generation seed for new worlds; existing worlds keep the seed in their `level.dat`. If absent, a random seed is used.
+	#[serde(default)]
+	pub seed: Option<i64>,
 }
 
 #[derive(Deserialize)]
@@ -77,14 +124,113 @@ pub struct ListingConfig {
 	pub icon: Option<String>,
 	#[serde(default = "default_motd")]
 	pub motd: String,
+	/// Leaves out the names of the online players, leaving just the count
+	#[serde(default)]
+	pub hide_players: bool,
 }
 
 impl Default for ListingConfig {
 	fn default() -> Self {
-		Self { icon: default_icon(), motd: default_motd() }
+		Self {
+			icon: default_icon(),
+			motd: default_motd(),
+			hide_players: false,
+		}
+	}
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum AuthConfig {
+	/// Authenticate with Mojang's session server
+	Mojang {
+		/// PEM file with the key that session properties (skins, etc.) are signed with. If absent, signatures are not checked.
+		#[serde(default)]
+		public_key: Option<PathBuf>,
+	},
+	/// Don't authenticate; players are given UUIDs derived from their usernames, like in vanilla's offline mode
+	Offline,
+	/// Authenticate with a session server that implements the same API as Mojang's, such as a mock service or an authentication proxy
+	Custom {
+		/// Base URL, without a trailing slash, e.g., "https://sessionserver.mojang.com"
+		session_server: String,
+		/// Base URL, without a trailing slash, e.g., "https://api.mojang.com"
+		api_server: String,
+		/// Same as for `Mojang`
+		#[serde(default)]
+		public_key: Option<PathBuf>,
+	},
+	/// Like `Offline`, but only the usernames in `file` (one per line) may join
+	AllowList { file: PathBuf },
+}
+
+impl Default for AuthConfig {
+	fn default() -> Self {
+		Self::Mojang { public_key: None }
+	}
+}
+
+fn default_flat_biome() -> String {
+	"minecraft:plains".to_owned()
+}
+
+fn default_void_biome() -> String {
+	"minecraft:the_void".to_owned()
+}
+
+fn default_caves() -> bool {
+	true
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum GeneratorConfig {
+	/// Nothing but air
+	Void {
+		#[serde(default = "default_void_biome")]
+		biome: String,
+	},
+	/// Like vanilla's superflat
+	Flat {
+		/// From the bottom of the world up
+		layers: Vec<FlatLayer>,
+		#[serde(default = "default_flat_biome")]
+		biome: String,
+	},
+	/// Hills, oceans, and a few biomes from the seed
+	Noise {
+		#[serde(default = "default_caves")]
+		caves: bool,
+	},
+}
+
+/// The same as vanilla's default superflat preset
+impl Default for GeneratorConfig {
+	fn default() -> Self {
+		Self::Flat {
+			layers: vec![
+				FlatLayer {
+					block: "minecraft:bedrock".to_owned(),
+					height: 1,
+				},
+				FlatLayer { block: "minecraft:dirt".to_owned(), height: 2 },
+				FlatLayer {
+					block: "minecraft:grass_block".to_owned(),
+					height: 1,
+				},
+			],
+			biome: default_flat_biome(),
+		}
 	}
 }
 
+#[derive(Deserialize, Debug)]
+pub struct FlatLayer {
+	/// Uses the block's default state
+	pub block: String,
+	pub height: u32,
+}
+
 pub fn load() -> Result<Config, Error> {
 	let raw = Figment::new().merge(Toml::file("server.toml")).merge(Env::prefixed("RUSTMC_"));
 	raw.extract()
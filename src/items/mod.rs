@@ -0,0 +1,149 @@
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fmt::{self, Display, Formatter};
+use std::str::FromStr;
+use std::sync::OnceLock;
+
+pub mod recipes;
+pub mod stack;
+
+/// An item's protocol ID
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Default, PartialOrd, Ord)]
+pub struct Item(pub u16);
+
+impl Item {
+	pub const AIR: Self = Self(0);
+
+	pub fn id(self) -> u16 {
+		self.0
+	}
+	/// `None` if the item isn't in the registry
+	pub fn name(self) -> Option<&'static str> {
+		registry().item(self).map(|item| item.name.as_str())
+	}
+	/// How many of the item fit in one slot
+	pub fn max_stack_size(self) -> u8 {
+		registry().item(self).map_or(64, |item| item.max_stack_size)
+	}
+}
+
+/// Like `minecraft:stick`, or `#<id>` if the item isn't in the registry
+impl Display for Item {
+	fn fmt(&self, formatter: &mut Formatter<'_>) -> fmt::Result {
+		match self.name() {
+			Some(name) => formatter.write_str(name),
+			None => write!(formatter, "#{}", self.0),
+		}
+	}
+}
+
+/// The inverse of `Display`. The namespace defaults to `minecraft`.
+impl FromStr for Item {
+	type Err = anyhow::Error;
+	fn from_str(text: &str) -> anyhow::Result<Self> {
+		let name = if text.contains(':') { text.to_owned() } else { format!("minecraft:{}", text) };
+		registry().by_name(&name).ok_or_else(|| anyhow::anyhow!("Unknown item {}", text))
+	}
+}
+
+#[derive(Deserialize)]
+struct RegistriesReport {
+	#[serde(rename = "minecraft:item")]
+	item: ItemsReport,
+}
+
+#[derive(Deserialize)]
+struct ItemsReport {
+	entries: HashMap<String, EntryReport>,
+}
+
+#[derive(Deserialize)]
+struct EntryReport {
+	protocol_id: u16,
+}
+
+/// The properties the data report leaves out, from `item_properties.json`
+#[derive(Deserialize)]
+struct PropertyReport {
+	max_stack_size: u8,
+}
+
+struct ItemData {
+	name: String,
+	max_stack_size: u8,
+}
+
+/// Every item, from the `minecraft:item` registry in vanilla's `registries.json` data report and the table in `item_properties.json`
+pub struct Registry {
+	/// Indexed by protocol ID
+	items: Vec<ItemData>,
+	items_by_name: HashMap<String, Item>,
+}
+
+impl Registry {
+	pub fn from_report(report: &str, properties: &str) -> anyhow::Result<Self> {
+		let report: RegistriesReport = serde_json::from_str(report)?;
+		let mut properties: HashMap<String, PropertyReport> = serde_json::from_str(properties)?;
+		let mut items: Vec<Option<ItemData>> = Vec::new();
+		for (name, entry) in report.item.entries {
+			let id = usize::from(entry.protocol_id);
+			if items.len() <= id {
+				items.resize_with(id + 1, || None);
+			}
+			anyhow::ensure!(items[id].is_none(), "Item {} is listed twice", id);
+			let table = properties.remove(&name).ok_or_else(|| anyhow::anyhow!("Item {} is missing from the property table", name))?;
+			items[id] = Some(ItemData { max_stack_size: table.max_stack_size, name });
+		}
+		let items: Vec<_> = items.into_iter().enumerate().map(|(id, item)| item.ok_or_else(|| anyhow::anyhow!("Item {} is missing", id))).collect::<anyhow::Result<_>>()?;
+		Ok(Self {
+			items_by_name: items.iter().enumerate().map(|(id, item)| (item.name.clone(), Item(id as u16))).collect(),
+			items,
+		})
+	}
+	fn item(&self, item: Item) -> Option<&ItemData> {
+		self.items.get(usize::from(item.0))
+	}
+	/// The number of items
+	pub fn len(&self) -> usize {
+		self.items.len()
+	}
+	pub fn is_empty(&self) -> bool {
+		self.items.is_empty()
+	}
+	pub fn by_name(&self, name: &str) -> Option<Item> {
+		self.items_by_name.get(name).copied()
+	}
+}
+
+/// Loaded from `assets/registries.json` and `assets/item_properties.json` the first time it's used
+pub fn registry() -> &'static Registry {
+	static REGISTRY: OnceLock<Registry> = OnceLock::new();
+	// PANICS: the reports are part of the source tree, and the tests load them
+	REGISTRY.get_or_init(|| Registry::from_report(include_str!("../../assets/registries.json"), include_str!("../../assets/item_properties.json")).unwrap())
+}
+
+#[cfg(test)]
+mod test {
+	use super::{registry, Item};
+
+	#[test]
+	pub fn lookup() {
+		let stick: Item = "minecraft:stick".parse().unwrap();
+		assert_eq!("stick".parse::<Item>().unwrap(), stick);
+		assert_eq!(stick.to_string(), "minecraft:stick");
+		assert_eq!(registry().by_name("minecraft:stone"), Some(Item(1)));
+		assert!("minecraft:not_an_item".parse::<Item>().is_err());
+		assert_eq!(Item(u16::MAX).to_string(), "#65535");
+		assert_eq!(Item(u16::MAX).name(), None);
+		assert_eq!(registry().len(), 1101);
+		assert_eq!(Item(1100).to_string(), "minecraft:pointed_dripstone");
+
+		assert_eq!(stick.max_stack_size(), 64);
+		assert_eq!("diamond_pickaxe".parse::<Item>().unwrap().max_stack_size(), 1);
+		assert_eq!("ender_pearl".parse::<Item>().unwrap().max_stack_size(), 16);
+		assert_eq!("water_bucket".parse::<Item>().unwrap().max_stack_size(), 1);
+		assert_eq!("red_bed".parse::<Item>().unwrap().max_stack_size(), 1);
+		assert_eq!("creeper_banner_pattern".parse::<Item>().unwrap().max_stack_size(), 1);
+		assert_eq!("oak_sign".parse::<Item>().unwrap().max_stack_size(), 16);
+	}
+}
@@ -0,0 +1,463 @@
+use super::stack::ItemStack;
+use super::Item;
+use crate::packets::helpers::game::recipes::{Ingredient, Recipe, SmeltingRecipe, Type};
+use crate::packets::helpers::game::slot::Slot;
+use crate::packets::helpers::varint::VarInt;
+use crate::packets::helpers::wrappers::std::{PrefixedString, PrefixedVec};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+/// An item tag file, like `data/minecraft/tags/items/planks.json`
+#[derive(Deserialize)]
+struct TagJson {
+	/// Item names, or other tags prefixed with `#`
+	values: Vec<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum ChoiceJson {
+	Item(String),
+	Tag(String),
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum IngredientJson {
+	One(ChoiceJson),
+	Any(Vec<ChoiceJson>),
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ResultJson {
+	Name(String),
+	Stack {
+		item: String,
+		#[serde(default = "one")]
+		count: u8,
+	},
+}
+
+fn one() -> u8 {
+	1
+}
+
+#[derive(Deserialize)]
+struct CookingJson {
+	#[serde(default)]
+	group: String,
+	ingredient: IngredientJson,
+	result: ResultJson,
+	#[serde(default)]
+	experience: f32,
+	cookingtime: Option<i32>,
+}
+
+/// A recipe file, like `data/minecraft/recipes/stick.json`
+#[derive(Deserialize)]
+#[serde(tag = "type")]
+enum RecipeJson {
+	#[serde(rename = "minecraft:crafting_shaped")]
+	Shaped {
+		#[serde(default)]
+		group: String,
+		pattern: Vec<String>,
+		key: HashMap<String, IngredientJson>,
+		result: ResultJson,
+	},
+	#[serde(rename = "minecraft:crafting_shapeless")]
+	Shapeless {
+		#[serde(default)]
+		group: String,
+		ingredients: Vec<IngredientJson>,
+		result: ResultJson,
+	},
+	#[serde(rename = "minecraft:crafting_special_armordye")]
+	ArmorDye,
+	#[serde(rename = "minecraft:crafting_special_bookcloning")]
+	BookCloning,
+	#[serde(rename = "minecraft:crafting_special_mapcloning")]
+	MapCloning,
+	#[serde(rename = "minecraft:crafting_special_mapextending")]
+	MapExtending,
+	#[serde(rename = "minecraft:crafting_special_firework_rocket")]
+	FireworkRocket,
+	#[serde(rename = "minecraft:crafting_special_firework_star")]
+	FireworkStar,
+	#[serde(rename = "minecraft:crafting_special_firework_star_fade")]
+	FireworkStarFade,
+	#[serde(rename = "minecraft:crafting_special_repairitem")]
+	RepairItem,
+	#[serde(rename = "minecraft:crafting_special_tippedarrow")]
+	TippedArrow,
+	#[serde(rename = "minecraft:crafting_special_bannerduplicate")]
+	BannerDuplicate,
+	#[serde(rename = "minecraft:crafting_special_banneraddpattern")]
+	BannerAddPattern,
+	#[serde(rename = "minecraft:crafting_special_shielddecoration")]
+	ShieldDecoration,
+	#[serde(rename = "minecraft:crafting_special_shulkerboxcoloring")]
+	ShulkerBoxColoring,
+	#[serde(rename = "minecraft:crafting_special_suspiciousstew")]
+	SuspiciousStew,
+	#[serde(rename = "minecraft:smelting")]
+	Smelting(CookingJson),
+	#[serde(rename = "minecraft:blasting")]
+	Blasting(CookingJson),
+	#[serde(rename = "minecraft:smoking")]
+	Smoking(CookingJson),
+	#[serde(rename = "minecraft:campfire_cooking")]
+	CampfireCooking(CookingJson),
+	#[serde(rename = "minecraft:stonecutting")]
+	Stonecutting {
+		#[serde(default)]
+		group: String,
+		ingredient: IngredientJson,
+		result: String,
+		#[serde(default = "one")]
+		count: u8,
+	},
+	#[serde(rename = "minecraft:smithing")]
+	Smithing { base: IngredientJson, addition: IngredientJson, result: ResultJson },
+}
+
+/// Resolves item names and tags while the recipes are being loaded
+struct Loader {
+	tags: HashMap<String, TagJson>,
+}
+
+impl Loader {
+	fn tag(&self, name: &str, depth: usize, items: &mut Vec<Item>) -> anyhow::Result<()> {
+		anyhow::ensure!(depth < 16, "Tag {} refers to itself", name);
+		let tag = self.tags.get(name).ok_or_else(|| anyhow::anyhow!("Unknown tag {}", name))?;
+		for value in &tag.values {
+			match value.strip_prefix('#') {
+				Some(name) => self.tag(name, depth + 1, items)?,
+				None => items.push(value.parse()?),
+			}
+		}
+		Ok(())
+	}
+	fn choice(&self, choice: &ChoiceJson, items: &mut Vec<Item>) -> anyhow::Result<()> {
+		match choice {
+			ChoiceJson::Item(name) => items.push(name.parse()?),
+			ChoiceJson::Tag(name) => self.tag(name, 0, items)?,
+		}
+		Ok(())
+	}
+	fn ingredient(&self, ingredient: &IngredientJson) -> anyhow::Result<Ingredient> {
+		let mut items = Vec::new();
+		match ingredient {
+			IngredientJson::One(choice) => self.choice(choice, &mut items)?,
+			IngredientJson::Any(choices) => {
+				for choice in choices {
+					self.choice(choice, &mut items)?;
+				}
+			}
+		}
+		anyhow::ensure!(!items.is_empty(), "Ingredient doesn't match any items");
+		Ok(PrefixedVec::new(items.into_iter().map(|item| ItemStack::to_slot(Some(&ItemStack::new(item, 1)))).collect()))
+	}
+	fn result(result: &ResultJson) -> anyhow::Result<Slot> {
+		let (item, count) = match result {
+			ResultJson::Name(item) => (item, 1),
+			ResultJson::Stack { item, count } => (item, *count),
+		};
+		Ok(ItemStack::to_slot(Some(&ItemStack::new(item.parse()?, count))))
+	}
+	fn cooking(&self, cooking: &CookingJson, default_time: i32) -> anyhow::Result<SmeltingRecipe> {
+		Ok(SmeltingRecipe {
+			group: PrefixedString(cooking.group.clone()),
+			ingredient: self.ingredient(&cooking.ingredient)?,
+			result: Self::result(&cooking.result)?,
+			experience: cooking.experience,
+			cooking_time: VarInt(cooking.cookingtime.unwrap_or(default_time)),
+		})
+	}
+
+	fn recipe(&self, recipe: &RecipeJson) -> anyhow::Result<Type> {
+		Ok(match recipe {
+			RecipeJson::Shaped { group, pattern, key, result } => {
+				let width = pattern.first().map_or(0, |row| row.chars().count());
+				anyhow::ensure!((1..=3).contains(&width) && (1..=3).contains(&pattern.len()), "Pattern is the wrong size");
+				let mut ingredients = Vec::new();
+				for row in pattern {
+					anyhow::ensure!(row.chars().count() == width, "Pattern rows have different widths");
+					for symbol in row.chars() {
+						ingredients.push(match symbol {
+							' ' => PrefixedVec::new(Vec::new()),
+							_ => self.ingredient(key.get(&symbol.to_string()).ok_or_else(|| anyhow::anyhow!("Pattern uses undefined symbol {:?}", symbol))?)?,
+						});
+					}
+				}
+				Type::Shaped {
+					width: VarInt(width as i32),
+					height: VarInt(pattern.len() as i32),
+					group: PrefixedString(group.clone()),
+					ingredients,
+					result: Self::result(result)?,
+				}
+			}
+			RecipeJson::Shapeless { group, ingredients, result } => {
+				anyhow::ensure!((1..=9).contains(&ingredients.len()), "Recipe has {} ingredients", ingredients.len());
+				Type::Shapeless {
+					group: PrefixedString(group.clone()),
+					ingredients: PrefixedVec::new(ingredients.iter().map(|ingredient| self.ingredient(ingredient)).collect::<anyhow::Result<_>>()?),
+					result: Self::result(result)?,
+				}
+			}
+			RecipeJson::ArmorDye => Type::ArmorDye,
+			RecipeJson::BookCloning => Type::BookCloning,
+			RecipeJson::MapCloning => Type::MapCloning,
+			RecipeJson::MapExtending => Type::MapExtending,
+			RecipeJson::FireworkRocket => Type::FireworkRocket,
+			RecipeJson::FireworkStar => Type::FireworkStar,
+			RecipeJson::FireworkStarFade => Type::FireworkStarFade,
+			RecipeJson::RepairItem => Type::RepairItem,
+			RecipeJson::TippedArrow => Type::TippedArrow,
+			RecipeJson::BannerDuplicate => Type::BannerDuplicate,
+			RecipeJson::BannerAddPattern => Type::BannerAddPattern,
+			RecipeJson::ShieldDecoration => Type::ShieldDecoration,
+			RecipeJson::ShulkerBoxColoring => Type::ShulkerBoxColoring,
+			RecipeJson::SuspiciousStew => Type::SuspiciousStew,
+			RecipeJson::Smelting(cooking) => Type::Smelting(self.cooking(cooking, 200)?),
+			RecipeJson::Blasting(cooking) => Type::Blasting(self.cooking(cooking, 100)?),
+			RecipeJson::Smoking(cooking) => Type::Smoking(self.cooking(cooking, 100)?),
+			RecipeJson::CampfireCooking(cooking) => Type::CampfireCooking(self.cooking(cooking, 100)?),
+			RecipeJson::Stonecutting { group, ingredient, result, count } => Type::Stonecutting {
+				group: PrefixedString(group.clone()),
+				ingredient: self.ingredient(ingredient)?,
+				result: Self::result(&ResultJson::Stack { item: result.clone(), count: *count })?,
+			},
+			RecipeJson::Smithing { base, addition, result } => Type::Smithing {
+				base: self.ingredient(base)?,
+				addition: self.ingredient(addition)?,
+				result: Self::result(result)?,
+			},
+		})
+	}
+}
+
+/// Whether the item can be used as the ingredient
+pub fn matches(ingredient: &Ingredient, item: Item) -> bool {
+	ingredient.0.iter().any(|slot| slot.0.as_ref().is_some_and(|slot| slot.item_id.0 == i32::from(item.id())))
+}
+/// The items that can be used as the ingredient
+pub fn choices(ingredient: &Ingredient) -> impl Iterator<Item = Item> + '_ {
+	ingredient.0.iter().filter_map(|slot| slot.0.as_ref().and_then(|slot| u16::try_from(slot.item_id.0).ok()).map(Item))
+}
+/// Every ingredient of the recipe, including empty ones in shaped recipes
+pub fn ingredients(recipe: &Type) -> Vec<&Ingredient> {
+	match recipe {
+		Type::Shaped { ingredients, .. } => ingredients.iter().collect(),
+		Type::Shapeless { ingredients, .. } => ingredients.0.iter().collect(),
+		Type::Smelting(inner) | Type::Blasting(inner) | Type::Smoking(inner) | Type::CampfireCooking(inner) => vec![&inner.ingredient],
+		Type::Stonecutting { ingredient, .. } => vec![ingredient],
+		Type::Smithing { base, addition, .. } => vec![base, addition],
+		_ => Vec::new(),
+	}
+}
+/// What the recipe makes, for the types that have a fixed result
+pub fn result(recipe: &Type) -> Option<ItemStack> {
+	let result = match recipe {
+		Type::Shaped { result, .. } | Type::Shapeless { result, .. } | Type::Stonecutting { result, .. } | Type::Smithing { result, .. } => result,
+		Type::Smelting(inner) | Type::Blasting(inner) | Type::Smoking(inner) | Type::CampfireCooking(inner) => &inner.result,
+		_ => return None,
+	};
+	let result = result.0.clone()?;
+	ItemStack::try_from(result).ok()
+}
+
+/// Where each ingredient goes in a crafting grid of the given size, or `None` if the recipe doesn't fit. Shapeless ingredients are laid out in order.
+pub fn layout(recipe: &Type, width: usize, height: usize) -> Option<Vec<Option<&Ingredient>>> {
+	let mut grid = vec![None; width * height];
+	match recipe {
+		Type::Shaped {
+			width: recipe_width,
+			height: recipe_height,
+			ingredients,
+			..
+		} => {
+			let (recipe_width, recipe_height) = (usize::try_from(recipe_width.0).ok()?, usize::try_from(recipe_height.0).ok()?);
+			if recipe_width > width || recipe_height > height {
+				return None;
+			}
+			for (index, ingredient) in ingredients.iter().enumerate() {
+				if !ingredient.0.is_empty() {
+					grid[index / recipe_width * width + index % recipe_width] = Some(ingredient);
+				}
+			}
+		}
+		Type::Shapeless { ingredients, .. } => {
+			if ingredients.0.len() > grid.len() {
+				return None;
+			}
+			for (cell, ingredient) in grid.iter_mut().zip(&ingredients.0) {
+				*cell = Some(ingredient);
+			}
+		}
+		_ => return None,
+	}
+	Some(grid)
+}
+
+/// The smallest rectangle around the items in the grid, as its position and size
+fn bounds(grid: &[Option<ItemStack>], width: usize) -> Option<(usize, usize, usize, usize)> {
+	let filled = || grid.iter().enumerate().filter(|(_, stack)| stack.is_some()).map(|(index, _)| (index % width, index / width));
+	let (left, right) = (filled().map(|(x, _)| x).min()?, filled().map(|(x, _)| x).max()?);
+	let (top, bottom) = (filled().map(|(_, y)| y).min()?, filled().map(|(_, y)| y).max()?);
+	Some((left, top, right - left + 1, bottom - top + 1))
+}
+
+fn matches_shaped(grid: &[Option<ItemStack>], width: usize, recipe_width: usize, ingredients: &[Ingredient]) -> bool {
+	let (left, top, items_width, items_height) = match bounds(grid, width) {
+		Some(bounds) => bounds,
+		None => return false,
+	};
+	if items_width != recipe_width || items_width * items_height != ingredients.len() {
+		return false;
+	}
+	// recipes can be mirrored left to right
+	[false, true].into_iter().any(|mirrored| {
+		(0..items_height).all(|y| {
+			(0..items_width).all(|x| {
+				let recipe_x = if mirrored { items_width - 1 - x } else { x };
+				let ingredient = &ingredients[y * items_width + recipe_x];
+				match &grid[(top + y) * width + left + x] {
+					Some(stack) => matches(ingredient, stack.item),
+					None => ingredient.0.is_empty(),
+				}
+			})
+		})
+	})
+}
+
+/// Whether each item can be paired with a different ingredient
+fn matches_shapeless(items: &[Item], ingredients: &[Ingredient]) -> bool {
+	fn assign(items: &[Item], ingredients: &[Ingredient], used: &mut [bool]) -> bool {
+		let (item, rest) = match items.split_first() {
+			Some(split) => split,
+			None => return true,
+		};
+		for (index, ingredient) in ingredients.iter().enumerate() {
+			if !used[index] && matches(ingredient, *item) {
+				used[index] = true;
+				if assign(rest, ingredients, used) {
+					return true;
+				}
+				used[index] = false;
+			}
+		}
+		false
+	}
+	items.len() == ingredients.len() && assign(items, ingredients, &mut vec![false; ingredients.len()])
+}
+
+/// Every recipe, from vanilla's recipe and item tag files
+pub struct Registry {
+	recipes: Vec<Recipe>,
+	by_id: HashMap<String, usize>,
+}
+
+impl Registry {
+	/// Both arguments are JSON objects mapping IDs to the contents of the files for them
+	pub fn from_json(recipes: &str, tags: &str) -> anyhow::Result<Self> {
+		let loader = Loader { tags: serde_json::from_str(tags)? };
+		let mut files: Vec<(String, RecipeJson)> = serde_json::from_str::<HashMap<_, _>>(recipes)?.into_iter().collect();
+		files.sort_by(|(a, _), (b, _)| a.cmp(b));
+		let recipes = files
+			.into_iter()
+			.map(|(id, recipe)| {
+				let data = loader.recipe(&recipe).map_err(|err| err.context(format!("Invalid recipe {}", id)))?;
+				Ok(Recipe { id: PrefixedString(id), data })
+			})
+			.collect::<anyhow::Result<Vec<_>>>()?;
+		Ok(Self {
+			by_id: recipes.iter().enumerate().map(|(index, recipe)| (recipe.id.0.clone(), index)).collect(),
+			recipes,
+		})
+	}
+
+	pub fn all(&self) -> &[Recipe] {
+		&self.recipes
+	}
+	pub fn get(&self, id: &str) -> Option<&Recipe> {
+		self.by_id.get(id).map(|&index| &self.recipes[index])
+	}
+
+	/// The shaped or shapeless recipe that the crafting grid matches. The grid is given row by row.
+	pub fn craft(&self, grid: &[Option<ItemStack>], width: usize) -> Option<&Recipe> {
+		let items: Vec<_> = grid.iter().flatten().map(|stack| stack.item).collect();
+		self.recipes.iter().find(|recipe| match &recipe.data {
+			Type::Shaped { width: recipe_width, ingredients, .. } => usize::try_from(recipe_width.0).is_ok_and(|recipe_width| matches_shaped(grid, width, recipe_width, ingredients)),
+			Type::Shapeless { ingredients, .. } => matches_shapeless(&items, &ingredients.0),
+			_ => false,
+		})
+	}
+	/// What the item smelts into in a furnace, and how many ticks it takes
+	pub fn smelt(&self, input: Item) -> Option<(ItemStack, u16)> {
+		self.recipes.iter().find_map(|recipe| match &recipe.data {
+			Type::Smelting(smelting) if matches(&smelting.ingredient, input) => Some((result(&recipe.data)?, u16::try_from(smelting.cooking_time.0).ok()?)),
+			_ => None,
+		})
+	}
+}
+
+/// Loaded from `assets/recipes.json` and `assets/item_tags.json` the first time it's used
+pub fn registry() -> &'static Registry {
+	static REGISTRY: OnceLock<Registry> = OnceLock::new();
+	// PANICS: the files are part of the source tree, and the tests load them
+	REGISTRY.get_or_init(|| Registry::from_json(include_str!("../../assets/recipes.json"), include_str!("../../assets/item_tags.json")).unwrap())
+}
+
+/// The result of a crafting grid, for crafting windows
+pub fn craft(grid: &[Option<ItemStack>], width: usize) -> Option<ItemStack> {
+	registry().craft(grid, width).and_then(|recipe| result(&recipe.data))
+}
+/// For furnaces
+pub fn smelt(input: Item) -> Option<(ItemStack, u16)> {
+	registry().smelt(input)
+}
+
+#[cfg(test)]
+mod test {
+	use super::{craft, layout, registry, smelt};
+	use crate::items::stack::ItemStack;
+	use crate::packets::play::send;
+	use encde::Encode;
+
+	fn grid(items: &[&str]) -> Vec<Option<ItemStack>> {
+		items.iter().map(|name| (!name.is_empty()).then(|| ItemStack::new(name.parse().unwrap(), 1))).collect()
+	}
+	fn crafted(grid: &[Option<ItemStack>], width: usize) -> Option<(String, u8)> {
+		craft(grid, width).map(|stack| (stack.item.to_string(), stack.count))
+	}
+
+	#[test]
+	pub fn matching() {
+		assert!(registry().all().len() > 50);
+		send::DeclareRecipes(registry().all()).encode(&mut Vec::new()).unwrap();
+		assert_eq!(crafted(&grid(&["", "", "", "oak_log"]), 2), Some(("minecraft:oak_planks".to_owned(), 4)));
+		// anywhere in the grid, with any planks
+		assert_eq!(crafted(&grid(&["", "", "", "", "", "birch_planks", "", "", "oak_planks"]), 3), Some(("minecraft:stick".to_owned(), 4)));
+		assert_eq!(crafted(&grid(&["", "", "", "", "", "oak_planks", "", "", ""]), 3), None);
+
+		// mirrored
+		let axe = ["iron_ingot", "iron_ingot", "", "iron_ingot", "stick", "", "", "stick", ""];
+		let mirrored = ["iron_ingot", "iron_ingot", "", "stick", "iron_ingot", "", "stick", "", ""];
+		assert_eq!(crafted(&grid(&axe), 3), Some(("minecraft:iron_axe".to_owned(), 1)));
+		assert_eq!(crafted(&grid(&mirrored), 3), Some(("minecraft:iron_axe".to_owned(), 1)));
+		assert_eq!(crafted(&grid(&["iron_ingot", "stick", "", "iron_ingot", "stick", "", "", "", "iron_ingot"]), 3), None);
+
+		// a 3x3 recipe doesn't fit in the inventory
+		let chest = registry().get("minecraft:chest").unwrap();
+		assert!(layout(&chest.data, 2, 2).is_none());
+		assert_eq!(layout(&chest.data, 3, 3).unwrap().iter().filter(|cell| cell.is_some()).count(), 8);
+
+		let (stone, ticks) = smelt("cobblestone".parse().unwrap()).unwrap();
+		assert_eq!((stone.item.to_string().as_str(), ticks), ("minecraft:stone", 200));
+		assert!(smelt("stone".parse().unwrap()).is_none());
+	}
+}
@@ -0,0 +1,215 @@
+use super::Item;
+use crate::packets::helpers::game::chat;
+use crate::packets::helpers::game::slot::{PresentSlot, Slot};
+use crate::packets::helpers::varint::VarInt;
+use crate::packets::helpers::wrappers::nbt::OptionalNbt;
+use crate::packets::helpers::wrappers::std::PrefixedOption;
+use std::collections::HashMap;
+
+/// One or more of an item, along with its NBT tag
+#[derive(Debug, Clone, PartialEq)]
+pub struct ItemStack {
+	pub item: Item,
+	/// At least 1
+	pub count: u8,
+	pub nbt: HashMap<String, nbt::Value>,
+}
+
+impl ItemStack {
+	pub fn new(item: Item, count: u8) -> Self {
+		Self { item, count, nbt: HashMap::new() }
+	}
+
+	/// Whether the two stacks could be merged, ignoring their counts
+	pub fn stacks_with(&self, other: &Self) -> bool {
+		self.item == other.item && self.nbt == other.nbt
+	}
+
+	fn display(&self) -> Option<&HashMap<String, nbt::Value>> {
+		match self.nbt.get("display") {
+			Some(nbt::Value::Compound(display)) => Some(display),
+			_ => None,
+		}
+	}
+	/// Runs `f` on the `display` compound, which is removed afterwards if it's left empty
+	fn edit_display(&mut self, f: impl FnOnce(&mut HashMap<String, nbt::Value>)) {
+		let display = self.nbt.entry("display".to_owned()).or_insert_with(|| nbt::Value::Compound(HashMap::new()));
+		if !matches!(display, nbt::Value::Compound(..)) {
+			*display = nbt::Value::Compound(HashMap::new());
+		}
+		if let nbt::Value::Compound(compound) = display {
+			f(compound);
+			if compound.is_empty() {
+				self.nbt.remove("display");
+			}
+		}
+	}
+
+	/// The custom name, as a JSON text component
+	pub fn display_name(&self) -> Option<&str> {
+		match self.display()?.get("Name") {
+			Some(nbt::Value::String(name)) => Some(name),
+			_ => None,
+		}
+	}
+	/// `None` removes the custom name
+	pub fn set_display_name(&mut self, name: Option<&chat::Content>) -> anyhow::Result<()> {
+		let name = name.map(serde_json::to_string).transpose()?;
+		self.edit_display(|display| match name {
+			Some(name) => {
+				display.insert("Name".to_owned(), nbt::Value::String(name));
+			}
+			None => {
+				display.remove("Name");
+			}
+		});
+		Ok(())
+	}
+
+	/// Each line as a JSON text component
+	pub fn lore(&self) -> Vec<&str> {
+		match self.display().and_then(|display| display.get("Lore")) {
+			Some(nbt::Value::List(lines)) => lines
+				.iter()
+				.filter_map(|line| match line {
+					nbt::Value::String(line) => Some(line.as_str()),
+					_ => None,
+				})
+				.collect(),
+			_ => Vec::new(),
+		}
+	}
+	/// No lines removes the lore
+	pub fn set_lore(&mut self, lines: &[chat::Content]) -> anyhow::Result<()> {
+		let lines = lines.iter().map(|line| serde_json::to_string(line).map(nbt::Value::String)).collect::<Result<Vec<_>, _>>()?;
+		self.edit_display(|display| {
+			if lines.is_empty() {
+				display.remove("Lore");
+			} else {
+				display.insert("Lore".to_owned(), nbt::Value::List(lines));
+			}
+		});
+		Ok(())
+	}
+
+	/// Enchantment IDs, like `minecraft:sharpness`, and their levels
+	pub fn enchantments(&self) -> Vec<(&str, i16)> {
+		match self.nbt.get("Enchantments") {
+			Some(nbt::Value::List(enchantments)) => enchantments
+				.iter()
+				.filter_map(|enchantment| match enchantment {
+					nbt::Value::Compound(enchantment) => match (enchantment.get("id"), enchantment.get("lvl")) {
+						(Some(nbt::Value::String(id)), Some(nbt::Value::Short(level))) => Some((id.as_str(), *level)),
+						_ => None,
+					},
+					_ => None,
+				})
+				.collect(),
+			_ => Vec::new(),
+		}
+	}
+	/// Replaces the level if the item already has the enchantment. A level of 0 removes it.
+	pub fn set_enchantment(&mut self, id: &str, level: i16) {
+		let mut enchantments: Vec<_> = self.enchantments().into_iter().filter(|&(other, _)| other != id).map(|(id, level)| (id.to_owned(), level)).collect();
+		if level != 0 {
+			enchantments.push((id.to_owned(), level));
+		}
+		if enchantments.is_empty() {
+			self.nbt.remove("Enchantments");
+		} else {
+			let enchantments = enchantments
+				.into_iter()
+				.map(|(id, level)| nbt::Value::Compound([("id".to_owned(), nbt::Value::String(id)), ("lvl".to_owned(), nbt::Value::Short(level))].into_iter().collect()))
+				.collect();
+			self.nbt.insert("Enchantments".to_owned(), nbt::Value::List(enchantments));
+		}
+	}
+
+	/// `None` is an empty slot
+	pub fn to_slot(stack: Option<&Self>) -> Slot {
+		PrefixedOption(stack.map(|stack| stack.clone().into()))
+	}
+	/// Fails if the slot has an unknown item or a count that's out of range
+	pub fn from_slot(slot: Slot) -> anyhow::Result<Option<Self>> {
+		slot.0.map(Self::try_from).transpose()
+	}
+}
+
+impl From<ItemStack> for PresentSlot {
+	fn from(stack: ItemStack) -> Self {
+		Self {
+			item_id: VarInt(stack.item.id().into()),
+			// PANICS: no item stacks past 64
+			count: stack.count.try_into().unwrap(),
+			nbt_data: OptionalNbt(if stack.nbt.is_empty() { None } else { Some(stack.nbt) }),
+		}
+	}
+}
+
+impl TryFrom<PresentSlot> for ItemStack {
+	type Error = anyhow::Error;
+	fn try_from(slot: PresentSlot) -> anyhow::Result<Self> {
+		let item = u16::try_from(slot.item_id.0)
+			.ok()
+			.map(Item)
+			.filter(|item| item.name().is_some())
+			.ok_or_else(|| anyhow::anyhow!("Unknown item ID {}", slot.item_id.0))?;
+		anyhow::ensure!(slot.count > 0, "Item stack has a count of {}", slot.count);
+		Ok(Self {
+			item,
+			count: slot.count as u8,
+			nbt: slot.nbt_data.0.unwrap_or_default(),
+		})
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::ItemStack;
+	use crate::packets::helpers::game::chat::Content;
+	use crate::packets::helpers::game::slot::Slot;
+	use encde::{Decode, Encode};
+
+	#[test]
+	pub fn nbt_and_slots() {
+		let mut stack = ItemStack::new("diamond_sword".parse().unwrap(), 1);
+		let plain = ItemStack::to_slot(Some(&stack));
+		assert!(plain.0.as_ref().unwrap().nbt_data.0.is_none());
+
+		let name = Content {
+			text: "Excalibur".to_owned(),
+			..Default::default()
+		};
+		stack.set_display_name(Some(&name)).unwrap();
+		stack
+			.set_lore(&[Content {
+				text: "Pulled from a stone".to_owned(),
+				..Default::default()
+			}])
+			.unwrap();
+		stack.set_enchantment("minecraft:sharpness", 3);
+		stack.set_enchantment("minecraft:unbreaking", 1);
+		stack.set_enchantment("minecraft:sharpness", 5);
+		assert!(stack.display_name().unwrap().contains("Excalibur"));
+		assert_eq!(stack.lore().len(), 1);
+		assert_eq!(stack.enchantments(), [("minecraft:unbreaking", 1), ("minecraft:sharpness", 5)]);
+		assert!(!stack.stacks_with(&ItemStack::new(stack.item, 1)));
+
+		let mut encoded = Vec::new();
+		ItemStack::to_slot(Some(&stack)).encode(&mut encoded).unwrap();
+		let decoded = ItemStack::from_slot(Slot::decode(&mut encoded.as_slice()).unwrap()).unwrap().unwrap();
+		assert_eq!(decoded, stack);
+
+		stack.set_display_name(None).unwrap();
+		stack.set_lore(&[]).unwrap();
+		stack.set_enchantment("minecraft:unbreaking", 0);
+		stack.set_enchantment("minecraft:sharpness", 0);
+		assert!(stack.nbt.is_empty());
+
+		// an empty slot, and then a stack of 3 stone without NBT
+		assert!(ItemStack::from_slot(Slot::decode(&mut [0u8].as_slice()).unwrap()).unwrap().is_none());
+		let stone = ItemStack::from_slot(Slot::decode(&mut [1u8, 1, 3, 0].as_slice()).unwrap()).unwrap().unwrap();
+		assert_eq!((stone.item.to_string().as_str(), stone.count), ("minecraft:stone", 3));
+		assert!(ItemStack::from_slot(Slot::decode(&mut [1u8, 1, 0, 0].as_slice()).unwrap()).is_err());
+	}
+}
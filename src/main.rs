@@ -1,7 +1,9 @@
 mod config;
+mod items;
 mod logging;
 mod packets;
 mod server;
+mod world;
 
 fn main() -> anyhow::Result<()> {
 	let config: &'static _ = Box::leak(Box::new(config::load()?));
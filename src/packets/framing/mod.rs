@@ -0,0 +1,118 @@
+//! Splitting the byte stream into packets, optionally with compression
+use super::helpers::varint::VarInt;
+use encde::{Decode, Encode};
+use flate2::{read::ZlibDecoder, write::ZlibEncoder};
+use std::io::{Read, Write};
+
+/// The same as vanilla's limit
+const MAX_UNCOMPRESSED_LENGTH: usize = 8 * 1024 * 1024;
+
+fn to_varint(len: usize) -> encde::Result<VarInt> {
+	Ok(VarInt(len.try_into().map_err(|err| encde::Error::Custom(Box::new(err)))?))
+}
+
+fn from_varint(len: VarInt) -> encde::Result<usize> {
+	len.try_into().map_err(|err| encde::Error::Custom(Box::new(err)))
+}
+
+/// Returns `None` if the stream ended cleanly before the packet started.
+///
+/// `compression_threshold` must match what was sent to the client in Set Compression.
+pub fn read_frame(reader: &mut dyn Read, compression_threshold: Option<usize>) -> encde::Result<Option<Vec<u8>>> {
+	let packet_len = match VarInt::decode(reader) {
+		Ok(packet_len) => from_varint(packet_len)?,
+		Err(encde::Error::Io(err)) if err.kind() == std::io::ErrorKind::UnexpectedEof => return Ok(None),
+		Err(err) => return Err(err),
+	};
+	let mut packet_data = vec![0u8; packet_len];
+	reader.read_exact(&mut packet_data)?;
+	if compression_threshold.is_none() {
+		return Ok(Some(packet_data));
+	}
+	let mut packet_data = packet_data.as_slice();
+	// 0 means the data is not compressed
+	let uncompressed_len = from_varint(VarInt::decode(&mut packet_data)?)?;
+	if uncompressed_len == 0 {
+		return Ok(Some(packet_data.to_vec()));
+	}
+	if uncompressed_len > MAX_UNCOMPRESSED_LENGTH {
+		return Err(encde::Error::UnexpectedLength {
+			expected: MAX_UNCOMPRESSED_LENGTH,
+			actual: uncompressed_len,
+		});
+	}
+	let mut uncompressed = Vec::with_capacity(uncompressed_len);
+	// read one byte more than expected so we can tell if there was too much data
+	ZlibDecoder::new(packet_data).take(uncompressed_len as u64 + 1).read_to_end(&mut uncompressed)?;
+	if uncompressed.len() != uncompressed_len {
+		return Err(encde::Error::UnexpectedLength {
+			expected: uncompressed_len,
+			actual: uncompressed.len(),
+		});
+	}
+	Ok(Some(uncompressed))
+}
+
+/// Packets whose data is at least `compression_threshold` bytes long will be compressed
+pub fn write_frame(writer: &mut dyn Write, packet_data: &[u8], compression_threshold: Option<usize>) -> encde::Result<()> {
+	let frame = match compression_threshold {
+		None => {
+			to_varint(packet_data.len())?.encode(writer)?;
+			writer.write_all(packet_data)?;
+			return Ok(());
+		}
+		Some(threshold) if packet_data.len() < threshold => {
+			let mut frame = encde::util::encode_to_vec(&VarInt(0))?;
+			frame.extend_from_slice(packet_data);
+			frame
+		}
+		Some(_) => {
+			let mut encoder = ZlibEncoder::new(encde::util::encode_to_vec(&to_varint(packet_data.len())?)?, flate2::Compression::default());
+			encoder.write_all(packet_data)?;
+			encoder.finish()?
+		}
+	};
+	to_varint(frame.len())?.encode(writer)?;
+	writer.write_all(&frame)?;
+	Ok(())
+}
+
+#[cfg(test)]
+mod test {
+	use super::{read_frame, write_frame, VarInt};
+	use encde::Decode;
+
+	fn roundtrip(packet_data: &[u8], compression_threshold: Option<usize>) -> Vec<u8> {
+		let mut stream = Vec::new();
+		write_frame(&mut stream, packet_data, compression_threshold).unwrap();
+		let mut reader = stream.as_slice();
+		assert_eq!(read_frame(&mut reader, compression_threshold).unwrap().unwrap(), packet_data);
+		assert!(reader.is_empty());
+		stream
+	}
+
+	#[test]
+	fn uncompressed() {
+		assert_eq!(roundtrip(&[0x00, 0x01, 0x02], None), [0x03, 0x00, 0x01, 0x02]);
+	}
+
+	#[test]
+	fn below_threshold() {
+		assert_eq!(roundtrip(&[0x00, 0x01, 0x02], Some(256)), [0x04, 0x00, 0x00, 0x01, 0x02]);
+	}
+
+	#[test]
+	fn above_threshold() {
+		let packet_data: Vec<u8> = (0..1000u32).map(|x| (x % 7) as u8).collect();
+		let stream = roundtrip(&packet_data, Some(256));
+		assert!(stream.len() < packet_data.len());
+		let mut stream = stream.as_slice();
+		let _packet_len = VarInt::decode(&mut stream).unwrap();
+		assert_eq!(VarInt::decode(&mut stream).unwrap(), VarInt(1000));
+	}
+
+	#[test]
+	fn clean_end_of_stream() {
+		assert!(read_frame(&mut [].as_slice(), Some(256)).unwrap().is_none());
+	}
+}
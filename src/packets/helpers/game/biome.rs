@@ -2,6 +2,22 @@ use serde::{Deserialize, Serialize};
 
 pub struct BiomeRegistry(Vec<BiomeRegistryEntry>);
 
+impl BiomeRegistry {
+	pub fn id_of(&self, name: &str) -> Option<i32> {
+		self.0.iter().find(|entry| entry.name == name).map(|entry| entry.id)
+	}
+	/// Names and IDs
+	pub fn iter(&self) -> impl Iterator<Item = (&str, i32)> {
+		self.0.iter().map(|entry| (entry.name.as_str(), entry.id))
+	}
+	pub fn len(&self) -> usize {
+		self.0.len()
+	}
+	pub fn is_empty(&self) -> bool {
+		self.0.is_empty()
+	}
+}
+
 #[derive(Deserialize)]
 struct BiomeRegistryWireDe<'a> {
 	#[serde(rename = "type")]
@@ -1,6 +1,8 @@
 use super::super::position::UnpackedPosition;
-use super::super::varint::VarLong;
+use super::super::varint::{VarInt, VarLong};
+use super::super::wrappers::util::{encode_encode_slice, encode_u8_slice};
 use super::super::wrappers::{bitvec::PrefixedBitVec, std::*};
+use bitvec::vec::BitVec;
 use encde::{Decode, Encode, Result as EResult};
 use std::io::Write;
 
@@ -15,17 +17,259 @@ pub struct LightUpdateCommon {
 	block_light_array: PrefixedVec<BlockLightData>,
 }
 
-// TODO implement
-// #[derive(Encode, Decode)]
-pub struct HeightMaps(());
+impl LightUpdateCommon {
+	/// No light data at all; the client treats every section as unlit
+	pub fn empty() -> Self {
+		Self {
+			trust_edges: true,
+			sky_light_mask: PrefixedBitVec(Default::default()),
+			block_light_mask: PrefixedBitVec(Default::default()),
+			empty_sky_light_mask: PrefixedBitVec(Default::default()),
+			empty_block_light_mask: PrefixedBitVec(Default::default()),
+			sky_light_array: PrefixedVec::new(Vec::new()),
+			block_light_array: PrefixedVec::new(Vec::new()),
+		}
+	}
+	/// Each entry is a light section index, starting with the section below the world, and its 2048 bytes of light levels or `None` if it's entirely dark.
+	/// Sections that aren't mentioned are left as they are.
+	pub fn new(sky_light: Vec<(usize, Option<Vec<u8>>)>, block_light: Vec<(usize, Option<Vec<u8>>)>) -> Self {
+		fn masks(sections: &[(usize, Option<Vec<u8>>)]) -> (PrefixedBitVec, PrefixedBitVec) {
+			// only as long as the highest bit that is set, so that there are no trailing zero longs, like vanilla's `BitSet.toLongArray`
+			let mask = |empty: bool| {
+				let len = sections.iter().filter(|(_, data)| data.is_none() == empty).map(|(idx, _)| idx + 1).max().unwrap_or(0);
+				let mut mask = BitVec::repeat(false, len);
+				for (idx, _) in sections.iter().filter(|(_, data)| data.is_none() == empty) {
+					mask.set(*idx, true);
+				}
+				PrefixedBitVec(mask)
+			};
+			(mask(false), mask(true))
+		}
+		let (sky_light_mask, empty_sky_light_mask) = masks(&sky_light);
+		let (block_light_mask, empty_block_light_mask) = masks(&block_light);
+		Self {
+			trust_edges: true,
+			sky_light_mask,
+			block_light_mask,
+			empty_sky_light_mask,
+			empty_block_light_mask,
+			sky_light_array: PrefixedVec::new(sky_light.into_iter().filter_map(|(_, data)| data).map(|data| SkyLightData(PrefixedBitVec(BitVec::from_vec(data)))).collect()),
+			block_light_array: PrefixedVec::new(block_light.into_iter().filter_map(|(_, data)| data).map(|data| BlockLightData(PrefixedBitVec(BitVec::from_vec(data)))).collect()),
+		}
+	}
+}
+
+/// The number of bits needed to represent every value in `0..count`, like vanilla's `Mth.ceillog2`
+pub fn ceil_log2(count: usize) -> u8 {
+	(usize::BITS - count.saturating_sub(1).leading_zeros()) as u8
+}
+
+/// Packs entries into longs the way vanilla does since 1.16: entries never span two longs, and the first entry is in the least-significant bits
+pub fn pack_entries(entries: &[i32], bits_per_entry: u8) -> Vec<u64> {
+	let bits_per_entry = usize::from(bits_per_entry);
+	let entries_per_long = 64 / bits_per_entry;
+	let mask = (1u64 << bits_per_entry) - 1;
+	entries
+		.chunks(entries_per_long)
+		.map(|chunk| chunk.iter().enumerate().fold(0u64, |long, (idx, &entry)| long | ((entry as u64 & mask) << (idx * bits_per_entry))))
+		.collect()
+}
+
+/// The inverse of `pack_entries`; `count` is needed because the last long may be partially filled
+pub fn unpack_entries(data: &[u64], bits_per_entry: u8, count: usize) -> Vec<i32> {
+	let bits_per_entry = usize::from(bits_per_entry);
+	let entries_per_long = 64 / bits_per_entry;
+	let mask = (1u64 << bits_per_entry) - 1;
+	data.iter().flat_map(|&long| (0..entries_per_long).map(move |idx| ((long >> (idx * bits_per_entry)) & mask) as i32)).take(count).collect()
+}
+
+/// Returns the distinct entries in order of first appearance, and each entry's index into that palette
+pub fn build_palette(entries: &[i32]) -> (Vec<i32>, Vec<i32>) {
+	let mut palette = Vec::new();
+	let mut palette_indices = std::collections::HashMap::new();
+	let indices = entries
+		.iter()
+		.map(|&entry| {
+			*palette_indices.entry(entry).or_insert_with(|| {
+				palette.push(entry);
+				palette.len() as i32 - 1
+			})
+		})
+		.collect();
+	(palette, indices)
+}
+
+/// The bits needed for the global block state palette, i.e., `ceil(log2(number of block states))`
+pub const GLOBAL_BLOCK_STATE_BITS: u8 = 15;
+
+/// Heightmaps for a chunk column, as an NBT compound of packed long arrays
+pub struct HeightMaps(nbt::Blob);
+
+impl HeightMaps {
+	/// An empty compound, which the client accepts; it will compute the heightmaps itself
+	pub fn empty() -> Self {
+		Self(nbt::Blob::new())
+	}
+	/// Both heightmaps are indexed by `z * 16 + x` and hold, relative to the bottom of the world, the Y coordinate one above the highest relevant block (so 0 for an empty column).
+	/// `motion_blocking` counts blocks that block movement or contain fluid; `world_surface` counts all non-air blocks.
+	pub fn new(motion_blocking: &[i32; 256], world_surface: &[i32; 256], world_height: usize) -> Self {
+		let bits_per_entry = ceil_log2(world_height + 1);
+		let pack = |heights: &[i32; 256]| nbt::Value::LongArray(pack_entries(heights, bits_per_entry).into_iter().map(|long| long as i64).collect());
+		let mut blob = nbt::Blob::new();
+		// PANICS: insert only fails for lists of mismatched types
+		blob.insert("MOTION_BLOCKING", pack(motion_blocking)).unwrap();
+		blob.insert("WORLD_SURFACE", pack(world_surface)).unwrap();
+		Self(blob)
+	}
+}
+
+impl Encode for HeightMaps {
+	fn encode(&self, mut writer: &mut dyn Write) -> EResult<()> {
+		self.0.to_writer(&mut writer).map_err(|err| encde::Error::Custom(Box::new(err)))
+	}
+}
+
+/// All the sections in a chunk column, from the bottom up
+pub struct Blocks(pub Vec<Section>);
+
+impl Encode for Blocks {
+	fn encode(&self, writer: &mut dyn Write) -> EResult<()> {
+		// the sections are sent as a byte array
+		let mut data = Vec::new();
+		for section in self.0.iter() {
+			section.encode(&mut data)?;
+		}
+		encode_u8_slice(writer, &data)
+	}
+}
 
-// TODO implement
-// #[derive(Encode, Decode)]
-pub struct Blocks(());
+/// 16x16x16 blocks
+#[derive(Encode)]
+pub struct Section {
+	/// Non-air blocks
+	pub block_count: i16,
+	/// 4096 entries, indexed by `(y * 16 + z) * 16 + x`
+	pub block_states: PalettedContainer,
+	/// 64 entries, one for each 4x4x4 cell, indexed the same way as `block_states`
+	pub biomes: PalettedContainer,
+}
 
-// TODO implement
-// #[derive(Encode, Decode)]
-pub struct BlockEntities(());
+pub enum PalettedContainer {
+	/// Every entry has the same value
+	Single(VarInt),
+	/// Entries are indices into the palette
+	Indirect { bits_per_entry: u8, palette: Vec<VarInt>, data: Vec<u64> },
+	/// Entries are global IDs
+	Direct { bits_per_entry: u8, data: Vec<u64> },
+}
+
+/// The parameter is the number of bits per entry
+enum Encoding {
+	Single,
+	Indirect(u8),
+	Direct(u8),
+}
+
+impl PalettedContainer {
+	/// Picks the same encoding as vanilla: 4 to 8 bits per entry with a palette, or the global palette beyond that
+	pub fn block_states(entries: &[i32]) -> Self {
+		Self::new(entries, |palette_bits| match palette_bits {
+			0 => Encoding::Single,
+			1..=4 => Encoding::Indirect(4),
+			5..=8 => Encoding::Indirect(palette_bits),
+			_ => Encoding::Direct(GLOBAL_BLOCK_STATE_BITS),
+		})
+	}
+	/// Picks the same encoding as vanilla: 1 to 3 bits per entry with a palette, or the global palette beyond that.
+	/// `biome_count` is the size of the biome registry sent in the dimension codec, which determines the size of direct entries.
+	pub fn biomes(entries: &[i32], biome_count: usize) -> Self {
+		Self::new(entries, |palette_bits| match palette_bits {
+			0 => Encoding::Single,
+			1..=3 => Encoding::Indirect(palette_bits),
+			_ => Encoding::Direct(ceil_log2(biome_count)),
+		})
+	}
+	/// `encoding_for` picks the encoding based on the number of bits needed to index the palette
+	fn new(entries: &[i32], encoding_for: impl FnOnce(u8) -> Encoding) -> Self {
+		let (palette, indices) = build_palette(entries);
+		match encoding_for(ceil_log2(palette.len())) {
+			// an empty container isn't valid but there's no sensible way to fail here
+			Encoding::Single => Self::Single(VarInt(palette.first().copied().unwrap_or(0))),
+			Encoding::Indirect(bits_per_entry) => Self::Indirect {
+				bits_per_entry,
+				palette: palette.into_iter().map(VarInt).collect(),
+				data: pack_entries(&indices, bits_per_entry),
+			},
+			Encoding::Direct(bits_per_entry) => Self::Direct {
+				bits_per_entry,
+				data: pack_entries(entries, bits_per_entry),
+			},
+		}
+	}
+}
+
+impl Encode for PalettedContainer {
+	fn encode(&self, writer: &mut dyn Write) -> EResult<()> {
+		match self {
+			Self::Single(value) => {
+				// bits per entry
+				0u8.encode(writer)?;
+				value.encode(writer)?;
+				// data array length
+				VarInt(0).encode(writer)
+			}
+			Self::Indirect { bits_per_entry, palette, data } => {
+				bits_per_entry.encode(writer)?;
+				encode_encode_slice(writer, palette)?;
+				encode_encode_slice(writer, data)
+			}
+			Self::Direct { bits_per_entry, data } => {
+				bits_per_entry.encode(writer)?;
+				encode_encode_slice(writer, data)
+			}
+		}
+	}
+}
+
+pub struct BlockEntity {
+	/// Relative to the chunk, 0-15
+	pub x: u8,
+	/// Absolute
+	pub y: i16,
+	/// Relative to the chunk, 0-15
+	pub z: u8,
+	/// ID in the block entity type registry
+	pub kind: VarInt,
+	/// The client-visible data, such as the text on a sign
+	pub data: Option<nbt::Blob>,
+}
+
+impl Encode for BlockEntity {
+	fn encode(&self, mut writer: &mut dyn Write) -> EResult<()> {
+		(((self.x & 0xf) << 4) | (self.z & 0xf)).encode(writer)?;
+		self.y.encode(writer)?;
+		self.kind.encode(writer)?;
+		match &self.data {
+			Some(data) => data.to_writer(&mut writer).map_err(|err| encde::Error::Custom(Box::new(err))),
+			// TAG_End
+			None => 0u8.encode(writer),
+		}
+	}
+}
+
+pub struct BlockEntities(pub Vec<BlockEntity>);
+
+impl BlockEntities {
+	pub fn none() -> Self {
+		Self(Vec::new())
+	}
+}
+
+impl Encode for BlockEntities {
+	fn encode(&self, writer: &mut dyn Write) -> EResult<()> {
+		encode_encode_slice(writer, &self.0)
+	}
+}
 
 /// 2048 u8 backing items, 4096 4-bit entries
 #[derive(Encode, Decode)]
@@ -35,14 +279,21 @@ pub struct SkyLightData(PrefixedBitVec<u8>);
 #[derive(Encode, Decode)]
 pub struct BlockLightData(PrefixedBitVec<u8>);
 
-#[derive(Encode, Decode)]
+#[derive(Encode, Decode, Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub struct Position<T: Encode + Decode> {
 	pub x: T,
 	pub z: T,
 }
 
+/// In units of sections, i.e., 16 blocks
 pub struct SectionPosition(UnpackedPosition<i32>);
 
+impl SectionPosition {
+	pub fn new(x: i32, y: i32, z: i32) -> Self {
+		Self(UnpackedPosition { x, y, z })
+	}
+}
+
 impl Encode for SectionPosition {
 	fn encode(&self, writer: &mut dyn Write) -> EResult<()> {
 		let encoded: u64 = ((((self.0.x as u32) & 0x3fffff) as u64) << 42) | (((self.0.y as u32) & 0xfffff) as u64) | ((((self.0.z as u32) & 0x3fffff) as u64) << 20);
@@ -55,9 +306,128 @@ pub struct MultiBlockChangeEntry {
 	new_block_state: i32,
 }
 
+impl MultiBlockChangeEntry {
+	/// `relative_position` is within the section, so each coordinate is 0-15
+	pub fn new(relative_position: UnpackedPosition<u8>, new_block_state: i32) -> Self {
+		Self { relative_position, new_block_state }
+	}
+}
+
 impl Encode for MultiBlockChangeEntry {
 	fn encode(&self, writer: &mut dyn Write) -> EResult<()> {
 		let encoded: u64 = ((self.new_block_state as u64) << 12) | ((self.relative_position.x as u64) << 8) | ((self.relative_position.z as u64) << 4) | (self.relative_position.y as u64);
 		VarLong(encoded as i64).encode(writer)
 	}
 }
+
+#[cfg(test)]
+mod test {
+	use super::super::super::varint::VarInt;
+	use super::{pack_entries, unpack_entries, BlockEntities, BlockEntity, Blocks, HeightMaps, LightUpdateCommon, PalettedContainer, Position, Section};
+	use crate::packets::play::send::{self, Packet as Send};
+	use encde::util::encode_to_vec;
+
+	/// A cross-check rather than vanilla's own bytes: assembled by `fixtures/update_chunk_data.py` from a separate reading of vanilla's writers, not captured from a server
+	const UPDATE_CHUNK_DATA: &[u8] = include_bytes!("fixtures/update_chunk_data.bin");
+
+	#[test]
+	pub fn single_valued() {
+		assert_eq!(encode_to_vec(&PalettedContainer::block_states(&[0; 4096])).unwrap(), [0, 0, 0]);
+		assert_eq!(encode_to_vec(&PalettedContainer::biomes(&[1; 64], 15)).unwrap(), [0, 1, 0]);
+	}
+
+	#[test]
+	pub fn indirect_block_states() {
+		// air at the origin, stone everywhere else
+		let mut entries = [1; 4096];
+		entries[0] = 0;
+		let encoded = encode_to_vec(&PalettedContainer::block_states(&entries)).unwrap();
+		// bits per entry, palette of air and stone, 256 longs
+		assert_eq!(encoded[..6], [4, 2, 0, 1, 0x80, 0x02]);
+		assert_eq!(encoded[6..14], [0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x10]);
+		assert_eq!(encoded[14..22], [0x11; 8]);
+		assert_eq!(encoded.len(), 6 + 256 * 8);
+	}
+
+	#[test]
+	pub fn direct_biomes() {
+		let entries: Vec<i32> = (0..64).map(|idx| idx % 10).collect();
+		let encoded = encode_to_vec(&PalettedContainer::biomes(&entries, 15)).unwrap();
+		// 4 bits per entry since there are 15 biomes, 4 longs
+		assert_eq!(encoded[..2], [4, 4]);
+		assert_eq!(encoded[2..10], [0x54, 0x32, 0x10, 0x98, 0x76, 0x54, 0x32, 0x10]);
+		assert_eq!(encoded.len(), 2 + 4 * 8);
+	}
+
+	#[test]
+	pub fn heightmaps() {
+		let packed = pack_entries(&[1; 256], 9);
+		// 7 entries per long
+		assert_eq!(packed.len(), 37);
+		assert_eq!(packed[0], 0x0040_2010_0804_0201);
+		assert_eq!(packed[36], 0x0000_0000_0804_0201);
+		assert_eq!(unpack_entries(&packed, 9, 256), [1; 256]);
+
+		let encoded = encode_to_vec(&HeightMaps::new(&[1; 256], &[2; 256], 384)).unwrap();
+		let decoded = nbt::Blob::from_reader(&mut encoded.as_slice()).unwrap();
+		let expected: Vec<i64> = packed.iter().map(|&long| long as i64).collect();
+		assert_eq!(decoded["MOTION_BLOCKING"], nbt::Value::LongArray(expected));
+		assert!(matches!(&decoded["WORLD_SURFACE"], nbt::Value::LongArray(longs) if longs[0] == 0x0080_4020_1008_0402));
+	}
+
+	#[test]
+	pub fn update_chunk_data() {
+		// the same column as the fixture
+		let section = |block_count, block_states: &[i32], biomes: &[i32]| Section {
+			block_count,
+			block_states: PalettedContainer::block_states(block_states),
+			biomes: PalettedContainer::biomes(biomes, 17),
+		};
+		let mut grass_on_stone: Vec<i32> = (0..4096).map(|idx| if idx / 256 == 15 { 9 } else { 1 }).collect();
+		grass_on_stone[15 * 256] = 0;
+		let plains_under_forest: Vec<i32> = (0..64).map(|idx| if idx / 16 < 2 { 1 } else { 5 }).collect();
+		let mut sections = vec![
+			section(4096, &[1; 4096], &[1; 64]),
+			section(4095, &grass_on_stone, &plains_under_forest),
+			section(4096, &(0..4096).map(|idx| 1 + idx % 20).collect::<Vec<_>>(), &(0..64).map(|idx| 1 + idx % 5).collect::<Vec<_>>()),
+			section(4096, &(0..4096).map(|idx| 1 + idx * 7 % 300).collect::<Vec<_>>(), &(0..64).map(|idx| idx % 9).collect::<Vec<_>>()),
+		];
+		sections.extend((4..24).map(|_| section(0, &[0; 4096], &[1; 64])));
+		let world_surface: Vec<i32> = (0..256).map(|idx| 63 + idx % 2).collect();
+		let packet = Send::UpdateChunkData(send::UpdateChunkData {
+			chunk_position: Position { x: 3, z: -2 },
+			height_maps: HeightMaps::new(&[64; 256], world_surface[..].try_into().unwrap(), 384),
+			chunk_blocks: Blocks(sections),
+			chunk_block_entities: BlockEntities(vec![BlockEntity {
+				x: 1,
+				y: -10,
+				z: 2,
+				kind: VarInt(1),
+				data: None,
+			}]),
+			common: LightUpdateCommon::new((0..26).map(|idx| (idx, (idx >= 5).then(|| vec![0xff; 2048]))).collect(), (0..26).map(|idx| (idx, None)).collect()),
+		});
+		let encoded = encode_to_vec(&packet).unwrap();
+
+		// the ID and position
+		assert_eq!(encoded[..9], UPDATE_CHUNK_DATA[..9]);
+		// the heightmaps are in hash map order on both sides, so only their contents can be compared
+		let (mut ours, mut expected) = (&encoded[9..], &UPDATE_CHUNK_DATA[9..]);
+		assert_eq!(nbt::Blob::from_reader(&mut ours).unwrap(), nbt::Blob::from_reader(&mut expected).unwrap());
+		// the sections, block entities, and light
+		assert_eq!(ours.iter().zip(expected).position(|(ours, expected)| ours != expected), None);
+		assert_eq!(ours.len(), expected.len());
+	}
+
+	#[test]
+	pub fn block_entity() {
+		let entity = BlockEntity {
+			x: 1,
+			y: -5,
+			z: 2,
+			kind: VarInt(7),
+			data: None,
+		};
+		assert_eq!(encode_to_vec(&entity).unwrap(), [0x12, 0xff, 0xfb, 0x07, 0x00]);
+	}
+}
@@ -1,4 +1,5 @@
 use super::biome::BiomeRegistry;
+use encde::{Decode, Encode};
 use serde::{Deserialize, Serialize};
 
 #[derive(Serialize, Deserialize)]
@@ -9,6 +10,15 @@ pub struct Codec {
 	biomes: BiomeRegistry,
 }
 
+impl Codec {
+	pub fn dimension_type(&self, name: &str) -> Option<&Type> {
+		self.dimension_types.0.iter().find(|entry| entry.name == name).map(|entry| &entry.element)
+	}
+	pub fn biomes(&self) -> &BiomeRegistry {
+		&self.biomes
+	}
+}
+
 pub struct TypeRegistry(Vec<TypeEntry>);
 
 #[derive(Serialize)]
@@ -67,3 +77,37 @@ pub struct Type {
 	ultra_warm: bool,
 	has_ceiling: bool,
 }
+
+impl Type {
+	pub fn min_y(&self) -> i32 {
+		self.min_y
+	}
+	pub fn height(&self) -> i32 {
+		self.height
+	}
+}
+
+/// What the client is told about the world seed, which it uses to blend biome colors. The real seed stays on the server.
+#[derive(Encode, Decode, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HashedSeed(pub i64);
+
+impl HashedSeed {
+	/// The first eight bytes of the SHA-256 hash of the seed, both little-endian, like vanilla
+	pub fn from_seed(seed: i64) -> Self {
+		let hash = openssl::sha::sha256(&seed.to_le_bytes());
+		// PANICS: the hash is 32 bytes long
+		Self(i64::from_le_bytes(hash[..8].try_into().unwrap()))
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::HashedSeed;
+
+	#[test]
+	pub fn hashed_seed() {
+		// SHA-256 of eight zero bytes starts with af 55 70 f5 a1 81 0b 7a
+		assert_eq!(HashedSeed::from_seed(0), HashedSeed(0x7a0b_81a1_f570_55af));
+		assert_ne!(HashedSeed::from_seed(1), HashedSeed::from_seed(0));
+	}
+}
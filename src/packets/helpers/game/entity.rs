@@ -3,9 +3,7 @@ use super::entity;
 use super::slot::Slot;
 use encde::Encode;
 
-// TODO implement
-// #[derive(Encode)]
-pub struct Metadata(());
+pub use super::metadata::Metadata;
 
 #[derive(Encode)]
 pub struct Property {
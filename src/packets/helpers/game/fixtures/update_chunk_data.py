@@ -0,0 +1,156 @@
+#!/usr/bin/env python3
+"""Writes update_chunk_data.bin, a 1.18.1 (protocol 757) Chunk Data and Update Light packet, for the tests in chunk.rs.
+
+This is a cross-check, not vanilla's own output: nothing here was captured from a server. The packet is assembled by a second,
+separate reading of the 1.18.1 writers (ClientboundLevelChunkWithLightPacket, ClientboundLevelChunkPacketData, PalettedContainer,
+SimpleBitStorage, ClientboundLightUpdatePacketData, BitSet.toLongArray, and NbtIo.write of a HashMap-backed CompoundTag) that
+shares no code with the Rust encoder, so it catches mistakes that the two readings don't have in common. Bytes captured from a
+real server should replace update_chunk_data.bin when they're available. Palettes are in order of first appearance, as they are
+for a section that is filled in index order.
+"""
+import struct
+from pathlib import Path
+
+MIN_Y = -64
+NUM_SECTIONS = 24
+# the size of the block state registry and of the biome registry in assets/dimension_codec.json
+BLOCK_STATES = 20342
+BIOMES = 17
+
+
+def varint(value):
+	value &= 0xFFFFFFFF
+	out = bytearray()
+	while True:
+		byte = value & 0x7F
+		value >>= 7
+		if value:
+			out.append(byte | 0x80)
+		else:
+			out.append(byte)
+			return bytes(out)
+
+
+def ceillog2(value):
+	"""Mth.ceillog2"""
+	return (value - 1).bit_length()
+
+
+def longs(values):
+	return b"".join(struct.pack(">q", value - (1 << 64) if value >= 1 << 63 else value) for value in values)
+
+
+def bit_storage(bits, entries):
+	"""SimpleBitStorage: entries never straddle two longs, and the first entry is in the lowest bits"""
+	per_long = 64 // bits
+	data = [0] * ((len(entries) + per_long - 1) // per_long)
+	for idx, entry in enumerate(entries):
+		data[idx // per_long] |= entry << (idx % per_long * bits)
+	return data
+
+
+def paletted_container(entries, strategy):
+	palette = []
+	for entry in entries:
+		if entry not in palette:
+			palette.append(entry)
+	bits = ceillog2(len(palette))
+	if strategy == "blocks":
+		# SECTION_STATES: single value, linear with 4 bits, hash map with 5 to 8 bits, then the global palette
+		kind, bits = ("single", 0) if bits == 0 else ("linear", 4) if bits <= 4 else ("hashmap", bits) if bits <= 8 else ("global", ceillog2(BLOCK_STATES))
+	else:
+		# SECTION_BIOMES: single value, linear with 1 to 3 bits, then the global palette
+		kind, bits = ("single", 0) if bits == 0 else ("linear", bits) if bits <= 3 else ("global", ceillog2(BIOMES))
+	out = bytes([bits])
+	if kind == "single":
+		return out + varint(palette[0]) + varint(0)
+	if kind == "global":
+		data = bit_storage(bits, entries)
+	else:
+		out += varint(len(palette)) + b"".join(varint(entry) for entry in palette)
+		data = bit_storage(bits, [palette.index(entry) for entry in entries])
+	return out + varint(len(data)) + longs(data)
+
+
+def java_string_hash(text):
+	value = 0
+	for char in text:
+		value = (31 * value + ord(char)) & 0xFFFFFFFF
+	return value
+
+
+def hash_map_order(keys):
+	"""The iteration order of a java.util.HashMap with the default capacity of 16"""
+	def bucket(key):
+		value = java_string_hash(key)
+		return (value ^ (value >> 16)) & 15
+	return sorted(keys, key=bucket)
+
+
+def nbt_string(text):
+	data = text.encode("utf-8")
+	return struct.pack(">H", len(data)) + data
+
+
+def heightmaps_nbt(heightmaps):
+	"""NbtIo.write: the root compound has an empty name"""
+	out = b"\x0a" + nbt_string("")
+	for key in hash_map_order(heightmaps):
+		data = bit_storage(ceillog2(NUM_SECTIONS * 16 + 1), heightmaps[key])
+		out += b"\x0c" + nbt_string(key) + struct.pack(">i", len(data)) + longs(data)
+	return out + b"\x00"
+
+
+def bit_set(indices, length):
+	"""BitSet.toLongArray, which leaves out trailing zero longs"""
+	data = [0] * ((length + 63) // 64)
+	for idx in indices:
+		data[idx // 64] |= 1 << (idx % 64)
+	while data and data[-1] == 0:
+		data.pop()
+	return varint(len(data)) + longs(data)
+
+
+def section(block_states, biomes):
+	count = sum(1 for state in block_states if state != 0)
+	return struct.pack(">h", count) + paletted_container(block_states, "blocks") + paletted_container(biomes, "biomes")
+
+
+def column_sections():
+	stone, grass, air, plains, forest = 1, 9, 0, 1, 5
+	sections = []
+	# a single value for both
+	sections.append(section([stone] * 4096, [plains] * 64))
+	# 3 block states with 4 bits, and 2 biomes with 1 bit: grass on top of stone, with air in one corner, and forest above plains
+	states = [grass if idx // 256 == 15 else stone for idx in range(4096)]
+	states[15 * 256] = air
+	sections.append(section(states, [plains if idx // 16 < 2 else forest for idx in range(64)]))
+	# 20 block states with 5 bits, and 5 biomes with 3 bits
+	sections.append(section([1 + idx % 20 for idx in range(4096)], [1 + idx % 5 for idx in range(64)]))
+	# 300 block states and 9 biomes, which both need the global palette
+	sections.append(section([1 + idx * 7 % 300 for idx in range(4096)], [idx % 9 for idx in range(64)]))
+	for _ in range(4, NUM_SECTIONS):
+		sections.append(section([air] * 4096, [plains] * 64))
+	return b"".join(sections)
+
+
+def packet():
+	out = varint(0x22) + struct.pack(">ii", 3, -2)
+	# the fourth section is full, so every column is 64 blocks high
+	out += heightmaps_nbt({"MOTION_BLOCKING": [64] * 256, "WORLD_SURFACE": [63 + idx % 2 for idx in range(256)]})
+	data = column_sections()
+	out += varint(len(data)) + data
+	# one chest (block entity type 1) at (1, -10, 2) in the chunk, with no data
+	out += varint(1) + bytes([(1 << 4) | 2]) + struct.pack(">h", -10) + varint(1) + b"\x00"
+	# sky light in the sections from the surface up, none in the stone, and no block light at all
+	light_sections = NUM_SECTIONS + 2
+	lit = range(5, light_sections)
+	dark = range(0, 5)
+	out += b"\x01"
+	out += bit_set(lit, light_sections) + bit_set([], light_sections) + bit_set(dark, light_sections) + bit_set(range(light_sections), light_sections)
+	out += varint(len(lit)) + b"".join(varint(2048) + bytes([0xFF]) * 2048 for _ in lit)
+	out += varint(0)
+	return out
+
+
+Path(__file__).with_name("update_chunk_data.bin").write_bytes(packet())
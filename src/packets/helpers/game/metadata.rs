@@ -0,0 +1,384 @@
+use super::super::misc::BlockFace;
+use super::super::position::{F32Position, PackedPosition};
+use super::super::varint::VarInt;
+use super::super::wrappers::nbt::OptionalNbt;
+use super::super::wrappers::std::{PrefixedOption, PrefixedString};
+use super::super::wrappers::uuid::Uuid;
+use super::chat::Chat;
+use super::particle::Particle;
+use super::slot::Slot;
+use encde::{Encode, Result as EResult};
+use std::io::Write;
+
+/// Marks the end of the entries
+const END: u8 = 0xff;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(i32)]
+pub enum Pose {
+	Standing = 0,
+	FallFlying = 1,
+	Sleeping = 2,
+	Swimming = 3,
+	SpinAttack = 4,
+	Crouching = 5,
+	LongJumping = 6,
+	Dying = 7,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct VillagerData {
+	/// The biome the villager is from, like desert (0) or plains (2)
+	pub kind: VarInt,
+	pub profession: VarInt,
+	/// From 1 to 5
+	pub level: VarInt,
+}
+
+/// The value of a metadata entry, which is sent along with its type
+pub enum Value {
+	Byte(i8),
+	VarInt(VarInt),
+	Float(f32),
+	String(PrefixedString),
+	Chat(Chat),
+	OptionalChat(PrefixedOption<Chat>),
+	Slot(Slot),
+	Boolean(bool),
+	/// Degrees around each axis
+	Rotation(F32Position),
+	Position(PackedPosition),
+	OptionalPosition(PrefixedOption<PackedPosition>),
+	Direction(BlockFace),
+	OptionalUuid(PrefixedOption<Uuid>),
+	/// A block state, where air means no block
+	BlockState(VarInt),
+	Nbt(OptionalNbt),
+	Particle(Particle),
+	VillagerData(VillagerData),
+	OptionalVarInt(Option<i32>),
+	Pose(Pose),
+}
+
+impl Value {
+	fn type_id(&self) -> i32 {
+		match self {
+			Self::Byte(_) => 0,
+			Self::VarInt(_) => 1,
+			Self::Float(_) => 2,
+			Self::String(_) => 3,
+			Self::Chat(_) => 4,
+			Self::OptionalChat(_) => 5,
+			Self::Slot(_) => 6,
+			Self::Boolean(_) => 7,
+			Self::Rotation(_) => 8,
+			Self::Position(_) => 9,
+			Self::OptionalPosition(_) => 10,
+			Self::Direction(_) => 11,
+			Self::OptionalUuid(_) => 12,
+			Self::BlockState(_) => 13,
+			Self::Nbt(_) => 14,
+			Self::Particle(_) => 15,
+			Self::VillagerData(_) => 16,
+			Self::OptionalVarInt(_) => 17,
+			Self::Pose(_) => 18,
+		}
+	}
+}
+
+impl Encode for Value {
+	fn encode(&self, writer: &mut dyn Write) -> EResult<()> {
+		VarInt(self.type_id()).encode(writer)?;
+		match self {
+			Self::Byte(value) => value.encode(writer),
+			Self::VarInt(value) | Self::BlockState(value) => value.encode(writer),
+			Self::Float(value) => value.encode(writer),
+			Self::String(value) => value.encode(writer),
+			Self::Chat(value) => value.encode(writer),
+			Self::OptionalChat(value) => value.encode(writer),
+			Self::Slot(value) => value.encode(writer),
+			Self::Boolean(value) => value.encode(writer),
+			Self::Rotation(value) => value.encode(writer),
+			Self::Position(value) => value.encode(writer),
+			Self::OptionalPosition(value) => value.encode(writer),
+			Self::Direction(value) => VarInt(*value as i32).encode(writer),
+			Self::OptionalUuid(value) => value.encode(writer),
+			Self::Nbt(value) => value.encode(writer),
+			Self::Particle(value) => value.encode(writer),
+			Self::VillagerData(value) => {
+				value.kind.encode(writer)?;
+				value.profession.encode(writer)?;
+				value.level.encode(writer)
+			}
+			// 0 is absent, so everything else is shifted up by one
+			Self::OptionalVarInt(value) => VarInt(value.map_or(0, |value| value + 1)).encode(writer),
+			Self::Pose(value) => VarInt(*value as i32).encode(writer),
+		}
+	}
+}
+
+/// Entries keyed by index, whose meaning depends on the entity's class. Only entries that have changed need to be sent.
+#[derive(Default)]
+pub struct Metadata(pub Vec<(u8, Value)>);
+
+impl Metadata {
+	/// Replaces the entry if it's already set
+	pub fn set(&mut self, index: u8, value: Value) {
+		assert!(index != END, "Metadata index {} is reserved", END);
+		match self.0.iter_mut().find(|(existing, _)| *existing == index) {
+			Some((_, existing)) => *existing = value,
+			None => self.0.push((index, value)),
+		}
+	}
+	pub fn is_empty(&self) -> bool {
+		self.0.is_empty()
+	}
+}
+
+impl Encode for Metadata {
+	fn encode(&self, writer: &mut dyn Write) -> EResult<()> {
+		for (index, value) in &self.0 {
+			index.encode(writer)?;
+			value.encode(writer)?;
+		}
+		END.encode(writer)
+	}
+}
+
+/// Bits of the flags that every entity has
+pub mod flags {
+	pub const ON_FIRE: u8 = 0x01;
+	pub const CROUCHING: u8 = 0x02;
+	pub const SPRINTING: u8 = 0x08;
+	pub const SWIMMING: u8 = 0x10;
+	pub const INVISIBLE: u8 = 0x20;
+	pub const GLOWING: u8 = 0x40;
+	pub const FLYING_WITH_ELYTRA: u8 = 0x80;
+}
+
+/// Bits of a player's displayed skin parts
+pub mod skin_parts {
+	pub const CAPE: u8 = 0x01;
+	pub const JACKET: u8 = 0x02;
+	pub const LEFT_SLEEVE: u8 = 0x04;
+	pub const RIGHT_SLEEVE: u8 = 0x08;
+	pub const LEFT_PANTS: u8 = 0x10;
+	pub const RIGHT_PANTS: u8 = 0x20;
+	pub const HAT: u8 = 0x40;
+	pub const ALL: u8 = 0x7f;
+}
+
+/// A builder for a class of entity, which knows the indices of that class's entries
+pub trait Builder: Sized {
+	fn metadata(&mut self) -> &mut Metadata;
+	fn build(self) -> Metadata;
+}
+
+/// Entries that every entity has
+pub trait Entity: Builder {
+	/// See `flags`
+	fn flags(mut self, flags: u8) -> Self {
+		self.metadata().set(0, Value::Byte(flags as i8));
+		self
+	}
+	fn air_ticks(mut self, ticks: i32) -> Self {
+		self.metadata().set(1, Value::VarInt(VarInt(ticks)));
+		self
+	}
+	fn custom_name(mut self, name: Option<Chat>) -> Self {
+		self.metadata().set(2, Value::OptionalChat(PrefixedOption(name)));
+		self
+	}
+	fn custom_name_visible(mut self, visible: bool) -> Self {
+		self.metadata().set(3, Value::Boolean(visible));
+		self
+	}
+	fn silent(mut self, silent: bool) -> Self {
+		self.metadata().set(4, Value::Boolean(silent));
+		self
+	}
+	fn no_gravity(mut self, no_gravity: bool) -> Self {
+		self.metadata().set(5, Value::Boolean(no_gravity));
+		self
+	}
+	fn pose(mut self, pose: Pose) -> Self {
+		self.metadata().set(6, Value::Pose(pose));
+		self
+	}
+	fn ticks_frozen(mut self, ticks: i32) -> Self {
+		self.metadata().set(7, Value::VarInt(VarInt(ticks)));
+		self
+	}
+}
+
+/// Entries that mobs, players, and armor stands have
+pub trait LivingEntity: Entity {
+	/// Bit 0x01 is whether a hand is active, and bit 0x02 is whether it's the offhand
+	fn hand_states(mut self, states: u8) -> Self {
+		self.metadata().set(8, Value::Byte(states as i8));
+		self
+	}
+	fn health(mut self, health: f32) -> Self {
+		self.metadata().set(9, Value::Float(health));
+		self
+	}
+	/// 0 for no potion effects
+	fn potion_effect_color(mut self, color: i32) -> Self {
+		self.metadata().set(10, Value::VarInt(VarInt(color)));
+		self
+	}
+	fn potion_effect_ambient(mut self, ambient: bool) -> Self {
+		self.metadata().set(11, Value::Boolean(ambient));
+		self
+	}
+	/// Stuck in the entity
+	fn arrows(mut self, count: i32) -> Self {
+		self.metadata().set(12, Value::VarInt(VarInt(count)));
+		self
+	}
+	fn bee_stingers(mut self, count: i32) -> Self {
+		self.metadata().set(13, Value::VarInt(VarInt(count)));
+		self
+	}
+	fn bed(mut self, position: Option<PackedPosition>) -> Self {
+		self.metadata().set(14, Value::OptionalPosition(PrefixedOption(position)));
+		self
+	}
+}
+
+macro_rules! builder {
+	($(#[$attr:meta])* $name:ident) => {
+		$(#[$attr])*
+		#[derive(Default)]
+		pub struct $name(Metadata);
+
+		impl Builder for $name {
+			fn metadata(&mut self) -> &mut Metadata {
+				&mut self.0
+			}
+			fn build(self) -> Metadata {
+				self.0
+			}
+		}
+	};
+}
+
+builder!(
+	/// Any entity, using only the entries that every entity has
+	Plain
+);
+impl Entity for Plain {}
+
+builder!(
+	/// A dropped item
+	ItemEntity
+);
+impl Entity for ItemEntity {}
+impl ItemEntity {
+	pub fn item(mut self, item: Slot) -> Self {
+		self.0.set(8, Value::Slot(item));
+		self
+	}
+}
+
+builder!(Player);
+impl Entity for Player {}
+impl LivingEntity for Player {}
+impl Player {
+	pub fn additional_hearts(mut self, hearts: f32) -> Self {
+		self.0.set(15, Value::Float(hearts));
+		self
+	}
+	pub fn score(mut self, score: i32) -> Self {
+		self.0.set(16, Value::VarInt(VarInt(score)));
+		self
+	}
+	/// See `skin_parts`
+	pub fn skin_parts(mut self, parts: u8) -> Self {
+		self.0.set(17, Value::Byte(parts as i8));
+		self
+	}
+	pub fn left_handed(mut self, left_handed: bool) -> Self {
+		self.0.set(18, Value::Byte(if left_handed { 0 } else { 1 }));
+		self
+	}
+	/// A parrot, or nothing
+	pub fn left_shoulder(mut self, entity: OptionalNbt) -> Self {
+		self.0.set(19, Value::Nbt(entity));
+		self
+	}
+	pub fn right_shoulder(mut self, entity: OptionalNbt) -> Self {
+		self.0.set(20, Value::Nbt(entity));
+		self
+	}
+}
+
+builder!(ArmorStand);
+impl Entity for ArmorStand {}
+impl LivingEntity for ArmorStand {}
+impl ArmorStand {
+	/// Bits are small (0x01), has arms (0x04), has no base plate (0x08), and marker (0x10)
+	pub fn armor_stand_flags(mut self, flags: u8) -> Self {
+		self.0.set(15, Value::Byte(flags as i8));
+		self
+	}
+	pub fn head_rotation(mut self, rotation: F32Position) -> Self {
+		self.0.set(16, Value::Rotation(rotation));
+		self
+	}
+	pub fn body_rotation(mut self, rotation: F32Position) -> Self {
+		self.0.set(17, Value::Rotation(rotation));
+		self
+	}
+}
+
+builder!(
+	/// Any mob, like a zombie or a cow, using only the entries that every mob has
+	Mob
+);
+impl Entity for Mob {}
+impl LivingEntity for Mob {}
+impl Mob {
+	/// Bits are no AI (0x01), left handed (0x02), and aggressive (0x04)
+	pub fn mob_flags(mut self, flags: u8) -> Self {
+		self.0.set(15, Value::Byte(flags as i8));
+		self
+	}
+}
+
+builder!(Villager);
+impl Entity for Villager {}
+impl LivingEntity for Villager {}
+impl Villager {
+	pub fn villager_data(mut self, data: VillagerData) -> Self {
+		self.0.set(18, Value::VillagerData(data));
+		self
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::{flags, Entity, LivingEntity, Player, Pose, Value};
+	use crate::packets::helpers::game::metadata::Builder;
+	use crate::packets::helpers::varint::VarInt;
+	use encde::Encode;
+
+	#[test]
+	pub fn encoding() {
+		let mut metadata = Player::default().flags(flags::CROUCHING).pose(Pose::Crouching).health(20.0).build();
+		metadata.set(0, Value::Byte(0));
+		metadata.set(17, Value::OptionalVarInt(None));
+		metadata.set(1, Value::VarInt(VarInt(300)));
+		let mut encoded = Vec::new();
+		metadata.encode(&mut encoded).unwrap();
+		#[rustfmt::skip]
+		assert_eq!(encoded, [
+			0, 0, 0,
+			6, 18, 5,
+			9, 2, 0x41, 0xa0, 0, 0,
+			17, 17, 0,
+			1, 1, 0xac, 0x02,
+			0xff,
+		]);
+	}
+}
@@ -5,6 +5,8 @@ pub mod chunk;
 pub mod dimension;
 pub mod entity;
 pub mod map;
+pub mod metadata;
+pub mod particle;
 pub mod player_list;
 pub mod recipes;
 pub mod scoreboard;
@@ -0,0 +1,89 @@
+use super::super::position::{F64Position, PackedPosition};
+use super::super::varint::VarInt;
+use super::super::wrappers::std::PrefixedString;
+use super::slot::Slot;
+use encde::{Encode, Result as EResult};
+use std::io::Write;
+
+/// Where a vibration particle travels to
+pub enum VibrationDestination {
+	Block(PackedPosition),
+	Entity(VarInt),
+}
+
+/// A particle type and its options
+pub enum Particle {
+	/// Any particle without options, by ID
+	Simple(VarInt),
+	/// A block state
+	Block(VarInt),
+	/// A block state
+	BlockMarker(VarInt),
+	/// The color components are from 0 to 1, and the scale is from 0.01 to 4
+	Dust {
+		red: f32,
+		green: f32,
+		blue: f32,
+		scale: f32,
+	},
+	DustColorTransition {
+		from: [f32; 3],
+		scale: f32,
+		to: [f32; 3],
+	},
+	/// A block state
+	FallingDust(VarInt),
+	Item(Slot),
+	Vibration {
+		origin: F64Position,
+		destination: VibrationDestination,
+		/// How long it takes to reach the destination
+		ticks: VarInt,
+	},
+}
+
+impl Particle {
+	pub fn id(&self) -> i32 {
+		match self {
+			Self::Simple(id) => id.0,
+			Self::Block(_) => 2,
+			Self::BlockMarker(_) => 3,
+			Self::Dust { .. } => 14,
+			Self::DustColorTransition { .. } => 15,
+			Self::FallingDust(_) => 24,
+			Self::Item(_) => 35,
+			Self::Vibration { .. } => 36,
+		}
+	}
+}
+
+impl Encode for Particle {
+	fn encode(&self, writer: &mut dyn Write) -> EResult<()> {
+		VarInt(self.id()).encode(writer)?;
+		match self {
+			Self::Simple(_) => Ok(()),
+			Self::Block(state) | Self::BlockMarker(state) | Self::FallingDust(state) => state.encode(writer),
+			Self::Dust { red, green, blue, scale } => [*red, *green, *blue, *scale].encode(writer),
+			Self::DustColorTransition { from, scale, to } => {
+				from.encode(writer)?;
+				scale.encode(writer)?;
+				to.encode(writer)
+			}
+			Self::Item(item) => item.encode(writer),
+			Self::Vibration { origin, destination, ticks } => {
+				origin.encode(writer)?;
+				match destination {
+					VibrationDestination::Block(position) => {
+						PrefixedString("minecraft:block".to_owned()).encode(writer)?;
+						position.encode(writer)?;
+					}
+					VibrationDestination::Entity(entity_id) => {
+						PrefixedString("minecraft:entity".to_owned()).encode(writer)?;
+						entity_id.encode(writer)?;
+					}
+				}
+				ticks.encode(writer)
+			}
+		}
+	}
+}
@@ -6,31 +6,43 @@ use encde::Encode;
 
 #[derive(Encode)]
 pub struct AddPlayer {
-	player_uuid: Uuid,
+	pub player_uuid: Uuid,
 	/// Usually one item named "textures" with the profile, skin, and cape data from the Mojang API as Base-64 JSON
-	properties: PrefixedVec<AddPlayerProperty>,
-	gamemode: GameMode,
-	ping: VarInt,
-	display_name: PrefixedOption<Chat>,
+	pub properties: PrefixedVec<AddPlayerProperty>,
+	pub gamemode: GameMode,
+	pub ping: VarInt,
+	pub display_name: PrefixedOption<Chat>,
 }
 
 #[derive(Encode)]
 pub struct AddPlayerProperty {
 	name: PrefixedString,
 	value: PrefixedString,
-	signature: PrefixedOption<PrefixedBytes>,
+	/// Base-64-encoded, as received from the session server
+	signature: PrefixedOption<PrefixedString>,
+}
+
+impl AddPlayerProperty {
+	pub fn new(name: String, value: String, signature: Option<String>) -> Self {
+		Self {
+			name: PrefixedString(name),
+			value: PrefixedString(value),
+			signature: PrefixedOption(signature.map(PrefixedString)),
+		}
+	}
 }
 
 #[derive(Encode)]
 pub struct UpdateGamemode {
-	player_uuid: Uuid,
-	new_gamemode: GameMode,
+	pub player_uuid: Uuid,
+	pub new_gamemode: GameMode,
 }
 
 #[derive(Encode)]
 pub struct UpdateLatency {
-	player_uuid: Uuid,
-	ping: VarInt,
+	pub player_uuid: Uuid,
+	/// In milliseconds
+	pub ping: VarInt,
 }
 
 #[derive(Encode)]
@@ -41,5 +53,5 @@ pub struct UpdateDisplayName {
 
 #[derive(Encode)]
 pub struct RemovePlayer {
-	player_uuid: Uuid,
+	pub player_uuid: Uuid,
 }
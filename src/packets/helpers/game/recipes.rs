@@ -12,13 +12,13 @@ pub enum UnlockAction {
 
 #[derive(Encode)]
 pub struct BookState {
-	open: bool,
-	filter_active: bool,
+	pub open: bool,
+	pub filter_active: bool,
 }
 
 pub struct Recipe {
-	id: PrefixedString,
-	data: Type,
+	pub id: PrefixedString,
+	pub data: Type,
 }
 
 pub enum Type {
@@ -67,11 +67,11 @@ pub enum Type {
 
 #[derive(Encode)]
 pub struct SmeltingRecipe {
-	group: PrefixedString,
-	ingredient: Ingredient,
-	result: Slot,
-	experience: f32,
-	cooking_time: VarInt,
+	pub group: PrefixedString,
+	pub ingredient: Ingredient,
+	pub result: Slot,
+	pub experience: f32,
+	pub cooking_time: VarInt,
 }
 
 pub type Ingredient = PrefixedVec<Slot>;
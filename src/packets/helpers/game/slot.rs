@@ -1,18 +1,18 @@
 use super::super::varint::VarInt;
-use super::super::wrappers::{nbt::NbtBlob, std::PrefixedOption};
+use super::super::wrappers::{nbt::OptionalNbt, std::PrefixedOption};
 use encde::{Decode, Encode};
 
 pub type Slot = PrefixedOption<PresentSlot>;
 
 #[derive(Encode, Decode)]
 pub struct IndexedSlot {
-	slot_index: i16,
-	slot_data: Slot,
+	pub slot_index: i16,
+	pub slot_data: Slot,
 }
 
-#[derive(Encode, Decode)]
+#[derive(Encode, Decode, Debug, Clone, PartialEq)]
 pub struct PresentSlot {
-	item_id: VarInt,
-	count: i8,
-	nbt_data: NbtBlob,
+	pub item_id: VarInt,
+	pub count: i8,
+	pub nbt_data: OptionalNbt,
 }
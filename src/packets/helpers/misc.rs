@@ -137,7 +137,7 @@ pub enum ServerDifficulty {
 	Hard = 3,
 }
 
-#[derive(Encode, Decode)]
+#[derive(Encode, Decode, Debug, Clone, Copy, PartialEq, Eq)]
 #[repr(u8)]
 pub enum GameMode {
 	Survival = 0,
@@ -184,7 +184,7 @@ pub struct PlayerRotationTargetEntity {
 	use_eyes: bool,
 }
 
-#[derive(Encode, Decode)]
+#[derive(Encode, Decode, Clone, Copy, Debug, PartialEq, Eq)]
 #[repr(u8)]
 pub enum BlockFace {
 	/// -Y
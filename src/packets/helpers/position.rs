@@ -1,7 +1,7 @@
 use encde::{Decode, Encode, Result as EResult};
 use std::io::{Read, Write};
 
-#[derive(Encode, Decode)]
+#[derive(Encode, Decode, Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub struct UnpackedPosition<T> {
 	pub x: T,
 	pub y: T,
@@ -11,6 +11,7 @@ pub type F32Position = UnpackedPosition<f32>;
 pub type F64Position = UnpackedPosition<f64>;
 
 // XXX is it better to store the position as packed or unpacked?
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub struct PackedPosition {
 	x: i32,
 	y: i16,
@@ -20,23 +21,33 @@ impl PackedPosition {
 	pub fn new(x: i32, y: i16, z: i32) -> Self {
 		Self { x, y, z }
 	}
+	pub fn x(&self) -> i32 {
+		self.x
+	}
+	pub fn y(&self) -> i16 {
+		self.y
+	}
+	pub fn z(&self) -> i32 {
+		self.z
+	}
 }
 impl Encode for PackedPosition {
 	fn encode(&self, writer: &mut dyn Write) -> EResult<()> {
 		// 26 MSBs = x
 		// 26 middle bits = z
 		// 12 LSBs = y
-		let out = (((self.x as u32 & 0x3ffffff) as u64) << 38) | (((self.z as u32 & 0x3fffff) as u64) << 12) | ((self.y as u16 & 0xfff) as u64);
+		let out = (((self.x as u32 & 0x3ffffff) as u64) << 38) | (((self.z as u32 & 0x3ffffff) as u64) << 12) | ((self.y as u16 & 0xfff) as u64);
 		out.encode(writer)
 	}
 }
 impl Decode for PackedPosition {
 	fn decode(reader: &mut dyn Read) -> EResult<Self> {
-		let raw = u64::decode(reader)?;
+		let raw = i64::decode(reader)?;
+		// shifting left then right sign-extends each field
 		Ok(Self {
 			x: (raw >> 38) as i32,
-			y: (raw & 0xfff) as i16,
-			z: ((raw >> 12) & 0x3ffffff) as i32,
+			y: ((raw << 52) >> 52) as i16,
+			z: ((raw << 26) >> 38) as i32,
 		})
 	}
 }
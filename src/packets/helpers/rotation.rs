@@ -1,7 +1,7 @@
 use encde::{Decode, Encode};
 
 /// The angle is encoded in 256th-turns
-#[derive(Encode, Decode)]
+#[derive(Encode, Decode, Clone, Copy, Debug, PartialEq, Eq)]
 pub struct Angle(u8);
 
 impl Angle {
@@ -16,16 +16,35 @@ impl Angle {
 		let ratio = Self::UNITS_PER_TURN / Self::DEGREES_PER_TURN;
 		let converted = degrees * ratio;
 		let normalized = converted.rem_euclid(Self::UNITS_PER_TURN);
-		debug_assert!(normalized.is_finite() && normalized > 0.0 && normalized < Self::UNITS_PER_TURN);
-		Self(unsafe { normalized.to_int_unchecked() })
+		// `rem_euclid` can round up to exactly one turn, which saturates to the last unit
+		debug_assert!((0.0..=Self::UNITS_PER_TURN).contains(&normalized));
+		Self(normalized as u8)
 	}
 }
 
-#[derive(Encode, Decode)]
+/// Yaw comes first on the wire
+#[derive(Encode, Decode, Clone, Copy, Debug, PartialEq)]
 pub struct Rotation<T: Encode + Decode> {
-	pub pitch: T,
 	pub yaw: T,
+	pub pitch: T,
 }
 
 pub type AngleRotation = Rotation<Angle>;
 pub type F32Rotation = Rotation<f32>;
+
+#[cfg(test)]
+mod test {
+	use super::{Angle, AngleRotation, F32Rotation};
+	use encde::util::{decode_from_entire_slice, encode_to_vec};
+
+	#[test]
+	pub fn wire_order() {
+		let decoded = decode_from_entire_slice::<F32Rotation>(&[0x42, 0xb4, 0, 0, 0xc2, 0x34, 0, 0]).unwrap();
+		assert_eq!(decoded, F32Rotation { yaw: 90.0, pitch: -45.0 });
+		let rotation = AngleRotation {
+			yaw: Angle::from_degrees(90.0),
+			pitch: Angle::from_degrees(-45.0),
+		};
+		assert_eq!(encode_to_vec(&rotation).unwrap(), [64, 224]);
+	}
+}
@@ -1,9 +1,13 @@
 use encde::{Decode, Encode, Result as EResult};
 use serde::Serialize;
+use std::collections::HashMap;
 use std::io::{Read, Write};
 
 pub struct NbtData<T>(pub T);
 pub struct NbtBlob(nbt::Blob);
+/// A nameless compound, or nothing at all, which is sent as a lone `TAG_End`
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct OptionalNbt(pub Option<HashMap<String, nbt::Value>>);
 
 impl Encode for NbtBlob {
 	fn encode(&self, mut writer: &mut dyn Write) -> EResult<()> {
@@ -17,6 +21,40 @@ impl Decode for NbtBlob {
 	}
 }
 
+const TAG_END: u8 = 0;
+const TAG_COMPOUND: u8 = 10;
+
+impl Encode for OptionalNbt {
+	fn encode(&self, mut writer: &mut dyn Write) -> EResult<()> {
+		match &self.0 {
+			Some(compound) => {
+				TAG_COMPOUND.encode(writer)?;
+				// the root's name, which is always empty
+				0u16.encode(writer)?;
+				nbt::Value::Compound(compound.clone()).to_writer(&mut writer).map_err(|err| encde::Error::Custom(Box::new(err)))
+			}
+			None => TAG_END.encode(writer),
+		}
+	}
+}
+
+impl Decode for OptionalNbt {
+	fn decode(mut reader: &mut dyn Read) -> EResult<Self> {
+		match u8::decode(reader)? {
+			TAG_END => Ok(Self(None)),
+			TAG_COMPOUND => {
+				let name_length = u16::decode(reader)?;
+				std::io::copy(&mut (&mut reader).take(name_length.into()), &mut std::io::sink())?;
+				match nbt::Value::from_reader(TAG_COMPOUND, &mut reader).map_err(|err| encde::Error::Custom(Box::new(err)))? {
+					nbt::Value::Compound(compound) => Ok(Self(Some(compound))),
+					other => Err(encde::Error::Custom(format!("Expected a compound but got NBT tag {}", other.id()).into())),
+				}
+			}
+			other => Err(encde::Error::Custom(format!("Expected a compound or nothing but got NBT tag {}", other).into())),
+		}
+	}
+}
+
 impl<T: Serialize> Encode for NbtData<T> {
 	fn encode(&self, writer: &mut dyn Write) -> EResult<()> {
 		nbt::to_writer(writer, &self.0, None).map_err(|err| encde::Error::Custom(Box::new(err)))
@@ -0,0 +1,53 @@
+//! The server list ping from before the Netty rewrite, which old clients and many monitoring tools still send
+use std::io::Write;
+
+/// The first byte of a legacy ping, which can't start a modern handshake
+pub const PING: u8 = 0xfe;
+/// The response is sent as if it were the reason for a kick
+const KICK: u8 = 0xff;
+/// Like vanilla, the protocol is reported as 127, which no legacy client speaks, so they show the version as incompatible instead of trying to join
+const PROTOCOL: i32 = 127;
+
+pub struct PingResponse<'a> {
+	pub version: &'a str,
+	pub motd: &'a str,
+	pub online: usize,
+	pub max: usize,
+}
+
+impl PingResponse<'_> {
+	/// The fields are separated by nulls and sent as UTF-16, prefixed with their length in code units
+	pub fn write(&self, writer: &mut dyn Write) -> std::io::Result<()> {
+		let text = format!("\u{a7}1\0{}\0{}\0{}\0{}\0{}", PROTOCOL, self.version, self.motd.replace('\0', ""), self.online, self.max);
+		let units: Vec<u16> = text.encode_utf16().collect();
+		let len = u16::try_from(units.len()).map_err(|_| std::io::Error::new(std::io::ErrorKind::InvalidInput, "Legacy ping response is too long"))?;
+		let mut response = vec![KICK];
+		response.extend_from_slice(&len.to_be_bytes());
+		for unit in units {
+			response.extend_from_slice(&unit.to_be_bytes());
+		}
+		writer.write_all(&response)
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::PingResponse;
+
+	#[test]
+	pub fn response() {
+		let mut written = Vec::new();
+		PingResponse {
+			version: "1.18.1",
+			motd: "Hi",
+			online: 3,
+			max: 20,
+		}
+		.write(&mut written)
+		.unwrap();
+		let text = "\u{a7}1\u{0}127\u{0}1.18.1\u{0}Hi\u{0}3\u{0}20";
+		assert_eq!(&written[..3], [0xff, 0, text.encode_utf16().count() as u8]);
+		let units: Vec<u8> = text.encode_utf16().flat_map(u16::to_be_bytes).collect();
+		assert_eq!(&written[3..], units);
+	}
+}
@@ -5,11 +5,10 @@ use crate::packets::helpers::wrappers::uuid::Uuid;
 use encde::Encode;
 
 /// This packet is optional and not sending it means to not compress.
-/// Thus, we don't need to send it until we've implemented compression.
 #[derive(Encode)]
 pub struct SetCompression {
-	/// Maximum packet size before it's compressed. If <= 0, disable compression.
-	threshold: VarInt,
+	/// Packets at least this long are compressed. If negative, disable compression.
+	pub threshold: VarInt,
 }
 
 // No LoginPluginRequest for now
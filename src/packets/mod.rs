@@ -5,7 +5,9 @@ pub mod play;
 pub mod status;
 
 pub mod cipher;
+pub mod framing;
 pub mod helpers;
+pub mod legacy;
 
 #[derive(encde::Encode, encde::Decode, Debug)]
 #[repr(u8)]
@@ -10,7 +10,7 @@ use helpers::wrappers::uuid::Uuid;
 
 #[derive(Decode)]
 pub struct ConfirmTeleport {
-	teleport_id: VarInt,
+	pub teleport_id: VarInt,
 }
 
 #[derive(Decode)]
@@ -35,7 +35,7 @@ pub enum RequestMisc {
 #[derive(Decode)]
 pub struct UpdateClientSettings {
 	locale: PrefixedString,
-	render_distance: i8,
+	pub render_distance: i8,
 	chat_mode: chat::ClientChatMode,
 	chat_colors: bool,
 	/// Bit flags (TODO custom type)
@@ -46,7 +46,7 @@ pub struct UpdateClientSettings {
 	/// 16 = left pants leg
 	/// 32 = right pants leg
 	/// 64 = hat
-	skin_parts: u8,
+	pub skin_parts: u8,
 	right_handed: bool,
 	/// Currently unused
 	text_filtering: bool,
@@ -61,32 +61,41 @@ pub struct TriggerTabComplete {
 
 #[derive(Decode)]
 pub struct ClickWindowButton {
-	window_id: i8,
-	button_id: i8,
+	pub window_id: i8,
+	pub button_id: i8,
 }
 
 #[derive(Decode)]
 pub struct ClickWindowSlot {
-	window_id: u8,
-	state_id: VarInt,
-	// TODO logic, enums, custom types
-	clicked_slot_index: i16,
-	button: i8,
-	mode: VarInt,
-	updated_slots: PrefixedVec<slot::IndexedSlot>,
-	clicked_slot_data: slot::Slot,
+	pub window_id: u8,
+	pub state_id: VarInt,
+	pub clicked_slot_index: i16,
+	pub button: i8,
+	pub mode: VarInt,
+	/// What the client thinks the click changed
+	pub updated_slots: PrefixedVec<slot::IndexedSlot>,
+	/// What the client thinks is on its cursor after the click
+	pub clicked_slot_data: slot::Slot,
 }
 
 #[derive(Decode)]
 pub struct CloseWindow {
-	window_id: u8,
+	pub window_id: u8,
 }
 
-// TODO something with DecodeSized
-// #[derive(Decode)]
 pub struct PluginMessage {
-	channel: PrefixedString,
-	data: UnprefixedBytes,
+	pub channel: PrefixedString,
+	pub data: UnprefixedBytes,
+}
+
+impl Decode for PluginMessage {
+	fn decode(reader: &mut dyn std::io::Read) -> encde::Result<Self> {
+		let channel = PrefixedString::decode(reader)?;
+		// the data extends to the end of the packet, which is fine since packets are decoded from their own buffers
+		let mut data = Vec::new();
+		reader.read_to_end(&mut data)?;
+		Ok(Self { channel, data: UnprefixedBytes(data) })
+	}
 }
 
 #[derive(Decode)]
@@ -117,7 +126,7 @@ pub struct GenerateStructure {
 }
 
 #[derive(Decode)]
-pub struct KeepAlive(i64);
+pub struct KeepAlive(pub i64);
 
 #[derive(Decode)]
 pub struct SetDifficultyLocked {
@@ -126,26 +135,26 @@ pub struct SetDifficultyLocked {
 
 #[derive(Decode)]
 pub struct MovePosition {
-	new_position: pos::F64Position,
-	on_ground: bool,
+	pub new_position: pos::F64Position,
+	pub on_ground: bool,
 }
 
 #[derive(Decode)]
 pub struct MoveRotation {
-	new_rotation: rot::F32Rotation,
-	on_ground: bool,
+	pub new_rotation: rot::F32Rotation,
+	pub on_ground: bool,
 }
 
 #[derive(Decode)]
 pub struct MovePosRot {
-	new_position: pos::F64Position,
-	new_rotation: rot::F32Rotation,
-	on_ground: bool,
+	pub new_position: pos::F64Position,
+	pub new_rotation: rot::F32Rotation,
+	pub on_ground: bool,
 }
 
 #[derive(Decode)]
 pub struct MoveStationary {
-	on_ground: bool,
+	pub on_ground: bool,
 }
 
 #[derive(Decode)]
@@ -167,9 +176,9 @@ pub struct PickItemFromInventory {
 
 #[derive(Decode)]
 pub struct SelectCraftRecipe {
-	window_id: i8,
-	recipe: PrefixedString,
-	make_all: bool,
+	pub window_id: i8,
+	pub recipe: PrefixedString,
+	pub make_all: bool,
 }
 
 #[derive(Decode)]
@@ -211,20 +220,20 @@ pub struct Pong(i32);
 
 #[derive(Decode)]
 pub struct SetRecipeBookState {
-	book_id: recipes::BookType,
-	book_open: bool,
-	filter_active: bool,
+	pub book_id: recipes::BookType,
+	pub book_open: bool,
+	pub filter_active: bool,
 }
 
 #[derive(Decode)]
 pub struct SetDisplayedRecipe {
-	recipe_id: PrefixedString,
+	pub recipe_id: PrefixedString,
 }
 
 /// Sent as the player types
 #[derive(Decode)]
 pub struct UpdateCustomItemName {
-	item_name: PrefixedString,
+	pub item_name: PrefixedString,
 }
 
 #[derive(Decode)]
@@ -254,7 +263,7 @@ pub struct SetBeaconEffect {
 }
 
 #[derive(Decode)]
-pub struct ChangeHeldItem(i16);
+pub struct ChangeHeldItem(pub i16);
 
 #[derive(Decode)]
 pub struct UpdateCommandBlock {
@@ -277,7 +286,7 @@ pub struct UpdateCommandBlockMinecart {
 
 /// Creative only
 #[derive(Decode)]
-pub struct CheatInventorySlot(slot::IndexedSlot);
+pub struct CheatInventorySlot(pub slot::IndexedSlot);
 
 #[derive(Decode)]
 pub struct UpdateJigsawBlock {
@@ -326,11 +335,11 @@ pub struct SpectateEntity(Uuid);
 
 #[derive(Decode)]
 pub struct PlaceBlock {
-	hand: misc::PlayerHand,
-	location: pos::PackedPosition,
-	face: misc::BlockFace,
-	cursor_position_within_block: pos::F32Position,
-	head_inside_block: bool,
+	pub hand: misc::PlayerHand,
+	pub location: pos::PackedPosition,
+	pub face: misc::BlockFace,
+	pub cursor_position_within_block: pos::F32Position,
+	pub head_inside_block: bool,
 }
 
 #[derive(Decode)]
@@ -359,8 +368,8 @@ pub enum Packet {
 	ClickWindowSlot(ClickWindowSlot),
 	#[encde(wire_tag = 0x09)]
 	CloseWindow(CloseWindow),
-	// #[encde(wire_tag = 0x0a)]
-	// PluginMessage(PluginMessage),
+	#[encde(wire_tag = 0x0a)]
+	PluginMessage(PluginMessage),
 	#[encde(wire_tag = 0x0b)]
 	EditBook(EditBook),
 	#[encde(wire_tag = 0x0c)]
@@ -12,14 +12,14 @@ use helpers::wrappers::uuid::Uuid;
 
 #[derive(Encode)]
 pub struct SpawnEntity {
-	entity_id: VarInt,
-	object_uuid: Uuid,
+	pub entity_id: VarInt,
+	pub object_uuid: Uuid,
 	/// TODO more specific type
-	entity_type: VarInt,
-	position: pos::F64Position,
-	rotation: rot::AngleRotation,
-	object_data: i32,
-	velocity: entity::Velocity,
+	pub entity_type: VarInt,
+	pub position: pos::F64Position,
+	pub rotation: rot::AngleRotation,
+	pub object_data: i32,
+	pub velocity: entity::Velocity,
 }
 
 #[derive(Encode)]
@@ -31,13 +31,13 @@ pub struct SpawnExperienceOrb {
 
 #[derive(Encode)]
 pub struct SpawnLivingEntity {
-	entity_id: VarInt,
-	entity_uuid: Uuid,
-	entity_type: VarInt,
-	position: pos::F64Position,
-	rotation: rot::AngleRotation,
-	head_pitch: rot::Angle,
-	velocity: entity::Velocity,
+	pub entity_id: VarInt,
+	pub entity_uuid: Uuid,
+	pub entity_type: VarInt,
+	pub position: pos::F64Position,
+	pub rotation: rot::AngleRotation,
+	pub head_pitch: rot::Angle,
+	pub velocity: entity::Velocity,
 }
 
 #[derive(Encode)]
@@ -51,10 +51,10 @@ pub struct SpawnPainting {
 /// When a player comes into the visible range of another player
 #[derive(Encode)]
 pub struct SpawnPlayer {
-	entity_id: VarInt,
-	entity_uuid: Uuid,
-	position: pos::F64Position,
-	rotation: rot::AngleRotation,
+	pub entity_id: VarInt,
+	pub entity_uuid: Uuid,
+	pub position: pos::F64Position,
+	pub rotation: rot::AngleRotation,
 }
 
 #[derive(Encode)]
@@ -106,8 +106,8 @@ pub struct TriggerBlockAction {
 
 #[derive(Encode)]
 pub struct BlockChange {
-	location: pos::PackedPosition,
-	new_block_state: VarInt,
+	pub location: pos::PackedPosition,
+	pub new_block_state: VarInt,
 }
 
 #[derive(Encode)]
@@ -146,32 +146,32 @@ pub struct TabCompletions {
 
 #[derive(Encode)]
 pub struct CloseWindow {
-	window_id: u8,
+	pub window_id: u8,
 }
 
 #[derive(Encode)]
 pub struct UpdateWindowItems {
-	window_id: u8,
+	pub window_id: u8,
 	/// The client echoes the most recently received State ID in subsequent window-related packets
-	state_id: VarInt,
-	slot_data: PrefixedVec<slot::Slot>,
+	pub state_id: VarInt,
+	pub slot_data: PrefixedVec<slot::Slot>,
 	/// Item that the player is holding with their mouse
-	floating_item: slot::Slot,
+	pub floating_item: slot::Slot,
 }
 
 #[derive(Encode)]
 pub struct UpdateWindowProperty {
-	window_id: u8,
-	// TODO more specific type
-	property_id: i16,
-	new_value: i16,
+	pub window_id: u8,
+	/// Depends on the window type
+	pub property_id: i16,
+	pub new_value: i16,
 }
 
 #[derive(Encode)]
 pub struct SetWindowSlot {
-	window_id: i8, // not u8
-	state_id: VarInt,
-	slot: slot::IndexedSlot,
+	pub window_id: i8, // not u8
+	pub state_id: VarInt,
+	pub slot: slot::IndexedSlot,
 }
 
 #[derive(Encode)]
@@ -214,7 +214,7 @@ pub struct Explosion {
 
 #[derive(Encode)]
 pub struct UnloadChunk {
-	chunk_position: chunk::Position<i32>,
+	pub chunk_position: chunk::Position<i32>,
 }
 
 #[derive(Encode)]
@@ -244,16 +244,15 @@ pub struct WorldBorderInitialize {
 }
 
 #[derive(Encode)]
-pub struct KeepAlive(i64);
+pub struct KeepAlive(pub i64);
 
-// TODO implement
-// #[derive(Encode)]
+#[derive(Encode)]
 pub struct UpdateChunkData {
-	chunk_position: chunk::Position<i32>,
-	height_maps: chunk::HeightMaps,
-	chunk_blocks: chunk::Blocks,
-	chunk_block_entities: chunk::BlockEntities,
-	common: chunk::LightUpdateCommon,
+	pub chunk_position: chunk::Position<i32>,
+	pub height_maps: chunk::HeightMaps,
+	pub chunk_blocks: chunk::Blocks,
+	pub chunk_block_entities: chunk::BlockEntities,
+	pub common: chunk::LightUpdateCommon,
 }
 
 #[derive(Encode)]
@@ -277,28 +276,29 @@ pub struct ShowParticle {
 
 #[derive(Encode)]
 pub struct UpdateLight {
-	chunk_position: chunk::Position<VarInt>,
-	common: chunk::LightUpdateCommon,
+	pub chunk_position: chunk::Position<VarInt>,
+	pub common: chunk::LightUpdateCommon,
 }
 
 #[derive(Encode)]
-pub struct JoinGame {
-	entity_id: i32,
-	is_hardcore: bool,
-	new_game_mode: misc::GameMode,
-	old_game_mode: misc::OptionalGameMode,
-	dimension_names: PrefixedVec<PrefixedString>,
-	dimension_codec: NbtData<dimension::Codec>,
-	dimension_data: NbtData<dimension::Type>,
-	current_dimension: PrefixedString,
-	hashed_seed: i64,
-	max_players: VarInt,
-	view_distance: VarInt,
-	simulation_distance: VarInt,
-	reduced_debug_info: bool,
-	enable_respawn_screen: bool,
-	is_debug: bool,
-	is_flat: bool,
+pub struct JoinGame<'a> {
+	pub entity_id: i32,
+	pub is_hardcore: bool,
+	pub new_game_mode: misc::GameMode,
+	pub old_game_mode: misc::OptionalGameMode,
+	pub dimension_names: PrefixedVec<PrefixedString>,
+	pub dimension_codec: NbtData<&'a dimension::Codec>,
+	pub dimension_data: NbtData<&'a dimension::Type>,
+	pub current_dimension: PrefixedString,
+	pub hashed_seed: dimension::HashedSeed,
+	/// Ignored by the client
+	pub max_players: VarInt,
+	pub view_distance: VarInt,
+	pub simulation_distance: VarInt,
+	pub reduced_debug_info: bool,
+	pub enable_respawn_screen: bool,
+	pub is_debug: bool,
+	pub is_flat: bool,
 }
 
 #[derive(Encode)]
@@ -326,26 +326,26 @@ pub struct TradeList {
 
 #[derive(Encode)]
 pub struct UpdateEntityNearPosition {
-	entity_id: VarInt,
+	pub entity_id: VarInt,
 	/// ((current * 32) - (previous * 32)) * 128
-	delta: pos::UnpackedPosition<i16>,
-	on_ground: bool,
+	pub delta: pos::UnpackedPosition<i16>,
+	pub on_ground: bool,
 }
 
 #[derive(Encode)]
 pub struct UpdateEntityNearPositionRotation {
-	entity_id: VarInt,
+	pub entity_id: VarInt,
 	/// ((current * 32) - (previous * 32)) * 128
-	position_delta: pos::UnpackedPosition<i16>,
-	new_rotation: rot::AngleRotation,
-	on_ground: bool,
+	pub position_delta: pos::UnpackedPosition<i16>,
+	pub new_rotation: rot::AngleRotation,
+	pub on_ground: bool,
 }
 
 #[derive(Encode)]
 pub struct UpdateEntityRotation {
-	entity_id: VarInt,
-	new_rotation: rot::AngleRotation,
-	on_ground: bool,
+	pub entity_id: VarInt,
+	pub new_rotation: rot::AngleRotation,
+	pub on_ground: bool,
 }
 
 #[derive(Encode)]
@@ -362,9 +362,9 @@ pub struct OpenBook {
 #[derive(Encode)]
 pub struct OpenWindow {
 	/// This ID is used as a handle for other window-related packets
-	window_id: VarInt,
-	window_type: VarInt,
-	window_title: chat::Chat,
+	pub window_id: VarInt,
+	pub window_type: VarInt,
+	pub window_title: chat::Chat,
 }
 
 #[derive(Encode)]
@@ -377,8 +377,8 @@ pub struct Ping(i32);
 
 #[derive(Encode)]
 pub struct AcceptCraftRecipeSelection {
-	window_id: i8,
-	recipe: PrefixedString,
+	pub window_id: i8,
+	pub recipe: PrefixedString,
 }
 
 #[derive(Encode)]
@@ -435,9 +435,9 @@ pub struct PlayerLookTargetUpdate {
 #[derive(Encode)]
 pub struct PlayerPositionRotationUpdate {
 	/// May be absolute or relative at the axis level
-	new_position: pos::F64Position,
+	pub new_position: pos::F64Position,
 	/// Ditto
-	new_rotation: rot::F32Rotation,
+	pub new_rotation: rot::F32Rotation,
 	/// TODO more specific type
 	/// Bit flags:
 	/// 1 = new_position.x is relative
@@ -445,18 +445,18 @@ pub struct PlayerPositionRotationUpdate {
 	/// 4 = new_position.z is relative
 	/// 8 = new_rotation.yaw is relative
 	/// 16 = new_rotation.pitch is relative
-	are_fields_relative: u8,
+	pub are_fields_relative: u8,
 	/// Echoed by client in Teleport Confirm
-	teleport_id: VarInt,
-	dismount_vehicle: bool,
+	pub teleport_id: VarInt,
+	pub dismount_vehicle: bool,
 }
 
 pub struct UnlockRecipes {
-	action: recipes::UnlockAction,
-	crafting_book: recipes::BookState,
-	smelting_book: recipes::BookState,
-	blast_furnace_book: recipes::BookState,
-	smoker_book: recipes::BookState,
+	pub action: recipes::UnlockAction,
+	pub crafting_book: recipes::BookState,
+	pub smelting_book: recipes::BookState,
+	pub blast_furnace_book: recipes::BookState,
+	pub smoker_book: recipes::BookState,
 }
 
 impl Encode for UnlockRecipes {
@@ -484,7 +484,7 @@ impl Encode for UnlockRecipes {
 }
 
 #[derive(Encode)]
-pub struct RemoveEntities(PrefixedVec<VarInt>);
+pub struct RemoveEntities(pub PrefixedVec<VarInt>);
 
 #[derive(Encode)]
 pub struct RemoveEntityEffect {
@@ -498,7 +498,7 @@ pub struct RemoveEntityEffect {
 pub struct RespawnPlayer {
 	dimension_data: NbtData<dimension::Type>,
 	dimension_name: PrefixedString,
-	hashed_seed: i64,
+	hashed_seed: dimension::HashedSeed,
 	new_gamemode: misc::GameMode,
 	previous_gamemode: misc::OptionalGameMode,
 	is_debug: bool,
@@ -510,17 +510,17 @@ pub struct RespawnPlayer {
 
 #[derive(Encode)]
 pub struct UpdateEntityHeadRotation {
-	entity_id: VarInt,
-	new_yaw: rot::Angle,
+	pub entity_id: VarInt,
+	pub new_yaw: rot::Angle,
 }
 
 #[derive(Encode)]
 pub struct MultiBlockChange {
 	/// Positions of blocks to update are relative to this position
-	origin_position: chunk::SectionPosition,
+	pub origin_position: chunk::SectionPosition,
 	/// The opposite of the trust_edges field in LightUpdateCommon
-	no_trust_edges: bool,
-	changes: PrefixedVec<chunk::MultiBlockChangeEntry>,
+	pub no_trust_edges: bool,
+	pub changes: PrefixedVec<chunk::MultiBlockChangeEntry>,
 }
 
 #[derive(Encode)]
@@ -562,17 +562,17 @@ pub struct ChangeActiveSlot(i8);
 
 // AKA "Update View Position"
 #[derive(Encode)]
-pub struct UpdateActiveChunk(chunk::Position<VarInt>);
+pub struct UpdateActiveChunk(pub chunk::Position<VarInt>);
 
 #[derive(Encode)]
-pub struct UpdateRenderDistance(VarInt);
+pub struct UpdateRenderDistance(pub VarInt);
 
 /// Also updates where compasses point
 #[derive(Encode)]
 pub struct UpdateSpawnPosition {
-	location: pos::PackedPosition,
+	pub location: pos::PackedPosition,
 	/// FIXME angle of what?
-	angle: f32,
+	pub angle: f32,
 }
 
 #[derive(Encode)]
@@ -581,10 +581,10 @@ pub struct DisplayScoreboard {
 	name: PrefixedString,
 }
 
-// #[derive(Encode)]
+#[derive(Encode)]
 pub struct UpdateEntityMetadata {
-	entity_id: VarInt,
-	metadata: entity::Metadata,
+	pub entity_id: VarInt,
+	pub metadata: entity::Metadata,
 }
 
 #[derive(Encode)]
@@ -651,7 +651,7 @@ pub struct UpdateScore {
 }
 
 #[derive(Encode)]
-pub struct UpdateSimulationDistance(VarInt);
+pub struct UpdateSimulationDistance(pub VarInt);
 
 #[derive(Encode)]
 pub struct SetTitleSubtitle(chat::Chat);
@@ -725,10 +725,10 @@ pub struct CollectItem {
 
 #[derive(Encode)]
 pub struct TeleportEntity {
-	entity_id: VarInt,
-	position: pos::F64Position,
-	rotation: rot::AngleRotation,
-	on_ground: bool,
+	pub entity_id: VarInt,
+	pub position: pos::F64Position,
+	pub rotation: rot::AngleRotation,
+	pub on_ground: bool,
 }
 
 // TODO "Advancements"
@@ -747,15 +747,25 @@ pub struct UpdateEntityEffect {
 	duration: time::TicksVarInt,
 }
 
-#[derive(Encode)]
-pub struct DeclareRecipes(PrefixedVec<recipes::Recipe>);
+/// Borrowed because the recipes are loaded once and shared by every client
+pub struct DeclareRecipes<'a>(pub &'a [recipes::Recipe]);
+
+impl Encode for DeclareRecipes<'_> {
+	fn encode(&self, writer: &mut dyn std::io::Write) -> encde::Result<()> {
+		VarInt(self.0.len().try_into().map_err(|err| encde::Error::Custom(Box::new(err)))?).encode(writer)?;
+		for recipe in self.0 {
+			recipe.encode(writer)?;
+		}
+		Ok(())
+	}
+}
 
 #[derive(Encode)]
 pub struct DeclareTaggedGroups(PrefixedVec<misc::TagGroup>);
 
 #[derive(Encode)]
 #[repr(u8)]
-pub enum Packet {
+pub enum Packet<'a> {
 	#[encde(wire_tag = 0x00)]
 	SpawnEntity(SpawnEntity),
 	#[encde(wire_tag = 0x01)]
@@ -824,8 +834,8 @@ pub enum Packet {
 	WorldBorderInitialize(WorldBorderInitialize),
 	#[encde(wire_tag = 0x21)]
 	KeepAlive(KeepAlive),
-	// #[encde(wire_tag = 0x22)]
-	// UpdateChunkData(UpdateChunkData),
+	#[encde(wire_tag = 0x22)]
+	UpdateChunkData(UpdateChunkData),
 	#[encde(wire_tag = 0x23)]
 	TriggerEffect(TriggerEffect),
 	#[encde(wire_tag = 0x24)]
@@ -833,7 +843,7 @@ pub enum Packet {
 	#[encde(wire_tag = 0x25)]
 	UpdateLight(UpdateLight),
 	#[encde(wire_tag = 0x26)]
-	JoinGame(JoinGame),
+	JoinGame(JoinGame<'a>),
 	#[encde(wire_tag = 0x27)]
 	MapData(MapData),
 	#[encde(wire_tag = 0x28)]
@@ -910,8 +920,8 @@ pub enum Packet {
 	UpdateSpawnPosition(UpdateSpawnPosition),
 	#[encde(wire_tag = 0x4c)]
 	DisplayScoreboard(DisplayScoreboard),
-	// #[encde(wire_tag = 0x4d)]
-	// UpdateEntityMetadata(UpdateEntityMetadata),
+	#[encde(wire_tag = 0x4d)]
+	UpdateEntityMetadata(UpdateEntityMetadata),
 	#[encde(wire_tag = 0x4e)]
 	AttachEntity(AttachEntity),
 	#[encde(wire_tag = 0x4f)]
@@ -961,7 +971,7 @@ pub enum Packet {
 	#[encde(wire_tag = 0x65)]
 	UpdateEntityEffect(UpdateEntityEffect),
 	#[encde(wire_tag = 0x66)]
-	DeclareRecipes(DeclareRecipes),
+	DeclareRecipes(DeclareRecipes<'a>),
 	#[encde(wire_tag = 0x67)]
 	DeclareTaggedGroups(DeclareTaggedGroups),
 }
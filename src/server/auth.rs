@@ -0,0 +1,253 @@
+use crate::config::AuthConfig;
+use crate::packets::helpers::game::player_list::AddPlayerProperty;
+use log::trace;
+use serde::Deserialize;
+use std::collections::HashSet;
+use std::path::Path;
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct SessionProperty {
+	pub name: String,
+	pub value: String,
+	/// Base-64-encoded SHA1withRSA signature of `value`
+	pub signature: Option<String>,
+}
+
+impl From<&SessionProperty> for AddPlayerProperty {
+	fn from(property: &SessionProperty) -> Self {
+		AddPlayerProperty::new(property.name.clone(), property.value.clone(), property.signature.clone())
+	}
+}
+
+#[derive(Deserialize)]
+pub struct SessionResponse {
+	#[serde(rename = "id")]
+	pub uuid: uuid::Uuid,
+	#[serde(rename = "name")]
+	pub username: String,
+	/// Usually one item named "textures"
+	#[serde(default)]
+	pub properties: Vec<SessionProperty>,
+}
+
+impl SessionResponse {
+	/// The decoded value of the `textures` property, containing the profile, skin, and cape data as JSON
+	pub fn skin_texture(&self) -> anyhow::Result<Option<Vec<u8>>> {
+		self.properties.iter().find(|property| property.name == "textures").map(|property| Ok(base64::decode(&property.value)?)).transpose()
+	}
+}
+
+/// The key that the session server signs properties with
+pub struct YggdrasilPublicKey(openssl::pkey::PKey<openssl::pkey::Public>);
+
+impl YggdrasilPublicKey {
+	pub fn load(path: &Path) -> anyhow::Result<Self> {
+		let pem = std::fs::read(path).map_err(|err| anyhow::Error::new(err).context(format!("Reading Yggdrasil public key from {}", path.display())))?;
+		Ok(Self(openssl::pkey::PKey::public_key_from_pem(&pem)?))
+	}
+	pub fn verify(&self, property: &SessionProperty) -> anyhow::Result<()> {
+		let signature = property.signature.as_ref().ok_or_else(|| anyhow::anyhow!("Session property {:?} is not signed", property.name))?;
+		let signature = base64::decode(signature)?;
+		let mut verifier = openssl::sign::Verifier::new(openssl::hash::MessageDigest::sha1(), &self.0)?;
+		verifier.update(property.value.as_bytes())?;
+		anyhow::ensure!(verifier.verify(&signature)?, "Session property {:?} has an invalid signature", property.name);
+		Ok(())
+	}
+}
+
+/// Decides who a connecting player is, and whether they are allowed to join
+pub trait Authenticator: Send + Sync {
+	/// If true, encryption is enabled before authenticating and the resulting server hash is passed to `authenticate`.
+	fn uses_encryption(&self) -> bool;
+	/// `server_hash` is `None` iff `uses_encryption` returned false.
+	fn authenticate(&self, username: String, server_hash: Option<&str>) -> anyhow::Result<SessionResponse>;
+}
+
+impl dyn Authenticator {
+	pub fn from_config(config: &AuthConfig) -> anyhow::Result<Box<Self>> {
+		Ok(match config {
+			AuthConfig::Mojang { public_key } => Box::new(Yggdrasil {
+				public_key: public_key.as_deref().map(YggdrasilPublicKey::load).transpose()?,
+				..Yggdrasil::mojang()
+			}),
+			AuthConfig::Offline => Box::new(Offline),
+			AuthConfig::Custom { session_server, api_server, public_key } => Box::new(Yggdrasil {
+				session_server: session_server.clone(),
+				api_server: api_server.clone(),
+				public_key: public_key.as_deref().map(YggdrasilPublicKey::load).transpose()?,
+			}),
+			AuthConfig::AllowList { file } => Box::new(AllowList::load(file)?),
+		})
+	}
+}
+
+/// The same as vanilla's offline-mode UUIDs: a version 3 UUID of `OfflinePlayer:<username>`, without a namespace
+fn offline_uuid(username: &str) -> anyhow::Result<uuid::Uuid> {
+	let hash = openssl::hash::hash(openssl::hash::MessageDigest::md5(), format!("OfflinePlayer:{}", username).as_bytes())?;
+	let mut bytes: uuid::Bytes = Default::default();
+	bytes.copy_from_slice(&hash);
+	Ok(uuid::Builder::from_bytes(bytes).set_variant(uuid::Variant::RFC4122).set_version(uuid::Version::Md5).build())
+}
+
+fn offline_session(username: String) -> anyhow::Result<SessionResponse> {
+	Ok(SessionResponse {
+		uuid: offline_uuid(&username)?,
+		username,
+		properties: Vec::new(),
+	})
+}
+
+/// Mojang's session server, or anything that implements the same API
+pub struct Yggdrasil {
+	/// Without a trailing slash
+	session_server: String,
+	/// Without a trailing slash
+	api_server: String,
+	/// If present, all session properties must have valid signatures
+	public_key: Option<YggdrasilPublicKey>,
+}
+
+impl Yggdrasil {
+	pub fn mojang() -> Self {
+		Self {
+			session_server: "https://sessionserver.mojang.com".to_owned(),
+			api_server: "https://api.mojang.com".to_owned(),
+			public_key: None,
+		}
+	}
+	fn request_session(&self, username: String, server_hash: Option<&str>) -> anyhow::Result<SessionResponse> {
+		let server_hash = server_hash.ok_or_else(|| anyhow::anyhow!("Yggdrasil authentication requires encryption"))?;
+		trace!("Making request to session server at {}", self.session_server);
+		let response = reqwest::blocking::get(reqwest::Url::parse_with_params(
+			&format!("{}/session/minecraft/hasJoined", self.session_server),
+			&[("username", username.as_str()), ("serverId", server_hash)],
+		)?)?
+		.error_for_status()?;
+		// This is a bit of a kludge, but we have to handle the No Content response somehow
+		if response.status() == reqwest::StatusCode::NO_CONTENT {
+			trace!("Session server returned No Content; using other endpoints to synthesize the data");
+			#[derive(Deserialize)]
+			struct UuidForUsernameResponse {
+				// there is also the "name" field but we can ignore it
+				#[serde(rename = "id")]
+				uuid: uuid::Uuid,
+			}
+			let uuid: UuidForUsernameResponse = reqwest::blocking::get(format!("{}/users/profiles/minecraft/{}", self.api_server, username))?.error_for_status()?.json()?;
+			let uuid = uuid.uuid;
+			Ok(reqwest::blocking::get(format!("{}/session/minecraft/profile/{}?unsigned=false", self.session_server, uuid))?.error_for_status()?.json()?)
+		} else {
+			Ok(response.json()?)
+		}
+	}
+}
+
+impl Authenticator for Yggdrasil {
+	fn uses_encryption(&self) -> bool {
+		true
+	}
+	fn authenticate(&self, username: String, server_hash: Option<&str>) -> anyhow::Result<SessionResponse> {
+		let session = self.request_session(username, server_hash)?;
+		if let Some(public_key) = &self.public_key {
+			for property in &session.properties {
+				public_key.verify(property)?;
+			}
+		}
+		Ok(session)
+	}
+}
+
+/// Lets anyone in, without encryption
+pub struct Offline;
+
+impl Authenticator for Offline {
+	fn uses_encryption(&self) -> bool {
+		false
+	}
+	fn authenticate(&self, username: String, _server_hash: Option<&str>) -> anyhow::Result<SessionResponse> {
+		offline_session(username)
+	}
+}
+
+/// Like `Offline`, but only lets in usernames from a fixed list
+pub struct AllowList {
+	/// Lowercase, since usernames are case-insensitive
+	usernames: HashSet<String>,
+}
+
+impl AllowList {
+	/// One username per line; empty lines and lines starting with `#` are ignored
+	pub fn load(path: &std::path::Path) -> anyhow::Result<Self> {
+		let contents = std::fs::read_to_string(path).map_err(|err| anyhow::Error::new(err).context(format!("Reading allow-list from {}", path.display())))?;
+		let usernames = contents.lines().map(str::trim).filter(|line| !line.is_empty() && !line.starts_with('#')).map(str::to_lowercase).collect();
+		Ok(Self { usernames })
+	}
+}
+
+impl Authenticator for AllowList {
+	fn uses_encryption(&self) -> bool {
+		false
+	}
+	fn authenticate(&self, username: String, _server_hash: Option<&str>) -> anyhow::Result<SessionResponse> {
+		anyhow::ensure!(self.usernames.contains(&username.to_lowercase()), "{} is not on the allow-list", username);
+		offline_session(username)
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::{Authenticator, SessionProperty, Yggdrasil, YggdrasilPublicKey};
+	use std::io::{Read, Write};
+	use std::net::TcpListener;
+
+	#[test]
+	pub fn offline_uuids() {
+		assert_eq!("b50ad385-829d-3141-a216-7e7d7539ba7f", super::offline_uuid("Notch").unwrap().to_hyphenated().to_string());
+	}
+
+	#[test]
+	pub fn custom_session_server() {
+		let listener = TcpListener::bind(("127.0.0.1", 0)).unwrap();
+		let base_url = format!("http://{}", listener.local_addr().unwrap());
+		let mock = std::thread::spawn(move || {
+			let (mut socket, _) = listener.accept().unwrap();
+			let mut request = Vec::new();
+			let mut buf = [0u8; 1024];
+			while !request.ends_with(b"\r\n\r\n") {
+				let amount = socket.read(&mut buf).unwrap();
+				request.extend_from_slice(&buf[..amount]);
+			}
+			let body = format!(r#"{{"id":"069a79f444e94726a5befca90e38aaf5","name":"Notch","properties":[{{"name":"textures","value":"{}"}}]}}"#, base64::encode("skin"));
+			write!(socket, "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}", body.len(), body).unwrap();
+			String::from_utf8(request).unwrap()
+		});
+
+		let authenticator = Yggdrasil {
+			session_server: base_url.clone(),
+			api_server: base_url,
+			public_key: None,
+		};
+		let session = authenticator.authenticate("Notch".to_owned(), Some("-1234abc")).unwrap();
+		assert_eq!(session.uuid.to_hyphenated().to_string(), "069a79f4-44e9-4726-a5be-fca90e38aaf5");
+		assert_eq!(session.username, "Notch");
+		assert_eq!(session.skin_texture().unwrap().unwrap(), b"skin");
+		assert!(mock.join().unwrap().starts_with("GET /session/minecraft/hasJoined?username=Notch&serverId=-1234abc "));
+	}
+
+	#[test]
+	pub fn property_signatures() {
+		let private_key = openssl::pkey::PKey::from_rsa(openssl::rsa::Rsa::generate(1024).unwrap()).unwrap();
+		let public_key = YggdrasilPublicKey(openssl::pkey::PKey::public_key_from_der(&private_key.public_key_to_der().unwrap()).unwrap());
+		let mut signer = openssl::sign::Signer::new(openssl::hash::MessageDigest::sha1(), &private_key).unwrap();
+		signer.update(b"dGV4dHVyZXM=").unwrap();
+		let mut property = SessionProperty {
+			name: "textures".to_owned(),
+			value: "dGV4dHVyZXM=".to_owned(),
+			signature: Some(base64::encode(signer.sign_to_vec().unwrap())),
+		};
+		public_key.verify(&property).unwrap();
+		property.value = "dGFtcGVyZWQ=".to_owned();
+		assert!(public_key.verify(&property).is_err());
+		property.signature = None;
+		assert!(public_key.verify(&property).is_err());
+	}
+}
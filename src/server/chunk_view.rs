@@ -0,0 +1,114 @@
+use crate::world::ChunkPosition;
+use std::collections::HashSet;
+
+/// Vanilla clients don't go lower than this
+pub const MIN_VIEW_DISTANCE: u8 = 2;
+
+/// The chunks that a client has been sent, which follow it around as it moves
+pub struct ChunkView {
+	/// `None` until the first update
+	center: Option<ChunkPosition>,
+	view_distance: u8,
+	loaded: HashSet<ChunkPosition>,
+}
+
+#[derive(Debug, Default)]
+pub struct ViewChanges {
+	pub center_changed: bool,
+	pub view_distance_changed: bool,
+	/// Nearest first
+	pub load: Vec<ChunkPosition>,
+	pub unload: Vec<ChunkPosition>,
+}
+
+/// Every chunk within `radius` of `center`, going around each square ring from the center outwards
+pub fn spiral(center: ChunkPosition, radius: i32) -> impl Iterator<Item = ChunkPosition> {
+	(0..=radius).flat_map(move |ring| {
+		let edges: Box<dyn Iterator<Item = (i32, i32)>> = if ring == 0 {
+			Box::new(std::iter::once((0, 0)))
+		} else {
+			Box::new(
+				(-ring..ring)
+					.map(move |i| (i, -ring))
+					.chain((-ring..ring).map(move |i| (ring, i)))
+					.chain((-ring..ring).map(move |i| (-i, ring)))
+					.chain((-ring..ring).map(move |i| (-ring, -i))),
+			)
+		};
+		edges.map(move |(x, z)| ChunkPosition { x: center.x + x, z: center.z + z })
+	})
+}
+
+impl ChunkView {
+	/// `view_distance` is the one that the client was told about when it joined
+	pub fn new(view_distance: u8) -> Self {
+		Self {
+			center: None,
+			view_distance,
+			loaded: HashSet::new(),
+		}
+	}
+	pub fn loaded(&self) -> impl Iterator<Item = ChunkPosition> + '_ {
+		self.loaded.iter().copied()
+	}
+	/// Moves the view, returning what the client needs to be told
+	pub fn update(&mut self, center: ChunkPosition, view_distance: u8) -> ViewChanges {
+		let mut changes = ViewChanges {
+			center_changed: self.center != Some(center),
+			view_distance_changed: self.view_distance != view_distance,
+			..Default::default()
+		};
+		if !changes.center_changed && !changes.view_distance_changed {
+			return changes;
+		}
+		self.center = Some(center);
+		self.view_distance = view_distance;
+		let radius = i32::from(view_distance);
+		changes.unload = self.loaded.iter().copied().filter(|position| (position.x - center.x).abs() > radius || (position.z - center.z).abs() > radius).collect();
+		for position in &changes.unload {
+			self.loaded.remove(position);
+		}
+		changes.load = spiral(center, radius).filter(|&position| self.loaded.insert(position)).collect();
+		changes
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::{spiral, ChunkView};
+	use crate::world::ChunkPosition;
+	use std::collections::HashSet;
+
+	#[test]
+	pub fn spiral_order() {
+		let center = ChunkPosition { x: 3, z: -2 };
+		let positions: Vec<_> = spiral(center, 3).collect();
+		assert_eq!(positions.len(), 49);
+		assert_eq!(positions.iter().collect::<HashSet<_>>().len(), 49);
+		assert_eq!(positions[0], center);
+		let rings: Vec<_> = positions.iter().map(|position| (position.x - center.x).abs().max((position.z - center.z).abs())).collect();
+		assert!(rings.windows(2).all(|pair| pair[0] <= pair[1]));
+	}
+
+	#[test]
+	pub fn movement() {
+		let mut view = ChunkView::new(2);
+		let changes = view.update(ChunkPosition { x: 0, z: 0 }, 2);
+		assert!(changes.center_changed && !changes.view_distance_changed);
+		assert_eq!(changes.load.len(), 25);
+		assert!(changes.unload.is_empty());
+
+		let changes = view.update(ChunkPosition { x: 0, z: 0 }, 2);
+		assert!(!changes.center_changed && changes.load.is_empty() && changes.unload.is_empty());
+
+		let changes = view.update(ChunkPosition { x: 1, z: 0 }, 2);
+		assert!(changes.load.iter().all(|position| position.x == 3));
+		assert!(changes.unload.iter().all(|position| position.x == -2));
+		assert_eq!((changes.load.len(), changes.unload.len()), (5, 5));
+
+		let changes = view.update(ChunkPosition { x: 1, z: 0 }, 1);
+		assert!(changes.view_distance_changed && changes.load.is_empty());
+		assert_eq!(changes.unload.len(), 16);
+		assert_eq!(view.loaded().count(), 9);
+	}
+}
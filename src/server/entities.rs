@@ -0,0 +1,347 @@
+use crate::packets::helpers::game::metadata::{self, Builder};
+use crate::packets::helpers::position::{F64Position, UnpackedPosition};
+use crate::packets::helpers::rotation::{Angle, AngleRotation, F32Rotation};
+use crate::packets::helpers::varint::VarInt;
+use crate::packets::helpers::wrappers::std::PrefixedVec;
+use crate::packets::helpers::wrappers::uuid::Uuid as UuidWrapper;
+use crate::packets::play::send::{self, Packet as Send};
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+
+/// Relative moves are in 4096ths of a block
+const DELTA_UNITS_PER_BLOCK: f64 = 4096.0;
+
+/// Decides which packet spawns the entity
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Kind {
+	/// The viewer must already have the player in their player list, or the client ignores the spawn
+	Player,
+	/// `data` depends on the type, like the block state of a falling block
+	Object {
+		entity_type: i32,
+		data: i32,
+	},
+	Living {
+		entity_type: i32,
+	},
+}
+
+impl Kind {
+	/// In chunks, and capped by the viewer's view distance. Vanilla varies this by type, but these are the most common values.
+	fn tracking_range(self) -> u8 {
+		match self {
+			Self::Player => 32,
+			Self::Living { .. } => 10,
+			Self::Object { .. } => 6,
+		}
+	}
+}
+
+#[derive(Clone, Debug)]
+pub struct Entity {
+	pub uuid: uuid::Uuid,
+	pub kind: Kind,
+	pub position: F64Position,
+	pub rotation: F32Rotation,
+	/// Where the head faces, which can be different from the body
+	pub head_yaw: f32,
+	pub on_ground: bool,
+	/// Which parts of a player's skin are shown, see `metadata::skin_parts`. Other entities don't have any.
+	pub skin_parts: u8,
+}
+
+impl Entity {
+	/// With a random UUID
+	pub fn new(kind: Kind, position: F64Position, rotation: F32Rotation) -> Self {
+		Self {
+			uuid: uuid::Builder::from_bytes(rand::random()).set_variant(uuid::Variant::RFC4122).set_version(uuid::Version::Random).build(),
+			kind,
+			position,
+			rotation,
+			head_yaw: rotation.yaw,
+			on_ground: false,
+			skin_parts: 0,
+		}
+	}
+}
+
+/// Every entity in the world, shared between all of the players
+#[derive(Default)]
+pub struct Entities {
+	next_id: i32,
+	entities: HashMap<i32, Entity>,
+}
+
+impl Entities {
+	/// Returns the entity's new ID
+	pub fn spawn(&mut self, entity: Entity) -> i32 {
+		let id = self.next_id;
+		self.next_id = self.next_id.wrapping_add(1);
+		self.entities.insert(id, entity);
+		id
+	}
+	pub fn get(&self, id: i32) -> Option<&Entity> {
+		self.entities.get(&id)
+	}
+	pub fn get_mut(&mut self, id: i32) -> Option<&mut Entity> {
+		self.entities.get_mut(&id)
+	}
+	pub fn remove(&mut self, id: i32) -> Option<Entity> {
+		self.entities.remove(&id)
+	}
+	pub fn iter(&self) -> impl Iterator<Item = (i32, &Entity)> {
+		self.entities.iter().map(|(&id, entity)| (id, entity))
+	}
+}
+
+/// What the viewer was last told about an entity
+struct Tracked {
+	/// In 4096ths of a block, so that relative moves don't accumulate rounding errors
+	position: UnpackedPosition<i64>,
+	rotation: AngleRotation,
+	head_yaw: Angle,
+	on_ground: bool,
+	skin_parts: u8,
+}
+
+impl Tracked {
+	fn new(entity: &Entity) -> Self {
+		Self {
+			position: quantize(entity.position),
+			rotation: angles(entity.rotation),
+			head_yaw: Angle::from_degrees(entity.head_yaw),
+			on_ground: entity.on_ground,
+			skin_parts: entity.skin_parts,
+		}
+	}
+}
+
+/// The entities that a client has been sent, which it keeps up to date as they move in and out of range
+pub struct Tracker {
+	/// The viewer's own entity, which it doesn't need to be sent
+	viewer: i32,
+	tracked: HashMap<i32, Tracked>,
+}
+
+fn quantize(position: F64Position) -> UnpackedPosition<i64> {
+	let quantize = |coordinate: f64| (coordinate * DELTA_UNITS_PER_BLOCK).round() as i64;
+	UnpackedPosition {
+		x: quantize(position.x),
+		y: quantize(position.y),
+		z: quantize(position.z),
+	}
+}
+
+fn angles(rotation: F32Rotation) -> AngleRotation {
+	AngleRotation {
+		yaw: Angle::from_degrees(rotation.yaw),
+		pitch: Angle::from_degrees(rotation.pitch),
+	}
+}
+
+/// Horizontally, like in vanilla
+fn in_range(entity: &Entity, center: F64Position, view_distance: u8) -> bool {
+	let range = f64::from(entity.kind.tracking_range().min(view_distance)) * 16.0;
+	let (dx, dz) = (entity.position.x - center.x, entity.position.z - center.z);
+	dx * dx + dz * dz <= range * range
+}
+
+fn head_rotation_packet(id: i32, head_yaw: Angle) -> Send<'static> {
+	Send::UpdateEntityHeadRotation(send::UpdateEntityHeadRotation { entity_id: VarInt(id), new_yaw: head_yaw })
+}
+
+/// The client shows players without the outer layer of their skin until it's told which parts to show
+fn skin_parts_packet(id: i32, skin_parts: u8) -> Send<'static> {
+	Send::UpdateEntityMetadata(send::UpdateEntityMetadata {
+		entity_id: VarInt(id),
+		metadata: metadata::Player::default().skin_parts(skin_parts).build(),
+	})
+}
+
+fn spawn_packet(id: i32, entity: &Entity) -> Send<'static> {
+	let entity_id = VarInt(id);
+	let uuid = UuidWrapper(entity.uuid);
+	let position = entity.position;
+	let rotation = angles(entity.rotation);
+	let velocity = UnpackedPosition { x: 0, y: 0, z: 0 };
+	match entity.kind {
+		Kind::Player => Send::SpawnPlayer(send::SpawnPlayer {
+			entity_id,
+			entity_uuid: uuid,
+			position,
+			rotation,
+		}),
+		Kind::Object { entity_type, data } => Send::SpawnEntity(send::SpawnEntity {
+			entity_id,
+			object_uuid: uuid,
+			entity_type: VarInt(entity_type),
+			position,
+			rotation,
+			object_data: data,
+			velocity,
+		}),
+		Kind::Living { entity_type } => Send::SpawnLivingEntity(send::SpawnLivingEntity {
+			entity_id,
+			entity_uuid: uuid,
+			entity_type: VarInt(entity_type),
+			position,
+			rotation,
+			head_pitch: Angle::from_degrees(entity.head_yaw),
+			velocity,
+		}),
+	}
+}
+
+/// Uses a relative move if the entity hasn't gone too far since it was last sent, and a teleport otherwise
+fn movement_packet(id: i32, entity: &Entity, tracked: &mut Tracked) -> Option<Send<'static>> {
+	let position = quantize(entity.position);
+	let rotation = angles(entity.rotation);
+	let moved = position != tracked.position;
+	let rotated = rotation != tracked.rotation;
+	if !moved && !rotated && entity.on_ground == tracked.on_ground {
+		return None;
+	}
+	let delta = (i16::try_from(position.x - tracked.position.x), i16::try_from(position.y - tracked.position.y), i16::try_from(position.z - tracked.position.z));
+	let entity_id = VarInt(id);
+	let on_ground = entity.on_ground;
+	let packet = match delta {
+		(Ok(x), Ok(y), Ok(z)) if moved && rotated => Send::UpdateEntityNearPositionRotation(send::UpdateEntityNearPositionRotation {
+			entity_id,
+			position_delta: UnpackedPosition { x, y, z },
+			new_rotation: rotation,
+			on_ground,
+		}),
+		(Ok(x), Ok(y), Ok(z)) if moved => Send::UpdateEntityNearPosition(send::UpdateEntityNearPosition {
+			entity_id,
+			delta: UnpackedPosition { x, y, z },
+			on_ground,
+		}),
+		(Ok(_), Ok(_), Ok(_)) => Send::UpdateEntityRotation(send::UpdateEntityRotation { entity_id, new_rotation: rotation, on_ground }),
+		_ => Send::TeleportEntity(send::TeleportEntity {
+			entity_id,
+			position: entity.position,
+			rotation,
+			on_ground,
+		}),
+	};
+	tracked.position = position;
+	tracked.rotation = rotation;
+	tracked.on_ground = on_ground;
+	Some(packet)
+}
+
+impl Tracker {
+	pub fn new(viewer: i32) -> Self {
+		Self { viewer, tracked: HashMap::new() }
+	}
+	/// Brings the viewer up to date with the entities around `center`, returning the packets to send. Players that aren't `listed` in the viewer's player list are left out.
+	pub fn update(&mut self, entities: &Entities, center: F64Position, view_distance: u8, listed: impl Fn(&uuid::Uuid) -> bool) -> Vec<Send<'static>> {
+		let visible = |entity: &Entity| in_range(entity, center, view_distance) && (entity.kind != Kind::Player || listed(&entity.uuid));
+		let mut removed = Vec::new();
+		self.tracked.retain(|&id, _| {
+			let keep = entities.get(id).is_some_and(visible);
+			if !keep {
+				removed.push(VarInt(id));
+			}
+			keep
+		});
+		let mut packets = Vec::new();
+		if !removed.is_empty() {
+			packets.push(Send::RemoveEntities(send::RemoveEntities(PrefixedVec::new(removed))));
+		}
+		for (id, entity) in entities.iter() {
+			if id == self.viewer || !visible(entity) {
+				continue;
+			}
+			match self.tracked.entry(id) {
+				Entry::Vacant(entry) => {
+					let tracked = entry.insert(Tracked::new(entity));
+					packets.push(spawn_packet(id, entity));
+					// player spawns don't include the head or metadata
+					if entity.kind == Kind::Player {
+						packets.push(head_rotation_packet(id, tracked.head_yaw));
+						packets.push(skin_parts_packet(id, tracked.skin_parts));
+					}
+				}
+				Entry::Occupied(mut entry) => {
+					let tracked = entry.get_mut();
+					packets.extend(movement_packet(id, entity, tracked));
+					let head_yaw = Angle::from_degrees(entity.head_yaw);
+					if head_yaw != tracked.head_yaw {
+						tracked.head_yaw = head_yaw;
+						packets.push(head_rotation_packet(id, head_yaw));
+					}
+					if entity.skin_parts != tracked.skin_parts {
+						tracked.skin_parts = entity.skin_parts;
+						packets.push(skin_parts_packet(id, entity.skin_parts));
+					}
+				}
+			}
+		}
+		packets
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::{Entities, Entity, Kind, Tracker};
+	use crate::packets::helpers::game::metadata::skin_parts;
+	use crate::packets::helpers::position::F64Position;
+	use crate::packets::helpers::rotation::F32Rotation;
+	use crate::packets::play::send::Packet as Send;
+
+	fn at(x: f64, z: f64) -> F64Position {
+		F64Position { x, y: 64.0, z }
+	}
+
+	#[test]
+	pub fn tracking() {
+		let rotation = F32Rotation { yaw: 0.0, pitch: 0.0 };
+		let mut entities = Entities::default();
+		let viewer = entities.spawn(Entity::new(Kind::Player, at(0.0, 0.0), rotation));
+		// a zombie
+		let near = entities.spawn(Entity::new(Kind::Living { entity_type: 107 }, at(5.0, 5.0), rotation));
+		let far = entities.spawn(Entity::new(Kind::Living { entity_type: 107 }, at(500.0, 0.0), rotation));
+		let mut tracker = Tracker::new(viewer);
+
+		let packets = tracker.update(&entities, at(0.0, 0.0), 10, |_| true);
+		assert!(matches!(packets[..], [Send::SpawnLivingEntity(ref spawn)] if spawn.entity_id.0 == near));
+		assert!(tracker.update(&entities, at(0.0, 0.0), 10, |_| true).is_empty());
+
+		entities.get_mut(near).unwrap().position = at(6.0, 5.0);
+		let packets = tracker.update(&entities, at(0.0, 0.0), 10, |_| true);
+		assert!(matches!(packets[..], [Send::UpdateEntityNearPosition(ref update)] if update.delta.x == 4096 && update.delta.z == 0));
+
+		entities.get_mut(near).unwrap().position = at(20.0, 5.0);
+		entities.get_mut(near).unwrap().rotation.yaw = 90.0;
+		let packets = tracker.update(&entities, at(0.0, 0.0), 10, |_| true);
+		assert!(matches!(packets[..], [Send::TeleportEntity(ref teleport)] if teleport.position == at(20.0, 5.0)));
+
+		entities.remove(near);
+		entities.get_mut(far).unwrap().position = at(50.0, 0.0);
+		let packets = tracker.update(&entities, at(0.0, 0.0), 10, |_| true);
+		assert!(matches!(packets[..], [Send::RemoveEntities(_), Send::SpawnLivingEntity(ref spawn)] if spawn.entity_id.0 == far));
+
+		let packets = tracker.update(&entities, at(0.0, 0.0), 2, |_| true);
+		assert!(matches!(packets[..], [Send::RemoveEntities(_)]));
+	}
+
+	#[test]
+	pub fn player_skin() {
+		let rotation = F32Rotation { yaw: 0.0, pitch: 0.0 };
+		let mut entities = Entities::default();
+		let viewer = entities.spawn(Entity::new(Kind::Player, at(0.0, 0.0), rotation));
+		let other = entities.spawn(Entity::new(Kind::Player, at(5.0, 5.0), rotation));
+		let mut tracker = Tracker::new(viewer);
+
+		// players that aren't listed yet are left out
+		assert!(tracker.update(&entities, at(0.0, 0.0), 10, |_| false).is_empty());
+		let packets = tracker.update(&entities, at(0.0, 0.0), 10, |_| true);
+		assert!(matches!(packets[..], [Send::SpawnPlayer(_), Send::UpdateEntityHeadRotation(_), Send::UpdateEntityMetadata(ref update)] if update.entity_id.0 == other));
+
+		entities.get_mut(other).unwrap().skin_parts = skin_parts::HAT | skin_parts::JACKET;
+		let packets = tracker.update(&entities, at(0.0, 0.0), 10, |_| true);
+		assert!(matches!(packets[..], [Send::UpdateEntityMetadata(ref update)] if update.metadata.0.len() == 1));
+		assert!(tracker.update(&entities, at(0.0, 0.0), 10, |_| true).is_empty());
+	}
+}
@@ -0,0 +1,170 @@
+use super::PacketSender;
+use crate::packets::helpers::game::chat;
+use crate::packets::helpers::wrappers::json::Json;
+use crate::packets::play::send::{self, Packet as Send};
+use log::{debug, trace};
+use std::net::{Shutdown, TcpStream};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Same as vanilla
+const INTERVAL: Duration = Duration::from_secs(15);
+
+struct Pending {
+	id: i64,
+	sent_at: Instant,
+}
+
+#[derive(Default)]
+struct State {
+	/// The most recent keep-alive that the client has not responded to yet
+	pending: Option<Pending>,
+	timed_out: bool,
+}
+
+/// Sends keep-alives on a background thread and disconnects the client if it doesn't respond to one in time.
+/// The thread stops when this is dropped.
+pub(super) struct KeepAlive {
+	state: Arc<Mutex<State>>,
+	/// Wakes the thread when a response arrives; the thread exits when this is dropped
+	wake: mpsc::Sender<()>,
+}
+
+impl KeepAlive {
+	/// Writes to `stream` time out after `timeout` too, since a client that stops reading is as gone as one that stops responding.
+	/// Otherwise a write to it could block forever, including the disconnect when it times out.
+	pub fn start(sender: PacketSender, stream: TcpStream, timeout: Duration) -> std::io::Result<Self> {
+		stream.set_write_timeout(Some(timeout))?;
+		let state = Arc::new(Mutex::new(State::default()));
+		let (wake, woken) = mpsc::channel();
+		let thread_state = Arc::clone(&state);
+		let thread_name = format!("{} keep-alive", std::thread::current().name().unwrap_or("unnamed"));
+		std::thread::Builder::new().name(thread_name).spawn(move || {
+			if let Err(err) = run(&thread_state, &woken, &sender, &stream, timeout) {
+				debug!("Keep-alive failed: {:#}", err);
+			}
+		})?;
+		Ok(Self { state, wake })
+	}
+
+	/// Checks the ID that the client echoed back and returns the round-trip time
+	pub fn receive(&self, id: i64) -> anyhow::Result<Duration> {
+		// PANICS: the keep-alive thread never panics while holding the lock
+		let mut state = self.state.lock().unwrap();
+		let latency = match &state.pending {
+			Some(pending) if pending.id == id => pending.sent_at.elapsed(),
+			Some(pending) => anyhow::bail!("Client responded to keep-alive {} but we were expecting {}", id, pending.id),
+			None => anyhow::bail!("Client responded to keep-alive {} but none was pending", id),
+		};
+		state.pending = None;
+		// if the thread has already exited, there is nothing to wake
+		let _ = self.wake.send(());
+		Ok(latency)
+	}
+
+	/// Whether the connection was closed because the client stopped responding
+	pub fn timed_out(&self) -> bool {
+		// PANICS: see `receive`
+		self.state.lock().unwrap().timed_out
+	}
+}
+
+fn run(state: &Mutex<State>, woken: &mpsc::Receiver<()>, sender: &PacketSender, stream: &TcpStream, timeout: Duration) -> anyhow::Result<()> {
+	let mut next_send = Instant::now();
+	loop {
+		// the lock is released before writing, since writes can block
+		let (deadline, to_send) = {
+			// PANICS: see `KeepAlive::receive`
+			let mut state = state.lock().unwrap();
+			let now = Instant::now();
+			match &state.pending {
+				Some(pending) if now >= pending.sent_at + timeout => {
+					state.timed_out = true;
+					drop(state);
+					debug!("Client did not respond to keep-alive within {:?}; disconnecting", timeout);
+					let reason = Json(chat::Content {
+						text: "Timed out".to_owned(),
+						..Default::default()
+					});
+					// the connection is being dropped anyway, so an error here isn't important
+					let _ = sender.send_packet(&Send::Disconnect { reason });
+					stream.shutdown(Shutdown::Both)?;
+					return Ok(());
+				}
+				Some(pending) => (pending.sent_at + timeout, None),
+				None if now >= next_send => {
+					// pending before it's sent, so that the response can't arrive first
+					let id = rand::random();
+					state.pending = Some(Pending { id, sent_at: now });
+					next_send = now + INTERVAL;
+					(now + timeout, Some(id))
+				}
+				None => (next_send, None),
+			}
+		};
+		if let Some(id) = to_send {
+			trace!("Sending keep-alive {}", id);
+			sender.send_packet(&Send::KeepAlive(send::KeepAlive(id)))?;
+		}
+		match woken.recv_timeout(deadline.saturating_duration_since(Instant::now())) {
+			Ok(()) | Err(RecvTimeoutError::Timeout) => {}
+			Err(RecvTimeoutError::Disconnected) => return Ok(()),
+		}
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::super::PacketSender;
+	use super::KeepAlive;
+	use crate::packets::helpers::varint::VarInt;
+	use encde::Decode;
+	use std::io::Read;
+	use std::net::{TcpListener, TcpStream};
+	use std::sync::{Arc, Mutex};
+	use std::time::Duration;
+
+	fn start(timeout: Duration) -> (KeepAlive, TcpStream) {
+		let listener = TcpListener::bind(("127.0.0.1", 0)).unwrap();
+		let client = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+		let (server, _) = listener.accept().unwrap();
+		let sender = PacketSender {
+			socket: Arc::new(Mutex::new(Box::new(server.try_clone().unwrap()))),
+			compression_threshold: None,
+		};
+		(KeepAlive::start(sender, server, timeout).unwrap(), client)
+	}
+
+	/// Returns the packet ID and the rest of the packet
+	fn receive_raw(socket: &mut TcpStream) -> (i32, Vec<u8>) {
+		let len = VarInt::decode(socket).unwrap().0.try_into().unwrap();
+		let mut body = vec![0u8; len];
+		socket.read_exact(&mut body).unwrap();
+		let mut body = body.as_slice();
+		let id = VarInt::decode(&mut body).unwrap().0;
+		(id, body.to_vec())
+	}
+
+	#[test]
+	pub fn echoed_id() {
+		let (keep_alive, mut client) = start(Duration::from_secs(30));
+		let (packet_id, body) = receive_raw(&mut client);
+		assert_eq!(packet_id, 0x21);
+		let id = i64::decode(&mut body.as_slice()).unwrap();
+		assert!(keep_alive.receive(id.wrapping_add(1)).is_err());
+		assert!(keep_alive.receive(id).is_ok());
+		// no longer pending
+		assert!(keep_alive.receive(id).is_err());
+		assert!(!keep_alive.timed_out());
+	}
+
+	#[test]
+	pub fn timeout() {
+		let (keep_alive, mut client) = start(Duration::from_millis(100));
+		assert_eq!(receive_raw(&mut client).0, 0x21);
+		assert_eq!(receive_raw(&mut client).0, 0x1a);
+		assert_eq!(client.read(&mut [0u8]).unwrap(), 0);
+		assert!(keep_alive.timed_out());
+	}
+}
@@ -1,57 +1,16 @@
+use super::auth::SessionResponse;
 use super::Client;
 use crate::packets::cipher::{Cipher, CipherWrapper};
+use crate::packets::helpers::game::chat;
+use crate::packets::helpers::varint::VarInt;
+use crate::packets::helpers::wrappers::json::Json;
 use crate::packets::helpers::wrappers::std::{PrefixedArray, PrefixedBorrowedBytes, PrefixedBytes, PrefixedString};
 use crate::packets::helpers::wrappers::uuid::Uuid as UuidWrapper;
 use crate::packets::login::receive::{self, Packet as Receive};
-use crate::packets::login::send::Packet as Send;
+use crate::packets::login::send::{Packet as Send, SetCompression};
 use log::trace;
-use serde::{Deserialize, Deserializer};
 use sha::utils::{Digest, DigestExt};
 
-fn deserialize_skin_texture<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
-	use serde::de::{self, Error};
-	use std::fmt;
-	#[derive(Deserialize)]
-	struct Property {
-		name: String,
-		value: String,
-		signature: Option<String>,
-	}
-
-	struct Visitor;
-	impl<'de> de::Visitor<'de> for Visitor {
-		type Value = Vec<u8>;
-
-		fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
-			formatter.write_str("A sequence of session properties including the `textures` property")
-		}
-		fn visit_seq<S: de::SeqAccess<'de>>(self, mut access: S) -> Result<Self::Value, S::Error>
-		where
-			S::Error: Error,
-		{
-			while let Some(property) = access.next_element::<Property>()? {
-				if property.name == "textures" {
-					// TODO check signature; I can't find the Yggdrasil public key anywhere
-					return base64::decode(property.value).map_err(|err| S::Error::custom(err.to_string()));
-				}
-			}
-			Err(S::Error::custom("No `textures` property present"))
-		}
-	}
-	deserializer.deserialize_seq(Visitor)
-}
-
-#[derive(Deserialize)]
-pub struct SessionResponse {
-	#[serde(rename = "id")]
-	pub uuid: uuid::Uuid,
-	#[serde(rename = "name")]
-	pub username: String,
-	#[serde(deserialize_with = "deserialize_skin_texture")]
-	#[serde(rename = "properties")]
-	pub skin_texture: Vec<u8>,
-}
-
 fn format_minecraft_sha1(mut data: &mut [u8]) -> String {
 	use std::fmt::Write;
 	let negative = (data[0] & 0x80) == 0x80;
@@ -83,7 +42,16 @@ fn format_minecraft_sha1(mut data: &mut [u8]) -> String {
 
 #[cfg(test)]
 mod test {
+	use super::super::{Client, GlobalState};
+	use crate::packets::helpers::varint::VarInt;
+	use crate::packets::helpers::wrappers::std::PrefixedString;
+	use crate::packets::helpers::wrappers::uuid::Uuid as UuidWrapper;
+	use encde::{Decode, Encode};
+	use figment::providers::{Format, Toml};
 	use sha::utils::{Digest, DigestExt};
+	use std::io::{Read, Write};
+	use std::net::{TcpListener, TcpStream};
+
 	pub fn format_sha(plain: &str) -> String {
 		super::format_minecraft_sha1(&mut sha::sha1::Sha1::default().digest(plain.as_bytes()).to_bytes())
 	}
@@ -93,6 +61,96 @@ mod test {
 		assert_eq!("-7c9d5b0044c130109a5d7b5fb5c317c02b4e28c1", format_sha("jeb_"));
 		assert_eq!("88e16a1019277b15d58faf0541e11910eb756f6", format_sha("simon"));
 	}
+
+	fn send_raw(socket: &mut TcpStream, body: &[u8]) {
+		VarInt(body.len().try_into().unwrap()).encode(socket).unwrap();
+		socket.write_all(body).unwrap();
+	}
+	fn receive_raw(socket: &mut TcpStream) -> Vec<u8> {
+		let len = VarInt::decode(socket).unwrap().0.try_into().unwrap();
+		let mut body = vec![0u8; len];
+		socket.read_exact(&mut body).unwrap();
+		body
+	}
+
+	/// Starts a server thread for one connection, with offline authentication and the extra config given
+	fn serve_one(extra_config: &str) -> (std::thread::JoinHandle<anyhow::Result<()>>, TcpStream) {
+		let config: crate::config::Config = figment::Figment::new()
+			.merge(Toml::string(&format!("address = \"127.0.0.1\"\nlogging = []\ncompression_threshold = -1\n{}\n[auth]\ntype = \"offline\"", extra_config)))
+			.extract()
+			.unwrap();
+		let config: &'static _ = Box::leak(Box::new(config));
+		let global_state: &'static _ = Box::leak(Box::new(GlobalState::new(config).unwrap()));
+		let listener = TcpListener::bind(("127.0.0.1", 0)).unwrap();
+		let server_address = listener.local_addr().unwrap();
+		let server = std::thread::spawn(move || {
+			let (socket, client_address) = listener.accept().unwrap();
+			Client::new(socket, client_address, config, global_state).unwrap().handle()
+		});
+		(server, TcpStream::connect(server_address).unwrap())
+	}
+	/// Sends the handshake and Login Start, and checks Login Success
+	fn log_in(socket: &mut TcpStream) {
+		let mut handshake = Vec::new();
+		VarInt(0).encode(&mut handshake).unwrap();
+		VarInt(super::super::PROTOCOL_VERSION).encode(&mut handshake).unwrap();
+		PrefixedString("localhost".to_owned()).encode(&mut handshake).unwrap();
+		25565u16.encode(&mut handshake).unwrap();
+		VarInt(2).encode(&mut handshake).unwrap();
+		send_raw(socket, &handshake);
+		let mut login_start = Vec::new();
+		VarInt(0).encode(&mut login_start).unwrap();
+		PrefixedString("Notch".to_owned()).encode(&mut login_start).unwrap();
+		send_raw(socket, &login_start);
+
+		let login_success = receive_raw(socket);
+		let mut login_success = login_success.as_slice();
+		assert_eq!(VarInt::decode(&mut login_success).unwrap(), VarInt(2));
+		assert_eq!(UuidWrapper::decode(&mut login_success).unwrap().to_string(), "b50ad385-829d-3141-a216-7e7d7539ba7f");
+		assert_eq!(PrefixedString::decode(&mut login_success).unwrap().0, "Notch");
+		assert!(login_success.is_empty());
+	}
+
+	#[test]
+	pub fn offline_login() {
+		let (server, mut socket) = serve_one("");
+		log_in(&mut socket);
+
+		let mut received_join_game = false;
+		let teleport_id = loop {
+			let packet = receive_raw(&mut socket);
+			let mut packet = packet.as_slice();
+			match VarInt::decode(&mut packet).unwrap().0 {
+				0x26 => received_join_game = true,
+				0x38 => {
+					// position, rotation, and relative-ness flags
+					let mut skipped = [0u8; 8 * 3 + 4 * 2 + 1];
+					packet.read_exact(&mut skipped).unwrap();
+					break VarInt::decode(&mut packet).unwrap();
+				}
+				_ => {}
+			}
+		};
+		assert!(received_join_game);
+		let mut confirm_teleport = Vec::new();
+		VarInt(0).encode(&mut confirm_teleport).unwrap();
+		teleport_id.encode(&mut confirm_teleport).unwrap();
+		send_raw(&mut socket, &confirm_teleport);
+
+		drop(socket);
+		server.join().unwrap().unwrap();
+	}
+
+	#[test]
+	pub fn server_full() {
+		let (server, mut socket) = serve_one("max_players = 0");
+		log_in(&mut socket);
+		let disconnect = receive_raw(&mut socket);
+		let mut disconnect = disconnect.as_slice();
+		assert_eq!(VarInt::decode(&mut disconnect).unwrap(), VarInt(0x1a));
+		assert!(PrefixedString::decode(&mut disconnect).unwrap().0.contains("The server is full!"));
+		assert!(server.join().unwrap().is_err());
+	}
 }
 
 fn rsa_private_decrypt(key: &openssl::rsa::RsaRef<openssl::pkey::Private>, data: &[u8]) -> anyhow::Result<Vec<u8>> {
@@ -141,51 +199,70 @@ impl Client {
 		use cfb8::cipher::NewCipher;
 		Ok(Cipher::new_from_slices(shared_secret, shared_secret)?)
 	}
-	fn get_session(&mut self, username: String, shared_secret: &[u8]) -> anyhow::Result<SessionResponse> {
+	/// Each direction has its own cipher state, so both start from the shared secret
+	fn enable_encryption(&mut self, shared_secret: &[u8]) -> anyhow::Result<()> {
+		let reader = std::mem::replace(&mut self.reader, Box::new(std::io::empty()));
+		self.reader = Box::new(CipherWrapper::new(reader, Self::make_cipher(shared_secret)?));
+		// PANICS: nothing else has access to the sender yet
+		let mut writer = self.sender.socket.lock().unwrap();
+		let inner = std::mem::replace(&mut *writer, Box::new(std::io::sink()));
+		*writer = Box::new(CipherWrapper::new(inner, Self::make_cipher(shared_secret)?));
+		Ok(())
+	}
+	fn compute_server_hash(&self, shared_secret: &[u8]) -> String {
 		let mut auth_hash = sha::sha1::Sha1::default().digest(b"").digest(shared_secret).digest(&self.global_state.rsa_public_der).to_bytes();
-		let auth_hash = format_minecraft_sha1(&mut auth_hash);
-		trace!("Making request to session server");
-		let response = reqwest::blocking::get(reqwest::Url::parse_with_params(
-			"https://sessionserver.mojang.com/session/minecraft/hasJoined",
-			&[("username", username.as_str()), ("serverId", auth_hash.as_str())],
-		)?)?
-		.error_for_status()?;
-		// This is a bit of a kludge, but we have to handle the No Content response somehow
-		if response.status() == reqwest::StatusCode::NO_CONTENT {
-			trace!("Session server returned No Content; using other endpoints to synthesize the data");
-			#[derive(Deserialize)]
-			struct UuidForUsernameResponse {
-				// there is also the "name" field but we can ignore it
-				#[serde(rename = "id")]
-				uuid: uuid::Uuid,
-			}
-			let uuid: UuidForUsernameResponse = reqwest::blocking::get(format!("https://api.mojang.com/users/profiles/minecraft/{}", username))?.error_for_status()?.json()?;
-			let uuid = uuid.uuid;
-			Ok(reqwest::blocking::get(format!("https://sessionserver.mojang.com/session/minecraft/profile/{}", uuid))?.error_for_status()?.json()?)
-		} else {
-			Ok(response.json()?)
+		format_minecraft_sha1(&mut auth_hash)
+	}
+	/// Tells the client why it couldn't join before passing on the error
+	fn check_authenticated(&mut self, result: anyhow::Result<SessionResponse>) -> anyhow::Result<SessionResponse> {
+		if let Err(err) = &result {
+			let reason = Json(chat::Content {
+				text: format!("Failed to authenticate: {}", err),
+				..Default::default()
+			});
+			// the connection is being dropped anyway, so an error here isn't important
+			let _ = self.send_packet(&Send::Disconnect { reason });
+		}
+		result
+	}
+	fn enable_compression(&mut self) -> anyhow::Result<()> {
+		// negative values disable compression
+		if let Ok(threshold) = usize::try_from(self.config.compression_threshold) {
+			trace!("Enabling compression with threshold {}", threshold);
+			self.send_packet(&Send::SetCompression(SetCompression {
+				threshold: VarInt(self.config.compression_threshold),
+			}))?;
+			self.sender.compression_threshold = Some(threshold);
 		}
+		Ok(())
 	}
-	fn enter_play(mut self, session: super::login::SessionResponse) -> anyhow::Result<()> {
+	fn enter_play(mut self, session: SessionResponse) -> anyhow::Result<()> {
+		self.enable_compression()?;
 		trace!("Sending login success packet");
 		let packet = crate::packets::login::send::Packet::LoginSuccess {
 			uuid: UuidWrapper(session.uuid),
-			username: PrefixedString(session.username),
+			username: PrefixedString(session.username.clone()),
 		};
 		self.send_packet(&packet)?;
-		self.handle_play()
+		self.handle_play(session)
 	}
 
 	pub(super) fn handle_login(mut self) -> anyhow::Result<()> {
 		trace!("Entering login state");
 		let username = self.receive_login_start()?;
 		trace!("Connection username: {}", username);
-		// TODO offline mode
+		let authenticator = &self.global_state.authenticator;
+		if !authenticator.uses_encryption() {
+			trace!("Authenticating without encryption");
+			let session_response = self.check_authenticated(authenticator.authenticate(username, None))?;
+			return self.enter_play(session_response);
+		}
 		let verify_token = self.request_encryption()?;
 		let shared_secret = self.receive_shared_secret(verify_token)?;
-		let session_response = self.get_session(username, &shared_secret)?;
-		let cipher = Self::make_cipher(&shared_secret)?;
-		self.socket = Box::new(CipherWrapper::new(self.socket, cipher));
+		let server_hash = self.compute_server_hash(&shared_secret);
+		// the client encrypts from its encryption response onwards, including the disconnect if authentication fails
+		self.enable_encryption(&shared_secret)?;
+		let session_response = self.check_authenticated(authenticator.authenticate(username, Some(&server_hash)))?;
 		self.enter_play(session_response)
 	}
 }
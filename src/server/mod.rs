@@ -1,19 +1,36 @@
 use crate::config::Config;
+use crate::packets::framing;
 use crate::packets::handshake::receive::Packet as HandshakeReceive;
-use crate::packets::helpers::varint::VarInt;
+use crate::packets::helpers::game::dimension;
+use crate::packets::helpers::position::PackedPosition;
+use crate::packets::legacy;
 use crate::packets::ProtocolState;
-use encde::{Decode, DecodeSized, Encode};
+use crate::world::{player_data, World};
+use encde::{DecodeSized, Encode};
 use log::{debug, info, trace};
+use std::collections::HashMap;
 use std::io::{Read, Write};
 use std::net::TcpListener;
 use std::net::{SocketAddr, TcpStream};
+use std::path::PathBuf;
+use std::sync::{mpsc, Arc, Mutex, RwLock};
+use window::chest::{self, Chest};
+use window::furnace::Furnace;
+use window::{Container, Shared};
 
+mod auth;
+mod chunk_view;
+mod entities;
+mod keep_alive;
 mod login;
 mod play;
+mod players;
+mod recipe_book;
 mod status;
+mod window;
 
 const PROTOCOL_VERSION: i32 = 757;
-const SERVER_VERSION: &str = "1.18.1";
+pub(crate) const SERVER_VERSION: &str = "1.18.1";
 
 pub struct Server {
 	config: &'static Config,
@@ -24,15 +41,41 @@ impl Server {
 	pub fn new(config: &'static Config) -> anyhow::Result<Self> {
 		Ok(Self {
 			config,
-			global_state: Box::leak(Box::new(GlobalState::new()?)),
+			global_state: Box::leak(Box::new(GlobalState::new(config)?)),
 		})
 	}
 	pub fn listen(self) -> anyhow::Result<()> {
 		let listener = TcpListener::bind((self.config.address, self.config.port))?;
 		info!("Listening on {}:{}", self.config.address, self.config.port);
+		let global_state = self.global_state;
+		ctrlc::set_handler(move || {
+			info!("Saving the world before shutting down");
+			global_state.save_players();
+			if let Err(err) = global_state.save_world() {
+				log::error!("Failed to save the world: {:#}", err);
+			}
+			std::process::exit(0);
+		})?;
+		std::thread::Builder::new().name("world".to_owned()).spawn(move || loop {
+			std::thread::sleep(play::TICK);
+			if let Err(err) = global_state.dispatch_world_changes() {
+				log::error!("Failed to send changes to the world: {:#}", err);
+			}
+		})?;
+		if self.config.autosave_interval > 0 {
+			let interval = std::time::Duration::from_secs(self.config.autosave_interval);
+			std::thread::Builder::new().name("autosave".to_owned()).spawn(move || loop {
+				std::thread::sleep(interval);
+				debug!("Autosaving");
+				global_state.save_players();
+				if let Err(err) = global_state.save_world() {
+					log::error!("Autosave failed: {:#}", err);
+				}
+			})?;
+		}
 		loop {
 			let (socket, client_address) = listener.accept()?;
-			let client = Client::new(socket, client_address, self.config, self.global_state);
+			let client = Client::new(socket, client_address, self.config, self.global_state)?;
 			std::thread::Builder::new().name(client_address.to_string()).spawn(move || {
 				if let Err(err) = client.handle() {
 					log::error!("{:#}", err);
@@ -45,56 +88,203 @@ impl Server {
 struct GlobalState {
 	rsa_key: openssl::rsa::Rsa<openssl::pkey::Private>,
 	rsa_public_der: Vec<u8>,
+	authenticator: Box<dyn auth::Authenticator>,
+	dimension_codec: dimension::Codec,
+	entities: RwLock<entities::Entities>,
+	players: RwLock<players::Players>,
+	world: RwLock<World>,
+	/// Encoded packets about changes to the world, for each player by their entity ID.
+	/// They go through the player's own thread, so that they can't overtake the chunks they change.
+	world_updates: Mutex<HashMap<i32, mpsc::Sender<Arc<[u8]>>>>,
+	/// The world directory that player files are kept in, if any. It's locked while they're written, so that an autosave can't overwrite what a player saved when leaving with older data.
+	player_files: Mutex<Option<PathBuf>>,
+	/// The contents of chests, barrels, and furnaces, made when they're first opened.
+	/// They're lost when the server stops, since saving them needs block entities.
+	containers: Mutex<HashMap<PackedPosition, Arc<Mutex<Shared>>>>,
 }
 
 impl GlobalState {
-	fn new() -> anyhow::Result<Self> {
+	fn new(config: &Config) -> anyhow::Result<Self> {
 		debug!("Generating RSA key");
 		let rsa_key = openssl::rsa::Rsa::generate(1024)?;
 		debug!("Finished generating RSA key");
 		let rsa_public_der = rsa_key.public_key_to_der()?;
-		Ok(Self { rsa_public_der, rsa_key })
+		let authenticator = <dyn auth::Authenticator>::from_config(&config.auth)?;
+		let dimension_codec: dimension::Codec = serde_json::from_str(include_str!("../../assets/dimension_codec.json"))?;
+		let dimension_type = dimension_codec.dimension_type(play::DIMENSION).ok_or_else(|| anyhow::anyhow!("Dimension {} is missing from the dimension codec", play::DIMENSION))?;
+		let world = RwLock::new(World::new(dimension_type, dimension_codec.biomes(), config.world_directory.as_deref(), &config.generator, config.seed)?);
+		Ok(Self {
+			rsa_public_der,
+			rsa_key,
+			authenticator,
+			dimension_codec,
+			entities: RwLock::default(),
+			players: RwLock::default(),
+			world,
+			world_updates: Mutex::default(),
+			player_files: Mutex::new(config.world_directory.clone()),
+			containers: Mutex::default(),
+		})
+	}
+	fn save_world(&self) -> anyhow::Result<()> {
+		// PANICS: the lock is only poisoned if another thread panicked while modifying the world
+		self.world.write().unwrap().save()
+	}
+	/// Passes the block and light changes made since the last call on to the players who have the columns loaded
+	fn dispatch_world_changes(&self) -> encde::Result<()> {
+		let changes: Vec<_> = {
+			// PANICS: the lock is only poisoned if another thread panicked while modifying the world
+			let mut world = self.world.write().unwrap();
+			// the blocks go first so that the light matches them when it arrives
+			let mut changes = world.take_changes();
+			changes.extend(world.take_light_changes());
+			changes.into_iter().map(|(position, packet)| (world.viewers(position).collect::<Vec<_>>(), packet)).collect()
+		};
+		if changes.is_empty() {
+			return Ok(());
+		}
+		// PANICS: the lock is only poisoned if another thread panicked while a player was joining or leaving
+		let world_updates = self.world_updates.lock().unwrap();
+		for (viewers, packet) in changes {
+			let packet: Arc<[u8]> = encde::util::encode_to_vec(&packet)?.into();
+			for viewer in viewers {
+				if let Some(updates) = world_updates.get(&viewer) {
+					// the player is leaving if their end is gone, so they don't need the update
+					let _ = updates.send(Arc::clone(&packet));
+				}
+			}
+		}
+		Ok(())
+	}
+	/// The container of the chest, barrel, or furnace at the position, which everyone who opens it shares.
+	/// Both halves of a double chest have the same one.
+	fn block_container(&self, position: PackedPosition) -> Option<Arc<Mutex<Shared>>> {
+		let (key, make): (_, fn() -> Box<dyn Container>) = {
+			// PANICS: the lock is only poisoned if another thread panicked while modifying the world
+			let world = self.world.read().unwrap();
+			let block = world.get_block(position)?;
+			match block.name()? {
+				name @ ("minecraft:chest" | "minecraft:trapped_chest") => {
+					// the other half has to point back at this one
+					let double = chest::other_half(block)
+						.map(|((x, z), first)| (PackedPosition::new(position.x() + x, position.y(), position.z() + z), first))
+						.filter(|&(other, first)| world.get_block(other).filter(|other| other.name() == Some(name)).and_then(chest::other_half).map(|(_, other_first)| other_first) == Some(!first));
+					match double {
+						// kept under the half that comes first
+						Some((other, first)) => (if first { position } else { other }, || Box::new(Chest::new(6, "Large Chest"))),
+						None => (position, || Box::new(Chest::new(3, "Chest"))),
+					}
+				}
+				"minecraft:barrel" => (position, || Box::new(Chest::barrel())),
+				"minecraft:furnace" => (position, || Box::new(Furnace::new())),
+				_ => return None,
+			}
+		};
+		// PANICS: the lock is only poisoned if another thread panicked while opening a container
+		Some(Arc::clone(self.containers.lock().unwrap().entry(key).or_insert_with(|| Shared::new(make()))))
+	}
+	/// The player's saved data, which is empty if they're new or the world has no directory
+	fn load_player(&self, uuid: uuid::Uuid) -> anyhow::Result<HashMap<String, nbt::Value>> {
+		// PANICS: the lock is only poisoned if another thread panicked while writing a player file
+		match &*self.player_files.lock().unwrap() {
+			Some(directory) => Ok(player_data::load(directory, uuid)?.unwrap_or_default()),
+			None => Ok(HashMap::new()),
+		}
+	}
+	/// Does nothing if the world has no directory
+	fn save_player(&self, uuid: uuid::Uuid, data: &HashMap<String, nbt::Value>) -> anyhow::Result<()> {
+		// PANICS: the lock is only poisoned if another thread panicked while writing a player file
+		match &*self.player_files.lock().unwrap() {
+			Some(directory) => player_data::save(directory, uuid, data),
+			None => Ok(()),
+		}
+	}
+	/// Saves everyone who is online, logging failures so that one bad file doesn't stop the rest
+	fn save_players(&self) {
+		// PANICS: the lock is only poisoned if another thread panicked while writing a player file
+		let directory = self.player_files.lock().unwrap();
+		let directory = match &*directory {
+			Some(directory) => directory,
+			None => return,
+		};
+		// copied so that joining and leaving isn't held up by the files being written
+		// PANICS: the lock is only poisoned if another thread panicked while modifying the players
+		let online: Vec<_> = self.players.read().unwrap().iter().map(|(uuid, player)| (uuid, player.username.clone(), player.saved_data.clone())).collect();
+		for (uuid, username, data) in &online {
+			if let Err(err) = player_data::save(directory, *uuid, data) {
+				log::error!("Failed to save {}: {:#}", username, err);
+			}
+		}
+		debug!("Saved {} players", online.len());
 	}
 }
 
-trait ClientSocket: Read + Write + Send {}
-impl<T: Read + Write + Send> ClientSocket for T {}
+/// `None` once the client has closed the connection
+fn read_packet<P: DecodeSized>(reader: &mut dyn Read, compression_threshold: Option<usize>) -> encde::Result<Option<P>> {
+	match framing::read_frame(reader, compression_threshold)? {
+		Some(packet_data) => encde::util::decode_from_entire_slice(&packet_data).map(Some),
+		None => Ok(None),
+	}
+}
+
+/// The sending half of a connection, which can be shared with background threads such as the keep-alive timer
+#[derive(Clone)]
+struct PacketSender {
+	socket: Arc<Mutex<Box<dyn Write + Send>>>,
+	/// Set once Set Compression has been sent
+	compression_threshold: Option<usize>,
+}
+
+impl PacketSender {
+	fn send_packet<P: Encode>(&self, data: &P) -> encde::Result<()> {
+		self.send_encoded(&encde::util::encode_to_vec(data)?)
+	}
+	/// Sends a packet that has already been encoded, without its frame
+	fn send_encoded(&self, packet_data: &[u8]) -> encde::Result<()> {
+		// PANICS: the lock is only poisoned if another thread panicked while sending, in which case the stream is corrupt anyway
+		let mut socket = self.socket.lock().unwrap();
+		framing::write_frame(&mut *socket, packet_data, self.compression_threshold)
+	}
+}
 
 struct Client {
-	socket: Box<dyn ClientSocket>,
+	reader: Box<dyn Read + Send>,
+	sender: PacketSender,
+	/// Used to forcibly close the connection from other threads
+	stream: TcpStream,
 	config: &'static Config,
 	global_state: &'static GlobalState,
 }
 
 impl Client {
 	fn receive_packet<P: DecodeSized>(&mut self) -> encde::Result<Option<P>> {
-		let packet_len = match VarInt::decode(&mut self.socket) {
-			Ok(VarInt(packet_len)) => packet_len.try_into().map_err(|err| encde::Error::Custom(Box::new(err)))?,
-			Err(encde::Error::Io(err)) if err.kind() == std::io::ErrorKind::UnexpectedEof => return Ok(None),
-			Err(err) => return Err(err),
-		};
-		let mut packet_data = vec![0u8; packet_len];
-		self.socket.read_exact(&mut packet_data)?;
-		encde::util::decode_from_entire_slice(&packet_data).map(Some)
+		read_packet(&mut self.reader, self.sender.compression_threshold)
 	}
 	fn send_packet<P: Encode>(&mut self, data: &P) -> encde::Result<()> {
-		let packet_data = encde::util::encode_to_vec(data)?;
-		VarInt(packet_data.len().try_into().map_err(|err| encde::Error::Custom(Box::new(err)))?).encode(&mut self.socket)?;
-		self.socket.write_all(&packet_data)?;
-		Ok(())
+		self.sender.send_packet(data)
 	}
 }
 
 impl Client {
-	pub fn new(socket: TcpStream, address: SocketAddr, config: &'static crate::config::Config, global_state: &'static GlobalState) -> Self {
+	pub fn new(socket: TcpStream, address: SocketAddr, config: &'static crate::config::Config, global_state: &'static GlobalState) -> std::io::Result<Self> {
 		trace!("New connection from {}", &address);
-		Self {
-			socket: Box::new(socket),
+		let writer: Box<dyn Write + Send> = Box::new(socket.try_clone()?);
+		Ok(Self {
+			reader: Box::new(socket.try_clone()?),
+			sender: PacketSender {
+				socket: Arc::new(Mutex::new(writer)),
+				compression_threshold: None,
+			},
+			stream: socket,
 			config,
 			global_state,
-		}
+		})
 	}
 	pub fn handle(mut self) -> anyhow::Result<()> {
+		let mut first_byte = [0u8; 1];
+		if self.stream.peek(&mut first_byte)? == 1 && first_byte[0] == legacy::PING {
+			return self.handle_legacy_status().map_err(|err| err.context("Legacy status"));
+		}
 		let HandshakeReceive::Handshake(handshake) = self.receive_packet()?.ok_or_else(|| anyhow::anyhow!("Client closed connection").context("Handshake"))?;
 		trace!("Client handshake: {:?}", handshake);
 		match handshake.next_state {
@@ -114,3 +304,69 @@ impl Client {
 		}
 	}
 }
+
+#[cfg(test)]
+mod test {
+	use super::window::Kind;
+	use super::GlobalState;
+	use crate::packets::helpers::position::PackedPosition;
+	use crate::world::{BlockState, ChunkPosition};
+	use figment::providers::{Format, Toml};
+	use std::sync::Arc;
+
+	fn global_state() -> GlobalState {
+		let config: crate::config::Config = figment::Figment::new()
+			.merge(Toml::string("address = \"127.0.0.1\"\nlogging = []\n[generator]\ntype = \"void\"\n[auth]\ntype = \"offline\""))
+			.extract()
+			.unwrap();
+		GlobalState::new(Box::leak(Box::new(config))).unwrap()
+	}
+
+	#[test]
+	pub fn world_changes() {
+		let global_state = global_state();
+		let (sender, updates) = std::sync::mpsc::channel();
+		global_state.world_updates.lock().unwrap().insert(7, sender);
+		{
+			let mut world = global_state.world.write().unwrap();
+			world.watch_column(ChunkPosition { x: 0, z: 0 }, 7).unwrap();
+			world.watch_column(ChunkPosition { x: 1, z: 0 }, 8).unwrap();
+			world.set_block(PackedPosition::new(1, 2, 3), BlockState(1));
+			world.set_block(PackedPosition::new(17, 2, 3), BlockState(1));
+		}
+		global_state.dispatch_world_changes().unwrap();
+		// only the changes in the column that player 7 has loaded, as a Block Change and then an Update Light
+		let received: Vec<_> = updates.try_iter().map(|packet| packet[0]).collect();
+		assert_eq!(received, [0x0c, 0x25]);
+	}
+
+	#[test]
+	pub fn block_containers() {
+		let global_state = global_state();
+		let blocks = [
+			((0, 0), "chest[facing=north,type=left]"),
+			((1, 0), "chest[facing=north,type=right]"),
+			// a left half with nothing to its right
+			((0, 5), "trapped_chest[facing=east,type=left]"),
+			((5, 5), "barrel"),
+			((6, 5), "furnace"),
+			((7, 5), "stone"),
+		];
+		{
+			let mut world = global_state.world.write().unwrap();
+			world.watch_column(ChunkPosition { x: 0, z: 0 }, 7).unwrap();
+			for ((x, z), block) in blocks {
+				world.set_block(PackedPosition::new(x, 2, z), block.parse().unwrap());
+			}
+		}
+		let open = |x, z| global_state.block_container(PackedPosition::new(x, 2, z));
+		let kind = |x, z| open(x, z).map(|shared| shared.lock().unwrap().container().kind());
+		assert!(Arc::ptr_eq(&open(0, 0).unwrap(), &open(1, 0).unwrap()));
+		assert!(Arc::ptr_eq(&open(5, 5).unwrap(), &open(5, 5).unwrap()));
+		assert_eq!(kind(0, 0), Some(Kind::Generic9x6));
+		assert_eq!(kind(0, 5), Some(Kind::Generic9x3));
+		assert_eq!(kind(5, 5), Some(Kind::Generic9x3));
+		assert_eq!(kind(6, 5), Some(Kind::Furnace));
+		assert_eq!(kind(7, 5), None);
+	}
+}
@@ -1,10 +1,644 @@
+use super::auth::SessionResponse;
+use super::chunk_view::{ChunkView, MIN_VIEW_DISTANCE};
+use super::entities::{self, Entity, Tracker};
+use super::keep_alive::KeepAlive;
+use super::players::{Online, PlayerList};
+use super::recipe_book::RecipeBook;
+use super::window::anvil::Anvil;
+use super::window::crafting_table::CraftingTable;
+use super::window::inventory::{self, Inventory};
+use super::window::session::Session;
+use super::window::{Shared, Window};
 use super::Client;
-use crate::packets::play::{receive::Packet as Receive, send::Packet as Send};
-use log::debug;
+use crate::items::recipes;
+use crate::items::stack::ItemStack;
+use crate::packets::helpers::game::slot::IndexedSlot;
+use crate::packets::helpers::game::{chat, chunk, metadata};
+use crate::packets::helpers::misc;
+use crate::packets::helpers::position::{F64Position, PackedPosition};
+use crate::packets::helpers::rotation::F32Rotation;
+use crate::packets::helpers::varint::VarInt;
+use crate::packets::helpers::wrappers::json::Json;
+use crate::packets::helpers::wrappers::nbt::NbtData;
+use crate::packets::helpers::wrappers::std::{PrefixedString, PrefixedVec};
+use crate::packets::play::send::Packet as Send;
+use crate::packets::play::{receive, receive::Packet as Receive, send};
+use crate::world::ChunkPosition;
+use log::{debug, trace};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+pub(super) const DIMENSION: &str = "minecraft:overworld";
+/// How often the player is brought up to date with the world around them, whether or not the client sends anything
+pub(super) const TICK: Duration = Duration::from_millis(50);
+
+/// The state of a client that has joined the game
+struct Player {
+	session: SessionResponse,
+	entity_id: i32,
+	position: F64Position,
+	rotation: F32Rotation,
+	on_ground: bool,
+	/// Set until the client confirms our most recent teleport; movement is ignored in the meantime, like in vanilla
+	pending_teleport: Option<i32>,
+	next_teleport_id: i32,
+	keep_alive: KeepAlive,
+	/// Round-trip time of the most recent keep-alive
+	latency: Duration,
+	/// The lower of the client's render distance and the server's view distance
+	view_distance: u8,
+	chunk_view: ChunkView,
+	entity_tracker: Tracker,
+	player_list: PlayerList,
+	game_mode: misc::GameMode,
+	inventory: Inventory,
+	/// For window 0, which is always open
+	inventory_session: Session,
+	container: Option<OpenContainer>,
+	/// Cycles from 1 to 100, like in vanilla
+	next_window_id: u8,
+	/// See `metadata::skin_parts`
+	skin_parts: u8,
+	/// Copied to the player's saved data in `Players` whenever it changes
+	recipe_book: RecipeBook,
+	/// Encoded packets about changes to the chunks the player has loaded
+	world_updates: Receiver<Arc<[u8]>>,
+}
+
+/// A container that the player has open on top of their inventory.
+/// Containers that belong to blocks are shared with the other players who have them open, and keep their contents when closed.
+struct OpenContainer {
+	container: Arc<Mutex<Shared>>,
+	session: Session,
+}
 
 impl Client {
-	pub(super) fn handle_play(mut self) -> anyhow::Result<()> {
-		debug!("Entering play state");
+	fn send_join_game(&mut self, player: &Player) -> anyhow::Result<()> {
+		let codec = &self.global_state.dimension_codec;
+		let dimension_type = codec.dimension_type(DIMENSION).ok_or_else(|| anyhow::anyhow!("Dimension {} is missing from the dimension codec", DIMENSION))?;
+		let view_distance = VarInt(self.config.view_distance.into());
+		// PANICS: the lock is only poisoned if another thread panicked while modifying the world
+		let (is_flat, hashed_seed) = {
+			let world = self.global_state.world.read().unwrap();
+			(world.is_flat(), world.hashed_seed())
+		};
+		self.send_packet(&Send::JoinGame(send::JoinGame {
+			entity_id: player.entity_id,
+			is_hardcore: false,
+			new_game_mode: player.game_mode,
+			old_game_mode: misc::OptionalGameMode::None,
+			dimension_names: PrefixedVec::new(vec![PrefixedString(DIMENSION.to_owned())]),
+			dimension_codec: NbtData(codec),
+			dimension_data: NbtData(dimension_type),
+			current_dimension: PrefixedString(DIMENSION.to_owned()),
+			hashed_seed,
+			max_players: VarInt(self.config.max_players.try_into().unwrap_or(i32::MAX)),
+			view_distance,
+			simulation_distance: view_distance,
+			reduced_debug_info: false,
+			enable_respawn_screen: true,
+			is_debug: false,
+			is_flat,
+		}))?;
+		Ok(())
+	}
+	/// Sends the chunks that have come into view and unloads the ones that have gone out of view
+	fn update_chunk_view(&mut self, player: &mut Player) -> anyhow::Result<()> {
+		let center = ChunkPosition {
+			x: (player.position.x.floor() as i32) >> 4,
+			z: (player.position.z.floor() as i32) >> 4,
+		};
+		let changes = player.chunk_view.update(center, player.view_distance);
+		if changes.view_distance_changed {
+			let view_distance = VarInt(player.view_distance.into());
+			self.send_packet(&Send::UpdateRenderDistance(send::UpdateRenderDistance(view_distance)))?;
+			self.send_packet(&Send::UpdateSimulationDistance(send::UpdateSimulationDistance(view_distance)))?;
+		}
+		// the client ignores chunks that are too far from its center
+		if changes.center_changed {
+			self.send_packet(&Send::UpdateActiveChunk(send::UpdateActiveChunk(chunk::Position { x: VarInt(center.x), z: VarInt(center.z) })))?;
+		}
+		for position in changes.unload {
+			// PANICS: the lock is only poisoned if another thread panicked while modifying the world
+			self.global_state.world.write().unwrap().unwatch_column(position, player.entity_id)?;
+			self.send_packet(&Send::UnloadChunk(send::UnloadChunk { chunk_position: position }))?;
+		}
+		if !changes.load.is_empty() {
+			trace!("Sending {} chunks around {:?}", changes.load.len(), center);
+		}
+		for position in changes.load {
+			// PANICS: the lock is only poisoned if another thread panicked while modifying the world
+			let packet = self.global_state.world.write().unwrap().watch_column(position, player.entity_id)?;
+			self.send_packet(&Send::UpdateChunkData(packet))?;
+		}
+		Ok(())
+	}
+	fn teleport(&mut self, player: &mut Player, position: F64Position, rotation: F32Rotation) -> anyhow::Result<()> {
+		let teleport_id = player.next_teleport_id;
+		player.next_teleport_id = player.next_teleport_id.wrapping_add(1);
+		player.pending_teleport = Some(teleport_id);
+		player.position = position;
+		player.rotation = rotation;
+		self.send_packet(&Send::PlayerPositionRotationUpdate(send::PlayerPositionRotationUpdate {
+			new_position: position,
+			new_rotation: rotation,
+			// all absolute
+			are_fields_relative: 0,
+			teleport_id: VarInt(teleport_id),
+			dismount_vehicle: false,
+		}))?;
+		self.update_chunk_view(player)?;
 		Ok(())
 	}
+	/// Publishes the player's own latency and game mode and sends who has joined or left
+	fn sync_player_list(&mut self, player: &mut Player) -> anyhow::Result<()> {
+		let packets = {
+			// PANICS: the lock is only poisoned if another thread panicked while modifying the players
+			let mut players = self.global_state.players.write().unwrap();
+			if let Some(online) = players.get_mut(player.session.uuid) {
+				online.game_mode = player.game_mode;
+				online.ping = player.latency.as_millis().try_into().unwrap_or(i32::MAX);
+			}
+			player.player_list.update(&players)
+		};
+		for packet in packets {
+			self.send_packet(&packet)?;
+		}
+		Ok(())
+	}
+	/// Publishes the player's own movement and sends the movement of the entities around them
+	fn sync_entities(&mut self, player: &mut Player) -> anyhow::Result<()> {
+		let packets = {
+			// PANICS: the lock is only poisoned if another thread panicked while modifying the entities
+			let mut entities = self.global_state.entities.write().unwrap();
+			if let Some(entity) = entities.get_mut(player.entity_id) {
+				entity.position = player.position;
+				entity.rotation = player.rotation;
+				entity.head_yaw = player.rotation.yaw;
+				entity.on_ground = player.on_ground;
+				entity.skin_parts = player.skin_parts;
+			}
+			// other players are spawned once they're in this player's list, which it has to be told about first
+			let player_list = &player.player_list;
+			player.entity_tracker.update(&entities, player.position, player.view_distance, |uuid| player_list.contains(uuid))
+		};
+		for packet in packets {
+			self.send_packet(&packet)?;
+		}
+		Ok(())
+	}
+	fn sync_inventory(&mut self, player: &mut Player) -> anyhow::Result<()> {
+		for packet in player.inventory_session.sync(&player.inventory.view()) {
+			self.send_packet(&packet)?;
+		}
+		self.discover_recipes(player)
+	}
+	/// Unlocks the recipes for any new items in the player's inventory
+	fn discover_recipes(&mut self, player: &mut Player) -> anyhow::Result<()> {
+		let items: Vec<_> = (0..inventory::SLOTS).filter_map(|slot| player.inventory.get(slot)).map(|stack| stack.item).collect();
+		let added = player.recipe_book.discover(items);
+		if !added.is_empty() {
+			trace!("Unlocked {} recipes", added.len());
+			self.send_packet(&player.recipe_book.add_packet(added))?;
+			self.store_recipe_book(player);
+		}
+		Ok(())
+	}
+	fn store_recipe_book(&self, player: &Player) {
+		// PANICS: the lock is only poisoned if another thread panicked while modifying the players
+		if let Some(online) = self.global_state.players.write().unwrap().get_mut(player.session.uuid) {
+			online.saved_data.insert("recipeBook".to_owned(), player.recipe_book.to_nbt());
+		}
+	}
+	fn sync_window(&mut self, player: &mut Player) -> anyhow::Result<()> {
+		let open = match &mut player.container {
+			Some(open) => open,
+			None => return Ok(()),
+		};
+		let packets = {
+			// PANICS: the lock is only poisoned if another thread panicked while using the container
+			let mut shared = open.container.lock().unwrap();
+			shared.tick();
+			open.session.sync(&player.inventory.view_with(shared.container()))
+		};
+		for packet in packets {
+			self.send_packet(&packet)?;
+		}
+		self.discover_recipes(player)
+	}
+	/// Opens the container on top of the player's inventory, closing whatever was open before
+	fn open_window(&mut self, player: &mut Player, container: Arc<Mutex<Shared>>) -> anyhow::Result<()> {
+		self.close_window(player, true)?;
+		let window_id = player.next_window_id;
+		player.next_window_id = player.next_window_id % 100 + 1;
+		let mut session = Session::new(window_id);
+		let (open_packet, packets) = {
+			// PANICS: the lock is only poisoned if another thread panicked while using the container
+			let mut shared = container.lock().unwrap();
+			shared.tick();
+			let open_packet = Send::OpenWindow(send::OpenWindow {
+				window_id: VarInt(window_id.into()),
+				window_type: VarInt(shared.container().kind() as i32),
+				window_title: Json(chat::Content {
+					text: shared.container().title().to_owned(),
+					..Default::default()
+				}),
+			});
+			(open_packet, session.full_sync(&player.inventory.view_with(shared.container())))
+		};
+		self.send_packet(&open_packet)?;
+		for packet in packets {
+			self.send_packet(&packet)?;
+		}
+		player.container = Some(OpenContainer { container, session });
+		Ok(())
+	}
+	/// Gives the cursor and whatever the container doesn't keep back to the player. `notify` is for when the server closes the window rather than the client.
+	fn close_window(&mut self, player: &mut Player, notify: bool) -> anyhow::Result<()> {
+		let mut open = match player.container.take() {
+			Some(open) => open,
+			None => return Ok(()),
+		};
+		if notify {
+			self.send_packet(&Send::CloseWindow(send::CloseWindow { window_id: open.session.window_id() }))?;
+		}
+		// PANICS: the lock is only poisoned if another thread panicked while using the container
+		let leftovers: Vec<_> = open.session.close().into_iter().chain(open.container.lock().unwrap().container().close()).collect();
+		let dropped = player.inventory.insert_all(leftovers);
+		self.drop_items(player, dropped);
+		self.sync_inventory(player)
+	}
+	/// Fills the crafting grid from the recipe book, or shows the player what they're missing
+	fn select_recipe(&mut self, player: &mut Player, select: receive::SelectCraftRecipe) -> anyhow::Result<()> {
+		let recipe = match recipes::registry().get(&select.recipe.0) {
+			Some(recipe) if player.recipe_book.is_unlocked(&select.recipe.0) => recipe,
+			_ => {
+				debug!("Ignoring selection of unknown or locked recipe {}", select.recipe.0);
+				return Ok(());
+			}
+		};
+		let placed = match &mut player.container {
+			_ if select.window_id == inventory::WINDOW_ID as i8 => player.inventory.view().place_recipe(recipe, select.make_all),
+			// PANICS: the lock is only poisoned if another thread panicked while using the container
+			Some(open) if open.session.window_id() as i8 == select.window_id => player.inventory.view_with(open.container.lock().unwrap().container()).place_recipe(recipe, select.make_all),
+			_ => {
+				debug!("Ignoring recipe selection in window {}, which isn't open", select.window_id);
+				return Ok(());
+			}
+		};
+		if !placed {
+			// the client shows a ghost of the recipe in the grid
+			self.send_packet(&Send::AcceptCraftRecipeSelection(send::AcceptCraftRecipeSelection {
+				window_id: select.window_id,
+				recipe: select.recipe,
+			}))?;
+		}
+		self.sync_inventory(player)?;
+		self.sync_window(player)
+	}
+	/// Opens the block's window if it has one. Returns whether it did.
+	fn use_block(&mut self, player: &mut Player, location: PackedPosition) -> anyhow::Result<bool> {
+		// PANICS: the lock is only poisoned if another thread panicked while modifying the world
+		let block = self.global_state.world.read().unwrap().get_block(location);
+		// what's left in these is given back when they're closed, so each player gets their own
+		let container = match block.and_then(|block| block.name()) {
+			Some("minecraft:crafting_table") => Shared::new(Box::new(CraftingTable::new())),
+			Some("minecraft:anvil" | "minecraft:chipped_anvil" | "minecraft:damaged_anvil") => Shared::new(Box::new(Anvil::new())),
+			_ => match self.global_state.block_container(location) {
+				Some(container) => container,
+				None => return Ok(false),
+			},
+		};
+		self.open_window(player, container)?;
+		Ok(true)
+	}
+	fn drop_items(&self, player: &Player, items: Vec<ItemStack>) {
+		for stack in items {
+			// TODO spawn item entities
+			debug!("{} dropped {} {}, which disappeared", player.session.username, stack.count, stack.item);
+		}
+	}
+	fn handle_keep_alive(&mut self, player: &mut Player, id: i64) -> anyhow::Result<()> {
+		match player.keep_alive.receive(id) {
+			Ok(latency) => {
+				trace!("Client responded to keep-alive after {:?}", latency);
+				player.latency = latency;
+				Ok(())
+			}
+			Err(err) => {
+				let reason = Json(chat::Content {
+					text: "Invalid keep-alive".to_owned(),
+					..Default::default()
+				});
+				// the connection is being dropped anyway, so an error here isn't important
+				let _ = self.send_packet(&Send::Disconnect { reason });
+				Err(err)
+			}
+		}
+	}
+	fn handle_play_packet(&mut self, player: &mut Player, packet: Receive) -> anyhow::Result<()> {
+		match packet {
+			Receive::KeepAlive(keep_alive) => self.handle_keep_alive(player, keep_alive.0)?,
+			Receive::ConfirmTeleport(confirm) => {
+				if player.pending_teleport == Some(confirm.teleport_id.0) {
+					trace!("Client confirmed teleport {}", confirm.teleport_id.0);
+					player.pending_teleport = None;
+				} else {
+					debug!("Client confirmed teleport {} but we were expecting {:?}", confirm.teleport_id.0, player.pending_teleport);
+				}
+			}
+			// the client's idea of its position is stale until it confirms the teleport
+			Receive::MovePosition(_) | Receive::MovePosRot(_) | Receive::MoveRotation(_) | Receive::MoveStationary(_) if player.pending_teleport.is_some() => {}
+			Receive::MovePosition(movement) => {
+				player.position = movement.new_position;
+				player.on_ground = movement.on_ground;
+				self.update_chunk_view(player)?;
+			}
+			Receive::MovePosRot(movement) => {
+				player.position = movement.new_position;
+				player.rotation = movement.new_rotation;
+				player.on_ground = movement.on_ground;
+				self.update_chunk_view(player)?;
+			}
+			Receive::MoveRotation(movement) => {
+				player.rotation = movement.new_rotation;
+				player.on_ground = movement.on_ground;
+			}
+			Receive::MoveStationary(movement) => {
+				player.on_ground = movement.on_ground;
+			}
+			Receive::UpdateClientSettings(settings) => {
+				player.view_distance = (settings.render_distance.max(MIN_VIEW_DISTANCE as i8) as u8).min(self.config.view_distance);
+				player.skin_parts = settings.skin_parts & metadata::skin_parts::ALL;
+				self.update_chunk_view(player)?;
+			}
+			Receive::ClickWindowSlot(click) if click.window_id == inventory::WINDOW_ID => {
+				let creative = player.game_mode == misc::GameMode::Creative;
+				let dropped = player.inventory_session.handle_click(&mut player.inventory.view(), click, creative);
+				self.drop_items(player, dropped);
+				self.sync_inventory(player)?;
+			}
+			Receive::ClickWindowSlot(click) => match &mut player.container {
+				Some(open) if open.session.window_id() == click.window_id => {
+					let creative = player.game_mode == misc::GameMode::Creative;
+					// PANICS: the lock is only poisoned if another thread panicked while using the container
+					let dropped = open.session.handle_click(&mut player.inventory.view_with(open.container.lock().unwrap().container()), click, creative);
+					self.drop_items(player, dropped);
+					self.sync_window(player)?;
+				}
+				_ => debug!("Ignoring click in window {}, which isn't open", click.window_id),
+			},
+			Receive::CloseWindow(close) if close.window_id == inventory::WINDOW_ID => {
+				let leftovers: Vec<_> = player.inventory_session.close().into_iter().chain(player.inventory.close()).collect();
+				let dropped = player.inventory.insert_all(leftovers);
+				self.drop_items(player, dropped);
+				self.sync_inventory(player)?;
+			}
+			Receive::CloseWindow(close) => match &player.container {
+				Some(open) if open.session.window_id() == close.window_id => self.close_window(player, false)?,
+				_ => debug!("Ignoring close of window {}, which isn't open", close.window_id),
+			},
+			Receive::ClickWindowButton(click) => match &mut player.container {
+				Some(open) if open.session.window_id() as i8 == click.window_id => {
+					// PANICS: the lock is only poisoned if another thread panicked while using the container
+					if !open.container.lock().unwrap().container().button(click.button_id as u8) {
+						debug!("Ignoring click on button {}, which does nothing", click.button_id);
+					}
+					self.sync_window(player)?;
+				}
+				_ => debug!("Ignoring button click in window {}, which isn't open", click.window_id),
+			},
+			Receive::UpdateCustomItemName(rename) => match &mut player.container {
+				Some(open) => {
+					// PANICS: the lock is only poisoned if another thread panicked while using the container
+					open.container.lock().unwrap().container().rename(&rename.item_name.0);
+					self.sync_window(player)?;
+				}
+				None => debug!("Ignoring item rename without a window open"),
+			},
+			Receive::SelectCraftRecipe(select) => self.select_recipe(player, select)?,
+			Receive::SetRecipeBookState(state) => {
+				player.recipe_book.set_book(state.book_id, state.book_open, state.filter_active);
+				self.store_recipe_book(player);
+			}
+			Receive::SetDisplayedRecipe(displayed) => {
+				player.recipe_book.displayed(&displayed.recipe_id.0);
+				self.store_recipe_book(player);
+			}
+			Receive::PlaceBlock(place) => {
+				// sneaking places the held item instead, but we don't track sneaking yet
+				if !self.use_block(player, place.location)? {
+					trace!("Ignoring use of block at {:?}", place.location);
+				}
+			}
+			Receive::CheatInventorySlot(cheat) if player.game_mode == misc::GameMode::Creative => {
+				let IndexedSlot { slot_index, slot_data } = cheat.0;
+				match (ItemStack::from_slot(slot_data), usize::try_from(slot_index)) {
+					(Ok(stack), Ok(slot)) if (inventory::CRAFTING_GRID.start..inventory::SLOTS).contains(&slot) => {
+						player.inventory_session.set_from_client(&mut player.inventory.view(), slot, stack);
+					}
+					// dropped from outside of the window
+					(Ok(stack), Err(_)) => self.drop_items(player, stack.into_iter().collect()),
+					(Ok(_), Ok(slot)) => {
+						debug!("Ignoring creative inventory action in slot {}", slot);
+						player.inventory_session.resync();
+					}
+					(Err(err), _) => {
+						debug!("Ignoring creative inventory action: {:#}", err);
+						player.inventory_session.resync();
+					}
+				}
+				self.sync_inventory(player)?;
+			}
+			Receive::CheatInventorySlot(_) => {
+				debug!("Ignoring creative inventory action outside of creative mode");
+				player.inventory_session.resync();
+				self.sync_inventory(player)?;
+			}
+			Receive::ChangeHeldItem(held) => match usize::try_from(held.0) {
+				Ok(slot) if slot < inventory::HOTBAR.len() => player.inventory.select(slot),
+				_ => debug!("Client selected invalid hotbar slot {}", held.0),
+			},
+			Receive::PluginMessage(message) => {
+				trace!("Ignoring plugin message on channel {:?}", message.channel.0);
+			}
+			_ => {
+				trace!("Ignoring unhandled packet");
+			}
+		}
+		Ok(())
+	}
+
+	pub(super) fn handle_play(mut self, session: SessionResponse) -> anyhow::Result<()> {
+		debug!("Entering play state");
+		let (spawn, spawn_angle) = {
+			// PANICS: the lock is only poisoned if another thread panicked while modifying the world
+			let world = self.global_state.world.read().unwrap();
+			(world.level().spawn, world.level().spawn_angle)
+		};
+		// the center of the spawn block, horizontally
+		let spawn_position = F64Position {
+			x: f64::from(spawn.x()) + 0.5,
+			y: f64::from(spawn.y()),
+			z: f64::from(spawn.z()) + 0.5,
+		};
+		let saved_data = self.global_state.load_player(session.uuid)?;
+		let recipe_book = RecipeBook::from_nbt(saved_data.get("recipeBook"));
+		let rotation = F32Rotation { yaw: spawn_angle, pitch: 0.0 };
+		let keep_alive = KeepAlive::start(self.sender.clone(), self.stream.try_clone()?, Duration::from_secs(self.config.keep_alive_timeout))?;
+		// TODO configurable
+		let game_mode = misc::GameMode::Creative;
+		let online = Online {
+			username: session.username.clone(),
+			properties: session.properties.clone(),
+			game_mode,
+			ping: 0,
+			saved_data,
+		};
+		let refusal = {
+			// PANICS: the lock is only poisoned if another thread panicked while modifying the players
+			let mut players = self.global_state.players.write().unwrap();
+			if players.len() >= self.config.max_players {
+				Some("The server is full!")
+			} else if !players.join(session.uuid, online) {
+				// vanilla kicks the old connection instead, but that would need a way to reach its thread
+				Some("You are already logged in")
+			} else {
+				None
+			}
+		};
+		if let Some(refusal) = refusal {
+			let reason = Json(chat::Content {
+				text: refusal.to_owned(),
+				..Default::default()
+			});
+			self.send_packet(&Send::Disconnect { reason })?;
+			anyhow::bail!("Refused to let {} join: {}", session.username, refusal);
+		}
+		let entity = Entity {
+			uuid: session.uuid,
+			..Entity::new(entities::Kind::Player, spawn_position, rotation)
+		};
+		// PANICS: the lock is only poisoned if another thread panicked while modifying the entities
+		let entity_id = self.global_state.entities.write().unwrap().spawn(entity);
+		let (updates_sender, world_updates) = mpsc::channel();
+		// PANICS: the lock is only poisoned if another thread panicked while a player was joining or leaving
+		self.global_state.world_updates.lock().unwrap().insert(entity_id, updates_sender);
+		let mut player = Player {
+			entity_id,
+			session,
+			position: spawn_position,
+			rotation,
+			on_ground: false,
+			pending_teleport: None,
+			next_teleport_id: 0,
+			keep_alive,
+			latency: Duration::ZERO,
+			view_distance: self.config.view_distance,
+			chunk_view: ChunkView::new(self.config.view_distance),
+			entity_tracker: Tracker::new(entity_id),
+			player_list: PlayerList::default(),
+			game_mode,
+			inventory: Inventory::new(),
+			inventory_session: Session::new(inventory::WINDOW_ID),
+			container: None,
+			next_window_id: 1,
+			// until the client sends its settings
+			skin_parts: 0,
+			recipe_book,
+			world_updates,
+		};
+		let result = self.play(&mut player, spawn);
+		self.leave(&player);
+		// stops the reader if the server is the one ending the connection
+		let _ = self.stream.shutdown(std::net::Shutdown::Both);
+		result
+	}
+
+	fn play(&mut self, player: &mut Player, spawn: PackedPosition) -> anyhow::Result<()> {
+		self.send_join_game(player)?;
+		self.send_packet(&Send::UpdateSpawnPosition(send::UpdateSpawnPosition { location: spawn, angle: player.rotation.yaw }))?;
+		self.send_packet(&Send::DeclareRecipes(send::DeclareRecipes(recipes::registry().all())))?;
+		self.send_packet(&player.recipe_book.init_packet())?;
+		for packet in player.inventory_session.full_sync(&player.inventory.view()) {
+			self.send_packet(&packet)?;
+		}
+		self.update_chunk_view(player)?;
+		self.sync_player_list(player)?;
+		self.sync_entities(player)?;
+		let (spawn_position, rotation) = (player.position, player.rotation);
+		self.teleport(player, spawn_position, rotation)?;
+		debug!("{} joined the game", player.session.username);
+		let received = self.spawn_reader()?;
+		let mut next_tick = Instant::now() + TICK;
+		loop {
+			match received.recv_timeout(next_tick.saturating_duration_since(Instant::now())) {
+				Ok(packet) => match packet? {
+					Some(packet) => self.handle_play_packet(player, packet)?,
+					// client is free to close the connection at any time
+					None if player.keep_alive.timed_out() => anyhow::bail!("{} timed out", player.session.username),
+					None => {
+						debug!("{} left the game", player.session.username);
+						return Ok(());
+					}
+				},
+				Err(RecvTimeoutError::Timeout) => {}
+				// the reader only stops after sending the end of the stream or an error
+				Err(RecvTimeoutError::Disconnected) => anyhow::bail!("Stopped receiving packets from {}", player.session.username),
+			}
+			if Instant::now() >= next_tick {
+				next_tick = Instant::now() + TICK;
+				self.tick(player)?;
+			}
+		}
+	}
+	/// Receives packets on another thread, so that the player can be kept up to date while the client is quiet.
+	/// It stops after the end of the stream or an error, which are passed on too.
+	fn spawn_reader(&mut self) -> anyhow::Result<Receiver<encde::Result<Option<Receive>>>> {
+		let (sender, receiver) = mpsc::channel();
+		let mut reader = std::mem::replace(&mut self.reader, Box::new(std::io::empty()));
+		let compression_threshold = self.sender.compression_threshold;
+		let name = format!("{} reader", std::thread::current().name().unwrap_or("client"));
+		std::thread::Builder::new().name(name).spawn(move || loop {
+			let packet = super::read_packet(&mut reader, compression_threshold);
+			let last = !matches!(packet, Ok(Some(_)));
+			if sender.send(packet).is_err() || last {
+				break;
+			}
+		})?;
+		Ok(receiver)
+	}
+	/// Brings the player up to date with changes to the world, progress bars, who is online, and the entities around them
+	fn tick(&mut self, player: &mut Player) -> anyhow::Result<()> {
+		for packet in player.world_updates.try_iter() {
+			self.sender.send_encoded(&packet)?;
+		}
+		self.sync_window(player)?;
+		self.sync_player_list(player)?;
+		self.sync_entities(player)
+	}
+
+	/// Saves the player and releases their hold on the world
+	fn leave(&self, player: &Player) {
+		// the entity goes first so that nobody is left with a player that isn't in their list
+		// PANICS: the lock is only poisoned if another thread panicked while modifying the entities
+		self.global_state.entities.write().unwrap().remove(player.entity_id);
+		// PANICS: the lock is only poisoned if another thread panicked while a player was joining or leaving
+		self.global_state.world_updates.lock().unwrap().remove(&player.entity_id);
+		// PANICS: the lock is only poisoned if another thread panicked while modifying the players
+		let online = self.global_state.players.write().unwrap().leave(player.session.uuid);
+		if let Some(online) = online {
+			if let Err(err) = self.global_state.save_player(player.session.uuid, &online.saved_data) {
+				log::error!("Failed to save {}: {:#}", player.session.username, err);
+			}
+		}
+		// PANICS: the lock is only poisoned if another thread panicked while modifying the world
+		let mut world = self.global_state.world.write().unwrap();
+		for position in player.chunk_view.loaded() {
+			if let Err(err) = world.unwatch_column(position, player.entity_id) {
+				log::error!("Failed to unload chunk {:?}: {:#}", position, err);
+			}
+		}
+	}
 }
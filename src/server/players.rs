@@ -0,0 +1,177 @@
+use super::auth::SessionProperty;
+use crate::packets::helpers::game::player_list;
+use crate::packets::helpers::misc::GameMode;
+use crate::packets::helpers::varint::VarInt;
+use crate::packets::helpers::wrappers::std::{PrefixedOption, PrefixedVec};
+use crate::packets::helpers::wrappers::uuid::Uuid as UuidWrapper;
+use crate::packets::play::send::{self, Packet as Send};
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+
+/// A player who has joined the game, as the other players see them
+#[derive(Clone, Debug)]
+pub struct Online {
+	pub username: String,
+	/// From the session, with the skin
+	pub properties: Vec<SessionProperty>,
+	pub game_mode: GameMode,
+	/// In milliseconds
+	pub ping: i32,
+	/// What goes in the player's file, kept up to date so that they can be saved while they're online
+	pub saved_data: HashMap<String, nbt::Value>,
+}
+
+/// Everyone who is in the game, shared between all of the players
+#[derive(Default)]
+pub struct Players {
+	online: HashMap<uuid::Uuid, Online>,
+}
+
+impl Players {
+	/// Returns false if a player with the same UUID is already online
+	pub fn join(&mut self, uuid: uuid::Uuid, player: Online) -> bool {
+		match self.online.entry(uuid) {
+			Entry::Occupied(_) => false,
+			Entry::Vacant(entry) => {
+				entry.insert(player);
+				true
+			}
+		}
+	}
+	pub fn leave(&mut self, uuid: uuid::Uuid) -> Option<Online> {
+		self.online.remove(&uuid)
+	}
+	pub fn get_mut(&mut self, uuid: uuid::Uuid) -> Option<&mut Online> {
+		self.online.get_mut(&uuid)
+	}
+	pub fn len(&self) -> usize {
+		self.online.len()
+	}
+	pub fn is_empty(&self) -> bool {
+		self.online.is_empty()
+	}
+	pub fn iter(&self) -> impl Iterator<Item = (uuid::Uuid, &Online)> {
+		self.online.iter().map(|(&uuid, player)| (uuid, player))
+	}
+}
+
+/// What the client was last told about a player
+struct Listed {
+	game_mode: GameMode,
+	ping: i32,
+}
+
+/// The players in a client's tab list, which it keeps up to date as players join and leave. This includes the client's own player.
+#[derive(Default)]
+pub struct PlayerList {
+	listed: HashMap<uuid::Uuid, Listed>,
+}
+
+impl PlayerList {
+	pub fn contains(&self, uuid: &uuid::Uuid) -> bool {
+		self.listed.contains_key(uuid)
+	}
+	/// Brings the client up to date with who is online, returning the packets to send
+	pub fn update(&mut self, players: &Players) -> Vec<Send<'static>> {
+		let mut removed = Vec::new();
+		self.listed.retain(|uuid, _| {
+			let keep = players.online.contains_key(uuid);
+			if !keep {
+				removed.push(player_list::RemovePlayer { player_uuid: UuidWrapper(*uuid) });
+			}
+			keep
+		});
+		let (mut added, mut game_modes, mut latencies) = (Vec::new(), Vec::new(), Vec::new());
+		for (uuid, player) in players.iter() {
+			match self.listed.get_mut(&uuid) {
+				None => {
+					added.push(player_list::AddPlayer {
+						player_uuid: UuidWrapper(uuid),
+						properties: PrefixedVec::new(player.properties.iter().map(Into::into).collect()),
+						gamemode: player.game_mode,
+						ping: VarInt(player.ping),
+						display_name: PrefixedOption(None),
+					});
+					self.listed.insert(
+						uuid,
+						Listed {
+							game_mode: player.game_mode,
+							ping: player.ping,
+						},
+					);
+				}
+				Some(listed) => {
+					if listed.game_mode != player.game_mode {
+						listed.game_mode = player.game_mode;
+						game_modes.push(player_list::UpdateGamemode {
+							player_uuid: UuidWrapper(uuid),
+							new_gamemode: player.game_mode,
+						});
+					}
+					if listed.ping != player.ping {
+						listed.ping = player.ping;
+						latencies.push(player_list::UpdateLatency {
+							player_uuid: UuidWrapper(uuid),
+							ping: VarInt(player.ping),
+						});
+					}
+				}
+			}
+		}
+		let mut packets = Vec::new();
+		if !removed.is_empty() {
+			packets.push(Send::UpdatePlayerList(send::UpdatePlayerList::RemovePlayers(PrefixedVec::new(removed))));
+		}
+		if !added.is_empty() {
+			packets.push(Send::UpdatePlayerList(send::UpdatePlayerList::AddPlayers(PrefixedVec::new(added))));
+		}
+		if !game_modes.is_empty() {
+			packets.push(Send::UpdatePlayerList(send::UpdatePlayerList::UpdateGamemode(PrefixedVec::new(game_modes))));
+		}
+		if !latencies.is_empty() {
+			packets.push(Send::UpdatePlayerList(send::UpdatePlayerList::UpdateLatency(PrefixedVec::new(latencies))));
+		}
+		packets
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::{Online, PlayerList, Players};
+	use crate::packets::helpers::misc::GameMode;
+	use crate::packets::play::send::{Packet as Send, UpdatePlayerList};
+
+	fn online(username: &str) -> Online {
+		Online {
+			username: username.to_owned(),
+			properties: Vec::new(),
+			game_mode: GameMode::Survival,
+			ping: 0,
+			saved_data: Default::default(),
+		}
+	}
+
+	#[test]
+	pub fn listing() {
+		let mut players = Players::default();
+		let (alice, bob) = (uuid::Uuid::from_u128(1), uuid::Uuid::from_u128(2));
+		assert!(players.join(alice, online("alice")));
+		assert!(!players.join(alice, online("alice")));
+		let mut list = PlayerList::default();
+		assert!(matches!(list.update(&players)[..], [Send::UpdatePlayerList(UpdatePlayerList::AddPlayers(_))]));
+		assert!(list.contains(&alice) && !list.contains(&bob));
+		assert!(list.update(&players).is_empty());
+
+		assert!(players.join(bob, online("bob")));
+		players.get_mut(alice).unwrap().ping = 50;
+		assert!(matches!(
+			list.update(&players)[..],
+			[Send::UpdatePlayerList(UpdatePlayerList::AddPlayers(_)), Send::UpdatePlayerList(UpdatePlayerList::UpdateLatency(_))]
+		));
+
+		players.leave(alice);
+		assert!(matches!(list.update(&players)[..], [Send::UpdatePlayerList(UpdatePlayerList::RemovePlayers(_))]));
+		assert!(!list.contains(&alice) && list.contains(&bob));
+		assert_eq!(players.len(), 1);
+	}
+}
@@ -0,0 +1,157 @@
+use crate::items::recipes;
+use crate::items::Item;
+use crate::packets::helpers::game::recipes::{BookState, BookType, UnlockAction};
+use crate::packets::helpers::wrappers::std::{PrefixedString, PrefixedVec};
+use crate::packets::play::send::{self, Packet as Send};
+use std::collections::{BTreeSet, HashSet};
+
+/// The keys in the `recipeBook` compound for whether each book is open and whether it only shows craftable recipes, in `BookType` order
+const BOOK_KEYS: [(&str, &str); 4] = [
+	("isGuiOpen", "isFilteringCraftable"),
+	("isFurnaceGuiOpen", "isFurnaceFilteringCraftable"),
+	("isBlastingFurnaceGuiOpen", "isBlastingFurnaceFilteringCraftable"),
+	("isSmokerGuiOpen", "isSmokerFilteringCraftable"),
+];
+
+fn prefixed(ids: impl IntoIterator<Item = String>) -> PrefixedVec<PrefixedString> {
+	PrefixedVec::new(ids.into_iter().map(PrefixedString).collect())
+}
+
+/// Which recipes a player has unlocked, and how their recipe book was left
+#[derive(Default)]
+pub struct RecipeBook {
+	/// Recipe IDs
+	unlocked: BTreeSet<String>,
+	/// Unlocked recipes that the player hasn't looked at yet, which the client highlights
+	highlighted: BTreeSet<String>,
+	/// Whether each book is open and whether it only shows craftable recipes, indexed by `BookType`
+	books: [(bool, bool); 4],
+	/// Items that have already unlocked their recipes. Not saved, since unlocking recipes again does nothing.
+	discovered: HashSet<Item>,
+}
+
+impl RecipeBook {
+	/// From the `recipeBook` compound in player data. Recipes that don't exist anymore are dropped.
+	pub fn from_nbt(data: Option<&nbt::Value>) -> Self {
+		let data = match data {
+			Some(nbt::Value::Compound(data)) => data,
+			_ => return Self::default(),
+		};
+		let ids = |key| match data.get(key) {
+			Some(nbt::Value::List(ids)) => ids
+				.iter()
+				.filter_map(|id| match id {
+					nbt::Value::String(id) if recipes::registry().get(id).is_some() => Some(id.clone()),
+					_ => None,
+				})
+				.collect(),
+			_ => BTreeSet::new(),
+		};
+		let flag = |key| matches!(data.get(key), Some(nbt::Value::Byte(value)) if *value != 0);
+		let unlocked = ids("recipes");
+		Self {
+			highlighted: ids("toBeDisplayed").intersection(&unlocked).cloned().collect(),
+			unlocked,
+			books: BOOK_KEYS.map(|(open, filter)| (flag(open), flag(filter))),
+			discovered: HashSet::new(),
+		}
+	}
+	pub fn to_nbt(&self) -> nbt::Value {
+		let ids = |ids: &BTreeSet<String>| nbt::Value::List(ids.iter().cloned().map(nbt::Value::String).collect());
+		let mut data: std::collections::HashMap<_, _> = [("recipes".to_owned(), ids(&self.unlocked)), ("toBeDisplayed".to_owned(), ids(&self.highlighted))].into_iter().collect();
+		for ((open_key, filter_key), (open, filter)) in BOOK_KEYS.into_iter().zip(self.books) {
+			data.insert(open_key.to_owned(), nbt::Value::Byte(open.into()));
+			data.insert(filter_key.to_owned(), nbt::Value::Byte(filter.into()));
+		}
+		nbt::Value::Compound(data)
+	}
+
+	pub fn is_unlocked(&self, id: &str) -> bool {
+		self.unlocked.contains(id)
+	}
+	/// Returns the recipes that weren't already unlocked, which are highlighted until the player looks at them
+	pub fn unlock<'a>(&mut self, ids: impl IntoIterator<Item = &'a str>) -> Vec<String> {
+		let added: Vec<String> = ids.into_iter().filter(|&id| !self.unlocked.contains(id)).map(str::to_owned).collect();
+		self.unlocked.extend(added.iter().cloned());
+		self.highlighted.extend(added.iter().cloned());
+		added
+	}
+	/// Returns the recipes that were unlocked
+	pub fn lock<'a>(&mut self, ids: impl IntoIterator<Item = &'a str>) -> Vec<String> {
+		let removed: Vec<String> = ids.into_iter().filter(|&id| self.unlocked.remove(id)).map(str::to_owned).collect();
+		for id in &removed {
+			self.highlighted.remove(id);
+		}
+		removed
+	}
+	/// Unlocks the recipes that use any of the items, which is roughly what vanilla's recipe advancements do. Returns the newly unlocked recipes.
+	pub fn discover(&mut self, items: impl IntoIterator<Item = Item>) -> Vec<String> {
+		let new: Vec<Item> = items.into_iter().filter(|&item| self.discovered.insert(item)).collect();
+		if new.is_empty() {
+			return Vec::new();
+		}
+		let ids: Vec<&str> = recipes::registry()
+			.all()
+			.iter()
+			.filter(|recipe| recipes::ingredients(&recipe.data).into_iter().any(|ingredient| new.iter().any(|&item| recipes::matches(ingredient, item))))
+			.map(|recipe| recipe.id.0.as_str())
+			.collect();
+		self.unlock(ids)
+	}
+	/// The player has looked at the recipe, so it's no longer highlighted
+	pub fn displayed(&mut self, id: &str) {
+		self.highlighted.remove(id);
+	}
+	pub fn set_book(&mut self, book: BookType, open: bool, filter_active: bool) {
+		self.books[book as usize] = (open, filter_active);
+	}
+
+	fn packet(&self, action: UnlockAction) -> Send<'static> {
+		let [crafting_book, smelting_book, blast_furnace_book, smoker_book] = self.books.map(|(open, filter_active)| BookState { open, filter_active });
+		Send::UnlockRecipes(send::UnlockRecipes {
+			action,
+			crafting_book,
+			smelting_book,
+			blast_furnace_book,
+			smoker_book,
+		})
+	}
+	/// Everything, for when the player joins
+	pub fn init_packet(&self) -> Send<'static> {
+		self.packet(UnlockAction::Init {
+			already_shown: prefixed(self.unlocked.iter().cloned()),
+			new: prefixed(self.highlighted.iter().cloned()),
+		})
+	}
+	/// The client shows a notification for the recipes
+	pub fn add_packet(&self, ids: Vec<String>) -> Send<'static> {
+		self.packet(UnlockAction::Add(prefixed(ids)))
+	}
+	pub fn remove_packet(&self, ids: Vec<String>) -> Send<'static> {
+		self.packet(UnlockAction::Remove(prefixed(ids)))
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::RecipeBook;
+	use crate::packets::helpers::game::recipes::BookType;
+
+	#[test]
+	pub fn unlocking() {
+		let mut book = RecipeBook::default();
+		let added = book.discover(["oak_log".parse().unwrap()]);
+		assert!(added.contains(&"minecraft:oak_planks".to_owned()));
+		assert!(added.contains(&"minecraft:charcoal".to_owned()));
+		assert!(book.discover(["oak_log".parse().unwrap()]).is_empty());
+		assert_eq!(book.unlock(["minecraft:oak_planks", "minecraft:stick"]), ["minecraft:stick"]);
+		assert_eq!(book.lock(["minecraft:charcoal", "minecraft:furnace"]), ["minecraft:charcoal"]);
+		book.displayed("minecraft:stick");
+		book.set_book(BookType::Furnace, true, false);
+
+		let loaded = RecipeBook::from_nbt(Some(&book.to_nbt()));
+		assert_eq!((&loaded.unlocked, loaded.books), (&book.unlocked, book.books));
+		assert!(loaded.highlighted.contains("minecraft:oak_planks") && !loaded.highlighted.contains("minecraft:stick"));
+		assert!(RecipeBook::from_nbt(None).unlocked.is_empty());
+	}
+}
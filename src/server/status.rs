@@ -1,10 +1,56 @@
 use super::Client;
 use crate::packets::helpers::wrappers::json::Json;
+use crate::packets::helpers::wrappers::uuid::Uuid as UuidWrapper;
+use crate::packets::legacy;
 use crate::packets::status::receive::Packet as Receive;
 use crate::packets::status::send::{self, Packet as Send};
 use log::trace;
+use rand::seq::{IteratorRandom, SliceRandom};
+use std::time::Duration;
+
+/// Like in vanilla
+const SAMPLE_SIZE: usize = 12;
 
 impl Client {
+	fn status_players(&self) -> send::StatusPlayers {
+		// PANICS: the lock is only poisoned if another thread panicked while modifying the players
+		let players = self.global_state.players.read().unwrap();
+		let sample = (!self.config.listing.hide_players).then(|| {
+			let mut rng = rand::thread_rng();
+			let mut sample: Vec<_> = players
+				.iter()
+				.map(|(uuid, player)| send::StatusPlayerSample {
+					name: player.username.clone(),
+					id: UuidWrapper(uuid),
+				})
+				.choose_multiple(&mut rng, SAMPLE_SIZE);
+			// `choose_multiple` doesn't shuffle
+			sample.shuffle(&mut rng);
+			sample
+		});
+		send::StatusPlayers {
+			max: self.config.max_players,
+			online: players.len(),
+			sample,
+		}
+	}
+	/// Responds in the `§1` format that 1.4 to 1.6 clients expect. Clients older than that show it garbled.
+	pub(super) fn handle_legacy_status(mut self) -> anyhow::Result<()> {
+		trace!("Responding to legacy ping");
+		let players = self.status_players();
+		legacy::PingResponse {
+			version: super::SERVER_VERSION,
+			motd: &self.config.listing.motd,
+			online: players.online,
+			max: players.max,
+		}
+		.write(&mut self.stream)?;
+		self.stream.shutdown(std::net::Shutdown::Write)?;
+		// the rest of the request doesn't matter, but closing with it unread would reset the connection before the client reads the response
+		self.stream.set_read_timeout(Some(Duration::from_secs(1)))?;
+		let _ = std::io::copy(&mut self.stream, &mut std::io::sink());
+		Ok(())
+	}
 	pub(super) fn handle_status(mut self) -> anyhow::Result<()> {
 		trace!("Entering status state");
 		loop {
@@ -20,7 +66,7 @@ impl Client {
 						name: super::SERVER_VERSION.to_string(),
 						protocol: super::PROTOCOL_VERSION,
 					},
-					players: send::StatusPlayers { max: 420, online: 69, sample: None },
+					players: self.status_players(),
 					description: send::StatusDescription { text: &self.config.listing.motd },
 					favicon: self.config.listing.icon.as_deref(),
 				})),
@@ -29,3 +75,53 @@ impl Client {
 		}
 	}
 }
+
+#[cfg(test)]
+mod test {
+	use super::super::players::Online;
+	use super::super::{Client, GlobalState};
+	use crate::packets::helpers::misc::GameMode;
+	use figment::providers::{Format, Toml};
+	use std::net::{TcpListener, TcpStream};
+
+	/// A client connected over loopback to a server with `count` players online
+	fn client(hide_players: bool, count: u128) -> Client {
+		let config: crate::config::Config = figment::Figment::new()
+			.merge(Toml::string(&format!("address = \"127.0.0.1\"\nlogging = []\n[listing]\nhide_players = {}\n[auth]\ntype = \"offline\"", hide_players)))
+			.extract()
+			.unwrap();
+		let config: &'static _ = Box::leak(Box::new(config));
+		let global_state: &'static GlobalState = Box::leak(Box::new(GlobalState::new(config).unwrap()));
+		for id in 0..count {
+			let online = Online {
+				username: format!("player{}", id),
+				properties: Vec::new(),
+				game_mode: GameMode::Survival,
+				ping: 0,
+				saved_data: Default::default(),
+			};
+			assert!(global_state.players.write().unwrap().join(uuid::Uuid::from_u128(id), online));
+		}
+		let listener = TcpListener::bind(("127.0.0.1", 0)).unwrap();
+		let _socket = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+		let (socket, address) = listener.accept().unwrap();
+		Client::new(socket, address, config, global_state).unwrap()
+	}
+
+	#[test]
+	pub fn player_sample() {
+		let players = client(false, 3).status_players();
+		assert_eq!((players.max, players.online, players.sample.map(|sample| sample.len())), (20, 3, Some(3)));
+
+		let players = client(false, 30).status_players();
+		let sample = players.sample.unwrap();
+		assert_eq!((players.online, sample.len()), (30, super::SAMPLE_SIZE));
+		let mut names: Vec<_> = sample.iter().map(|player| &player.name).collect();
+		names.sort();
+		names.dedup();
+		assert_eq!(names.len(), super::SAMPLE_SIZE);
+
+		let players = client(true, 3).status_players();
+		assert_eq!((players.online, players.sample.is_none()), (3, true));
+	}
+}
@@ -0,0 +1,107 @@
+use super::{take_all, Container, Kind, Window};
+use crate::items::stack::ItemStack;
+use crate::packets::helpers::game::chat;
+
+const LEFT: usize = 0;
+const RIGHT: usize = 1;
+const OUTPUT: usize = 2;
+/// Longer names are cut off
+const MAX_NAME_LENGTH: usize = 35;
+
+/// Renames items. Combining and repairing items isn't supported yet, so the right slot is unused.
+pub struct Anvil {
+	title: String,
+	slots: Vec<Option<ItemStack>>,
+	/// What the player typed in, which is empty to remove a custom name
+	name: Option<String>,
+}
+
+impl Anvil {
+	pub fn new() -> Self {
+		Self::with_title("Repair & Name")
+	}
+	pub fn with_title(title: impl Into<String>) -> Self {
+		Self {
+			title: title.into(),
+			slots: vec![None; 3],
+			name: None,
+		}
+	}
+
+	fn renamed(&self) -> Option<ItemStack> {
+		let left = self.slots[LEFT].as_ref()?;
+		let name = self.name.as_deref()?;
+		let mut output = left.clone();
+		let content = (!name.is_empty()).then(|| chat::Content {
+			text: name.chars().take(MAX_NAME_LENGTH).collect(),
+			..Default::default()
+		});
+		output.set_display_name(content.as_ref()).ok()?;
+		(output != *left).then_some(output)
+	}
+}
+
+impl Window for Anvil {
+	fn slots(&self) -> &[Option<ItemStack>] {
+		&self.slots
+	}
+	fn slots_mut(&mut self) -> &mut [Option<ItemStack>] {
+		&mut self.slots
+	}
+	fn is_output(&self, slot: usize) -> bool {
+		slot == OUTPUT
+	}
+	fn take_output(&mut self, _slot: usize) {
+		self.slots[LEFT] = None;
+	}
+	fn changed(&mut self) {
+		self.slots[OUTPUT] = self.renamed();
+	}
+	/// The experience cost, which is always 1 for renaming
+	fn properties(&self) -> Vec<i16> {
+		vec![self.slots[OUTPUT].is_some().into()]
+	}
+	fn rename(&mut self, name: &str) {
+		self.name = Some(name.to_owned());
+		self.changed();
+	}
+	fn close(&mut self) -> Vec<ItemStack> {
+		self.slots[OUTPUT] = None;
+		take_all(&mut self.slots[LEFT..=RIGHT])
+	}
+}
+
+impl Container for Anvil {
+	fn kind(&self) -> Kind {
+		Kind::Anvil
+	}
+	fn title(&self) -> &str {
+		&self.title
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::{Anvil, LEFT, OUTPUT};
+	use crate::items::stack::ItemStack;
+	use crate::server::window::Window;
+
+	#[test]
+	pub fn rename() {
+		let mut anvil = Anvil::new();
+		anvil.slots_mut()[LEFT] = Some(ItemStack::new("diamond_sword".parse().unwrap(), 1));
+		anvil.rename("Excalibur");
+		let output = anvil.slots()[OUTPUT].clone().unwrap();
+		assert!(output.display_name().unwrap().contains("Excalibur"));
+		assert_eq!(anvil.properties(), [1]);
+
+		anvil.take_output(OUTPUT);
+		anvil.slots_mut()[LEFT] = Some(output);
+		anvil.changed();
+		// the name is already the same
+		assert!(anvil.slots()[OUTPUT].is_none());
+		anvil.rename("");
+		assert!(anvil.slots()[OUTPUT].as_ref().unwrap().nbt.is_empty());
+		assert_eq!(anvil.close().len(), 1);
+	}
+}
@@ -0,0 +1,72 @@
+use super::{Container, Kind, Window};
+use crate::items::stack::ItemStack;
+use crate::world::BlockState;
+
+/// Rows of nine slots, like a chest or a barrel
+pub struct Chest {
+	title: String,
+	slots: Vec<Option<ItemStack>>,
+}
+
+impl Chest {
+	/// There can be between 1 and 6 rows
+	pub fn new(rows: usize, title: impl Into<String>) -> Self {
+		assert!((1..=6).contains(&rows), "A chest can't have {rows} rows");
+		Self {
+			title: title.into(),
+			slots: vec![None; rows * 9],
+		}
+	}
+	pub fn barrel() -> Self {
+		Self::new(3, "Barrel")
+	}
+
+	pub fn rows(&self) -> usize {
+		self.slots.len() / 9
+	}
+}
+
+/// Where the other half of a double chest is, as an X and Z offset, and whether this half's slots come first.
+/// Single chests and blocks that aren't chests have no other half.
+pub fn other_half(state: BlockState) -> Option<((i32, i32), bool)> {
+	let properties = state.properties()?;
+	// a quarter turn clockwise from the way the chest faces
+	let clockwise = match properties.get("facing")?.as_str() {
+		"north" => (1, 0),
+		"east" => (0, 1),
+		"south" => (-1, 0),
+		"west" => (0, -1),
+		_ => return None,
+	};
+	// like vanilla, the right half is on top
+	match properties.get("type")?.as_str() {
+		"left" => Some((clockwise, false)),
+		"right" => Some(((-clockwise.0, -clockwise.1), true)),
+		_ => None,
+	}
+}
+
+impl Window for Chest {
+	fn slots(&self) -> &[Option<ItemStack>] {
+		&self.slots
+	}
+	fn slots_mut(&mut self) -> &mut [Option<ItemStack>] {
+		&mut self.slots
+	}
+}
+
+impl Container for Chest {
+	fn kind(&self) -> Kind {
+		match self.rows() {
+			1 => Kind::Generic9x1,
+			2 => Kind::Generic9x2,
+			3 => Kind::Generic9x3,
+			4 => Kind::Generic9x4,
+			5 => Kind::Generic9x5,
+			_ => Kind::Generic9x6,
+		}
+	}
+	fn title(&self) -> &str {
+		&self.title
+	}
+}
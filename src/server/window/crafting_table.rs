@@ -0,0 +1,78 @@
+use super::{take_all, use_ingredients, Container, Kind, Window, PLAYER_SLOTS};
+use crate::items::recipes;
+use crate::items::stack::ItemStack;
+use std::ops::Range;
+
+/// Looks up what a crafting grid makes. The grid is given row by row, with the width of each row.
+pub type CraftFn = fn(grid: &[Option<ItemStack>], width: usize) -> Option<ItemStack>;
+
+const RESULT: usize = 0;
+const GRID: Range<usize> = 1..10;
+const SIZE: usize = GRID.end;
+const MAIN: Range<usize> = SIZE..SIZE + PLAYER_SLOTS - 9;
+const HOTBAR: Range<usize> = MAIN.end..SIZE + PLAYER_SLOTS;
+
+/// A 3x3 crafting grid and its result
+pub struct CraftingTable {
+	title: String,
+	slots: Vec<Option<ItemStack>>,
+	craft: CraftFn,
+}
+
+impl CraftingTable {
+	/// Uses the vanilla recipes
+	pub fn new() -> Self {
+		Self::with_title("Crafting", recipes::craft)
+	}
+	pub fn with_title(title: impl Into<String>, craft: CraftFn) -> Self {
+		Self {
+			title: title.into(),
+			slots: vec![None; SIZE],
+			craft,
+		}
+	}
+}
+
+impl Window for CraftingTable {
+	fn slots(&self) -> &[Option<ItemStack>] {
+		&self.slots
+	}
+	fn slots_mut(&mut self) -> &mut [Option<ItemStack>] {
+		&mut self.slots
+	}
+	fn is_output(&self, slot: usize) -> bool {
+		slot == RESULT
+	}
+	fn take_output(&mut self, _slot: usize) {
+		use_ingredients(&mut self.slots[GRID]);
+	}
+	fn changed(&mut self) {
+		self.slots[RESULT] = (self.craft)(&self.slots[GRID], 3);
+	}
+	fn quick_move_target(&self, slot: usize, _stack: &ItemStack) -> Range<usize> {
+		if slot < SIZE {
+			MAIN.start..HOTBAR.end
+		} else if MAIN.contains(&slot) {
+			HOTBAR
+		} else {
+			MAIN
+		}
+	}
+	fn crafting_grid(&self) -> Option<(Range<usize>, usize)> {
+		Some((GRID, 3))
+	}
+	/// The ingredients go back to the player
+	fn close(&mut self) -> Vec<ItemStack> {
+		self.slots[RESULT] = None;
+		take_all(&mut self.slots[GRID])
+	}
+}
+
+impl Container for CraftingTable {
+	fn kind(&self) -> Kind {
+		Kind::Crafting
+	}
+	fn title(&self) -> &str {
+		&self.title
+	}
+}
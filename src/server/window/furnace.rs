@@ -0,0 +1,220 @@
+use super::{Container, Kind, Window, PLAYER_SLOTS};
+use crate::items::recipes;
+use crate::items::stack::ItemStack;
+use crate::items::Item;
+use std::ops::Range;
+
+/// Looks up what an item smelts into, and how many ticks it takes
+pub type SmeltFn = fn(input: Item) -> Option<(ItemStack, u16)>;
+
+const INPUT: usize = 0;
+const FUEL: usize = 1;
+const OUTPUT: usize = 2;
+const SIZE: usize = 3;
+const MAIN: Range<usize> = SIZE..SIZE + PLAYER_SLOTS - 9;
+const HOTBAR: Range<usize> = MAIN.end..SIZE + PLAYER_SLOTS;
+
+/// How many ticks the item burns for, by name
+fn fuel_ticks(item: Item) -> Option<u16> {
+	let name = item.name()?.strip_prefix("minecraft:")?;
+	const WOODS: [&str; 6] = ["oak", "spruce", "birch", "jungle", "acacia", "dark_oak"];
+	// nether wood doesn't burn
+	let wooden = WOODS.iter().any(|wood| name.starts_with(wood) || name.strip_prefix("stripped_").is_some_and(|name| name.starts_with(wood)));
+	Some(match name {
+		"lava_bucket" => 20000,
+		"coal_block" => 16000,
+		"dried_kelp_block" => 4001,
+		"blaze_rod" => 2400,
+		"coal" | "charcoal" => 1600,
+		"crafting_table" | "chest" | "barrel" | "bookshelf" => 300,
+		"bamboo" | "scaffolding" => 50,
+		"stick" | "bowl" => 100,
+		_ if name.ends_with("_sapling") || name.ends_with("_wool") => 100,
+		_ if name.ends_with("_carpet") => 67,
+		_ if name.starts_with("wooden_") => 200,
+		_ if wooden && name.ends_with("_boat") => 1200,
+		_ if wooden && (name.ends_with("_sign") || name.ends_with("_door")) => 200,
+		_ if wooden && name.ends_with("_slab") => 150,
+		_ if wooden && ["_planks", "_log", "_wood", "_stairs", "_fence", "_fence_gate", "_trapdoor", "_pressure_plate"].iter().any(|suffix| name.ends_with(suffix)) => 300,
+		_ => return None,
+	})
+}
+
+/// Smelts its input using its fuel, one item at a time
+pub struct Furnace {
+	title: String,
+	slots: Vec<Option<ItemStack>>,
+	smelt: SmeltFn,
+	/// Ticks left on the current fuel
+	burn_time: u16,
+	/// Ticks that the current fuel lasts in total, for the flame in the window
+	burn_total: u16,
+	/// Ticks that the current input has been cooking for
+	cook: u16,
+	cook_total: u16,
+}
+
+impl Furnace {
+	/// Uses the vanilla smelting recipes
+	pub fn new() -> Self {
+		Self::with_title("Furnace", recipes::smelt)
+	}
+	pub fn with_title(title: impl Into<String>, smelt: SmeltFn) -> Self {
+		Self {
+			title: title.into(),
+			slots: vec![None; SIZE],
+			smelt,
+			burn_time: 0,
+			burn_total: 0,
+			cook: 0,
+			cook_total: 0,
+		}
+	}
+
+	pub fn is_burning(&self) -> bool {
+		self.burn_time > 0
+	}
+
+	/// The result of smelting the input, if it would fit in the output slot
+	fn result(&self) -> Option<(ItemStack, u16)> {
+		let input = self.slots[INPUT].as_ref()?;
+		let (result, cook_time) = (self.smelt)(input.item)?;
+		match &self.slots[OUTPUT] {
+			Some(output) if !output.stacks_with(&result) || output.count + result.count > output.item.max_stack_size() => None,
+			_ => Some((result, cook_time)),
+		}
+	}
+
+	fn tick_once(&mut self) {
+		if self.is_burning() {
+			self.burn_time -= 1;
+		}
+		let (result, cook_time) = match self.result() {
+			Some(result) => result,
+			None => {
+				self.cook = 0;
+				return;
+			}
+		};
+		self.cook_total = cook_time;
+		if !self.is_burning() {
+			// the bucket left behind by lava, or anything else that got into the fuel slot, doesn't burn
+			let (fuel, burn_time) = match self.slots[FUEL].as_mut().and_then(|fuel| fuel_ticks(fuel.item).map(|ticks| (fuel, ticks))) {
+				Some(fuel) => fuel,
+				None => {
+					// cooling down
+					self.cook = self.cook.saturating_sub(2);
+					return;
+				}
+			};
+			self.burn_time = burn_time;
+			self.burn_total = self.burn_time;
+			fuel.count -= 1;
+			if fuel.count == 0 {
+				// the lava is used up, but the bucket stays
+				self.slots[FUEL] = fuel.item.name().filter(|&name| name == "minecraft:lava_bucket").and_then(|_| "bucket".parse().ok()).map(|bucket| ItemStack::new(bucket, 1));
+			}
+		}
+		self.cook += 1;
+		if self.cook >= self.cook_total {
+			self.cook = 0;
+			match &mut self.slots[OUTPUT] {
+				Some(output) => output.count += result.count,
+				output @ None => *output = Some(result),
+			}
+			// PANICS: there was a result, so there's an input
+			let input = self.slots[INPUT].as_mut().unwrap();
+			input.count -= 1;
+			if input.count == 0 {
+				self.slots[INPUT] = None;
+			}
+		}
+	}
+}
+
+impl Window for Furnace {
+	fn slots(&self) -> &[Option<ItemStack>] {
+		&self.slots
+	}
+	fn slots_mut(&mut self) -> &mut [Option<ItemStack>] {
+		&mut self.slots
+	}
+	fn accepts(&self, slot: usize, stack: &ItemStack) -> bool {
+		slot != FUEL || fuel_ticks(stack.item).is_some()
+	}
+	fn is_output(&self, slot: usize) -> bool {
+		slot == OUTPUT
+	}
+	fn quick_move_target(&self, slot: usize, stack: &ItemStack) -> Range<usize> {
+		if slot < SIZE {
+			MAIN.start..HOTBAR.end
+		} else if (self.smelt)(stack.item).is_some() {
+			INPUT..INPUT + 1
+		} else if fuel_ticks(stack.item).is_some() {
+			FUEL..FUEL + 1
+		} else if MAIN.contains(&slot) {
+			HOTBAR
+		} else {
+			MAIN
+		}
+	}
+	/// The flame, how long the fuel lasts, the arrow, and how long the input takes
+	fn properties(&self) -> Vec<i16> {
+		[self.burn_time, self.burn_total, self.cook, self.cook_total].into_iter().map(|value| value.min(i16::MAX as u16) as i16).collect()
+	}
+	fn tick(&mut self, ticks: u32) {
+		for _ in 0..ticks {
+			self.tick_once();
+		}
+	}
+}
+
+impl Container for Furnace {
+	fn kind(&self) -> Kind {
+		Kind::Furnace
+	}
+	fn title(&self) -> &str {
+		&self.title
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::{Furnace, FUEL, INPUT, OUTPUT};
+	use crate::items::stack::ItemStack;
+	use crate::server::window::Window;
+
+	fn count(furnace: &Furnace, slot: usize) -> u8 {
+		furnace.slots()[slot].as_ref().map_or(0, |stack| stack.count)
+	}
+
+	#[test]
+	pub fn smelting() {
+		let mut furnace = Furnace::new();
+		assert!(!furnace.accepts(FUEL, &ItemStack::new("cobblestone".parse().unwrap(), 1)));
+		furnace.slots_mut()[INPUT] = Some(ItemStack::new("cobblestone".parse().unwrap(), 2));
+		furnace.slots_mut()[FUEL] = Some(ItemStack::new("stick".parse().unwrap(), 1));
+
+		// a stick lasts for half of an item
+		furnace.tick(101);
+		assert_eq!((count(&furnace, INPUT), count(&furnace, OUTPUT), furnace.is_burning()), (2, 0, false));
+		assert_eq!(furnace.properties(), [0, 100, 98, 200]);
+
+		furnace.slots_mut()[FUEL] = Some(ItemStack::new("coal".parse().unwrap(), 1));
+		furnace.tick(500);
+		assert_eq!((count(&furnace, INPUT), count(&furnace, OUTPUT), count(&furnace, FUEL)), (0, 2, 0));
+		// the rest of the coal burns away with nothing to smelt
+		assert_eq!(furnace.properties()[2], 0);
+
+		// lava leaves its bucket in the fuel slot, which doesn't burn
+		let mut furnace = Furnace::new();
+		furnace.slots_mut()[INPUT] = Some(ItemStack::new("cobblestone".parse().unwrap(), 2));
+		furnace.slots_mut()[FUEL] = Some(ItemStack::new("lava_bucket".parse().unwrap(), 1));
+		furnace.tick(20001);
+		assert_eq!((count(&furnace, INPUT), count(&furnace, OUTPUT), furnace.is_burning()), (0, 2, false));
+		assert_eq!(furnace.slots()[FUEL].as_ref().unwrap().item.name(), Some("minecraft:bucket"));
+		furnace.slots_mut()[INPUT] = Some(ItemStack::new("cobblestone".parse().unwrap(), 1));
+		furnace.tick(10);
+		assert_eq!((count(&furnace, INPUT), furnace.is_burning()), (1, false));
+	}
+}
@@ -0,0 +1,269 @@
+use super::session::transfer;
+use super::{take_all, use_ingredients, View, Window};
+use crate::items::recipes;
+use crate::items::stack::ItemStack;
+use crate::items::Item;
+use std::ops::Range;
+
+/// The player's inventory is always open as window 0
+pub const WINDOW_ID: u8 = 0;
+pub const SLOTS: usize = 46;
+pub const CRAFTING_RESULT: usize = 0;
+pub const CRAFTING_GRID: Range<usize> = 1..5;
+/// Head, chest, legs, and feet
+pub const ARMOR: Range<usize> = 5..9;
+pub const MAIN: Range<usize> = 9..36;
+pub const HOTBAR: Range<usize> = 36..45;
+pub const OFFHAND: usize = 45;
+
+/// The armor slot that the item can be worn in, by name
+fn armor_slot(item: Item) -> Option<usize> {
+	let name = item.name()?.strip_prefix("minecraft:")?;
+	if name.ends_with("_helmet") || name.ends_with("_head") || name.ends_with("_skull") || name == "carved_pumpkin" {
+		Some(ARMOR.start)
+	} else if name.ends_with("_chestplate") || name == "elytra" {
+		Some(ARMOR.start + 1)
+	} else if name.ends_with("_leggings") {
+		Some(ARMOR.start + 2)
+	} else if name.ends_with("_boots") {
+		Some(ARMOR.start + 3)
+	} else {
+		None
+	}
+}
+
+/// A player's 46 slots, laid out like window 0
+pub struct Inventory {
+	slots: Vec<Option<ItemStack>>,
+	/// Index into the hotbar
+	selected: usize,
+}
+
+impl Inventory {
+	pub fn new() -> Self {
+		Self { slots: vec![None; SLOTS], selected: 0 }
+	}
+
+	pub fn get(&self, slot: usize) -> Option<&ItemStack> {
+		self.slots[slot].as_ref()
+	}
+	/// The client is told about the change on the next sync
+	pub fn set(&mut self, slot: usize, stack: Option<ItemStack>) {
+		self.slots[slot] = stack;
+	}
+	/// The item in the selected hotbar slot
+	pub fn held(&self) -> Option<&ItemStack> {
+		self.get(HOTBAR.start + self.selected)
+	}
+	pub fn select(&mut self, hotbar_slot: usize) {
+		self.selected = hotbar_slot.min(HOTBAR.len() - 1);
+	}
+
+	/// Adds the stack to the hotbar and then the main inventory, merging it with existing stacks first. Returns whatever doesn't fit.
+	pub fn insert(&mut self, stack: ItemStack) -> Option<ItemStack> {
+		let mut stack = Some(stack);
+		for merge in [true, false] {
+			for slot in HOTBAR.chain(MAIN) {
+				if self.slots[slot].is_some() == merge {
+					let count = stack.as_ref().map_or(0, |stack| stack.count);
+					let capacity = stack.as_ref().map_or(0, |stack| stack.item.max_stack_size());
+					transfer(&mut stack, &mut self.slots[slot], count, capacity);
+				}
+			}
+		}
+		stack
+	}
+	/// Inserts every stack, returning whatever doesn't fit
+	pub fn insert_all(&mut self, stacks: impl IntoIterator<Item = ItemStack>) -> Vec<ItemStack> {
+		stacks.into_iter().filter_map(|stack| self.insert(stack)).collect()
+	}
+
+	/// Window 0
+	pub fn view(&mut self) -> View<'_> {
+		View { window: self, player: &mut [] }
+	}
+	/// The main inventory and the hotbar, which containers show after their own slots
+	pub fn view_with<'a>(&'a mut self, window: &'a mut dyn Window) -> View<'a> {
+		View {
+			window,
+			player: &mut self.slots[MAIN.start..HOTBAR.end],
+		}
+	}
+}
+
+impl Window for Inventory {
+	fn slots(&self) -> &[Option<ItemStack>] {
+		&self.slots
+	}
+	fn slots_mut(&mut self) -> &mut [Option<ItemStack>] {
+		&mut self.slots
+	}
+	fn accepts(&self, slot: usize, stack: &ItemStack) -> bool {
+		!ARMOR.contains(&slot) || (armor_slot(stack.item) == Some(slot) && stack.count <= 1)
+	}
+	fn capacity(&self, slot: usize, item: Item) -> u8 {
+		if ARMOR.contains(&slot) {
+			1
+		} else {
+			item.max_stack_size()
+		}
+	}
+	fn is_output(&self, slot: usize) -> bool {
+		slot == CRAFTING_RESULT
+	}
+	fn take_output(&mut self, _slot: usize) {
+		use_ingredients(&mut self.slots[CRAFTING_GRID]);
+	}
+	fn changed(&mut self) {
+		self.slots[CRAFTING_RESULT] = recipes::craft(&self.slots[CRAFTING_GRID], 2);
+	}
+	fn quick_move_target(&self, slot: usize, stack: &ItemStack) -> Range<usize> {
+		match armor_slot(stack.item) {
+			Some(armor) if !ARMOR.contains(&slot) && self.slots[armor].is_none() => armor..armor + 1,
+			_ if MAIN.contains(&slot) => HOTBAR,
+			_ if HOTBAR.contains(&slot) => MAIN,
+			_ => MAIN.start..HOTBAR.end,
+		}
+	}
+	fn hotbar(&self) -> Option<usize> {
+		Some(HOTBAR.start)
+	}
+	fn offhand(&self) -> Option<usize> {
+		Some(OFFHAND)
+	}
+	fn crafting_grid(&self) -> Option<(Range<usize>, usize)> {
+		Some((CRAFTING_GRID, 2))
+	}
+	/// The crafting grid is emptied when the inventory is closed
+	fn close(&mut self) -> Vec<ItemStack> {
+		self.slots[CRAFTING_RESULT] = None;
+		take_all(&mut self.slots[CRAFTING_GRID])
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::{Inventory, ARMOR, CRAFTING_GRID, CRAFTING_RESULT, HOTBAR, MAIN, OFFHAND};
+	use crate::items::recipes;
+	use crate::items::stack::ItemStack;
+	use crate::packets::helpers::game::slot::IndexedSlot;
+	use crate::packets::helpers::varint::VarInt;
+	use crate::packets::helpers::wrappers::std::{PrefixedOption, PrefixedVec};
+	use crate::packets::play::receive;
+	use crate::server::window::session::{Click, DragKind, Session};
+	use crate::server::window::Window;
+
+	fn stack(name: &str, count: u8) -> ItemStack {
+		ItemStack::new(name.parse().unwrap(), count)
+	}
+	fn count(inventory: &Inventory, slot: usize) -> u8 {
+		inventory.get(slot).map_or(0, |stack| stack.count)
+	}
+
+	#[test]
+	pub fn clicks() {
+		let mut inventory = Inventory::new();
+		let mut session = Session::new(0);
+		let mut click = |inventory: &mut Inventory, click, creative| session.click(&mut inventory.view(), click, creative);
+		assert!(inventory.insert(stack("stone", 100)).is_none());
+		assert_eq!((count(&inventory, HOTBAR.start), count(&inventory, HOTBAR.start + 1)), (64, 36));
+
+		// right click takes half, rounded up, and left click puts it all down
+		click(&mut inventory, Click::Pickup { slot: Some(HOTBAR.start + 1), right: true }, false);
+		assert_eq!(count(&inventory, HOTBAR.start + 1), 18);
+		click(&mut inventory, Click::Pickup { slot: Some(MAIN.start), right: true }, false);
+		click(&mut inventory, Click::Pickup { slot: Some(MAIN.start), right: false }, false);
+		assert_eq!(count(&inventory, MAIN.start), 18);
+
+		// dragging splits the cursor evenly
+		click(&mut inventory, Click::Pickup { slot: Some(MAIN.start), right: false }, false);
+		click(&mut inventory, Click::DragStart(DragKind::Even), false);
+		for slot in MAIN.start + 1..MAIN.start + 5 {
+			click(&mut inventory, Click::DragAdd(DragKind::Even, slot), false);
+		}
+		click(&mut inventory, Click::DragEnd(DragKind::Even), false);
+		assert_eq!(count(&inventory, MAIN.start + 1), 4);
+		// and double clicking gathers it back up, partial stacks first
+		click(&mut inventory, Click::PickupAll, false);
+		assert_eq!((count(&inventory, MAIN.start + 4), count(&inventory, HOTBAR.start + 1), count(&inventory, HOTBAR.start)), (0, 0, 36));
+		let dropped = click(&mut inventory, Click::Pickup { slot: None, right: true }, false);
+		assert_eq!(dropped[0].count, 1);
+		click(&mut inventory, Click::Pickup { slot: Some(MAIN.end - 1), right: false }, false);
+		assert_eq!(count(&inventory, MAIN.end - 1), 63);
+
+		// armor only goes in its own slot
+		inventory.set(MAIN.start, Some(stack("iron_helmet", 1)));
+		click(&mut inventory, Click::Swap { slot: MAIN.start, with: ARMOR.start + 1 }, false);
+		assert!(inventory.get(MAIN.start).is_some());
+		click(&mut inventory, Click::QuickMove(MAIN.start), false);
+		assert_eq!(inventory.get(ARMOR.start).unwrap().item.to_string(), "minecraft:iron_helmet");
+		click(&mut inventory, Click::QuickMove(MAIN.end - 1), false);
+		assert_eq!((count(&inventory, MAIN.end - 1), count(&inventory, HOTBAR.start), count(&inventory, HOTBAR.start + 1)), (0, 64, 35));
+		click(&mut inventory, Click::Swap { slot: HOTBAR.start, with: OFFHAND }, false);
+		assert_eq!(count(&inventory, OFFHAND), 64);
+		let dropped = click(&mut inventory, Click::Drop { slot: OFFHAND, all: false }, false);
+		assert_eq!((dropped.len(), count(&inventory, OFFHAND)), (1, 63));
+
+		// cloning only works in creative
+		click(&mut inventory, Click::Clone(HOTBAR.start + 1), false);
+		click(&mut inventory, Click::Clone(HOTBAR.start + 1), true);
+		let leftovers: Vec<_> = session.close().into_iter().chain(inventory.close()).collect();
+		assert_eq!(leftovers[0].count, 64);
+		assert!(inventory.insert_all(leftovers).is_empty());
+		assert_eq!((count(&inventory, HOTBAR.start + 1), count(&inventory, HOTBAR.start)), (64, 35));
+	}
+
+	#[test]
+	pub fn crafting() {
+		let mut inventory = Inventory::new();
+		let mut session = Session::new(0);
+		inventory.set(MAIN.start, Some(stack("oak_log", 3)));
+		inventory.set(MAIN.start + 1, Some(stack("oak_planks", 62)));
+
+		// the recipe book moves all three logs into the grid
+		let planks = recipes::registry().get("minecraft:oak_planks").unwrap();
+		assert!(inventory.view().place_recipe(planks, true));
+		assert_eq!((count(&inventory, CRAFTING_GRID.start), count(&inventory, MAIN.start)), (3, 0));
+		assert_eq!(count(&inventory, CRAFTING_RESULT), 4);
+		// and shift clicking crafts all of them
+		session.click(&mut inventory.view(), Click::QuickMove(CRAFTING_RESULT), false);
+		assert_eq!((count(&inventory, CRAFTING_GRID.start), count(&inventory, CRAFTING_RESULT)), (0, 0));
+		assert_eq!((count(&inventory, MAIN.start + 1), count(&inventory, MAIN.start)), (64, 10));
+
+		// sticks need two planks
+		assert!(inventory.view().place_recipe(recipes::registry().get("minecraft:stick").unwrap(), false));
+		assert_eq!(inventory.get(CRAFTING_RESULT).unwrap().item.to_string(), "minecraft:stick");
+		assert!(!inventory.view().place_recipe(recipes::registry().get("minecraft:iron_block").unwrap(), false));
+	}
+
+	#[test]
+	pub fn resync() {
+		let mut inventory = Inventory::new();
+		let mut session = Session::new(0);
+		inventory.set(MAIN.start, Some(stack("dirt", 10)));
+		session.full_sync(&inventory.view());
+		assert!(session.sync(&inventory.view()).is_empty());
+
+		// the client correctly predicts picking up the stack
+		let click = |state_id, cursor: Option<&ItemStack>| receive::ClickWindowSlot {
+			window_id: 0,
+			state_id: VarInt(state_id),
+			clicked_slot_index: MAIN.start as i16,
+			button: 0,
+			mode: VarInt(0),
+			updated_slots: PrefixedVec::new(vec![IndexedSlot {
+				slot_index: MAIN.start as i16,
+				slot_data: PrefixedOption(None),
+			}]),
+			clicked_slot_data: ItemStack::to_slot(cursor),
+		};
+		session.handle_click(&mut inventory.view(), click(1, Some(&stack("dirt", 10))), false);
+		assert!(session.sync(&inventory.view()).is_empty());
+		// but not putting it back down
+		session.handle_click(&mut inventory.view(), click(1, Some(&stack("dirt", 10))), false);
+		assert_eq!(session.sync(&inventory.view()).len(), 2);
+		// and a stale state ID resyncs everything
+		session.handle_click(&mut inventory.view(), click(1, None), false);
+		assert!(matches!(session.sync(&inventory.view())[..], [crate::packets::play::send::Packet::UpdateWindowItems(_)]));
+	}
+}
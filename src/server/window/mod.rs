@@ -0,0 +1,307 @@
+use crate::items::recipes;
+use crate::items::stack::ItemStack;
+use crate::items::Item;
+use crate::packets::helpers::game::recipes::Recipe;
+use session::transfer;
+use std::collections::HashMap;
+use std::ops::Range;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+pub mod anvil;
+pub mod chest;
+pub mod crafting_table;
+pub mod furnace;
+pub mod inventory;
+pub mod session;
+
+/// The player's main inventory and hotbar, which every container window shows below its own slots
+pub const PLAYER_SLOTS: usize = 36;
+
+/// The menu types that the client knows how to draw
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(i32)]
+pub enum Kind {
+	Generic9x1 = 0,
+	Generic9x2 = 1,
+	Generic9x3 = 2,
+	Generic9x4 = 3,
+	Generic9x5 = 4,
+	Generic9x6 = 5,
+	Generic3x3 = 6,
+	Anvil = 7,
+	Beacon = 8,
+	BlastFurnace = 9,
+	BrewingStand = 10,
+	Crafting = 11,
+	Enchantment = 12,
+	Furnace = 13,
+	Grindstone = 14,
+	Hopper = 15,
+	Lectern = 16,
+	Loom = 17,
+	Merchant = 18,
+	ShulkerBox = 19,
+	Smithing = 20,
+	Smoker = 21,
+	CartographyTable = 22,
+	Stonecutter = 23,
+}
+
+/// A set of slots that the player can click in, like their own inventory or a chest.
+///
+/// The window only holds its own slots; the player's inventory is shown after them in containers, and clicks are applied to both by a `Session`.
+pub trait Window: std::marker::Send {
+	fn slots(&self) -> &[Option<ItemStack>];
+	fn slots_mut(&mut self) -> &mut [Option<ItemStack>];
+
+	/// Whether the player can put the stack into the slot
+	fn accepts(&self, _slot: usize, _stack: &ItemStack) -> bool {
+		true
+	}
+	fn capacity(&self, _slot: usize, item: Item) -> u8 {
+		item.max_stack_size()
+	}
+	/// Output slots can only be taken from, and taking from them calls `take_output`
+	fn is_output(&self, _slot: usize) -> bool {
+		false
+	}
+	/// Uses up whatever made the output that was just taken
+	fn take_output(&mut self, _slot: usize) {}
+	/// Called after every click, so that outputs can be updated
+	fn changed(&mut self) {}
+	/// Where shift-clicking a stack sends it. Indices are the same as in clicks, so the player's inventory starts at the end of the window's own slots.
+	fn quick_move_target(&self, slot: usize, _stack: &ItemStack) -> Range<usize> {
+		let size = self.slots().len();
+		if slot < size {
+			size..size + PLAYER_SLOTS
+		} else {
+			0..size
+		}
+	}
+	/// Where the hotbar starts, if it's not right after the main inventory
+	fn hotbar(&self) -> Option<usize> {
+		None
+	}
+	fn offhand(&self) -> Option<usize> {
+		None
+	}
+	/// The slots of the crafting grid and its width, for the recipe book
+	fn crafting_grid(&self) -> Option<(Range<usize>, usize)> {
+		None
+	}
+
+	/// Sent with `UpdateWindowProperty` whenever they change, indexed by property ID
+	fn properties(&self) -> Vec<i16> {
+		Vec::new()
+	}
+	/// Returns whether the button did anything
+	fn button(&mut self, _button: u8) -> bool {
+		false
+	}
+	/// The name that the player typed into an anvil
+	fn rename(&mut self, _name: &str) {}
+	/// Called with the number of ticks since the last call, while the window is open
+	fn tick(&mut self, _ticks: u32) {}
+	/// Takes the items that go back to the player when they close the window
+	fn close(&mut self) -> Vec<ItemStack> {
+		Vec::new()
+	}
+}
+
+/// A window that's opened with `OpenWindow`, unlike the player's inventory which is always open
+pub trait Container: Window {
+	fn kind(&self) -> Kind;
+	fn title(&self) -> &str;
+}
+
+/// A container that any number of players can have open at once, like the chest at some position.
+/// It ticks for all of them together, catching up on the ticks since it was last used.
+pub struct Shared {
+	container: Box<dyn Container>,
+	last_tick: Instant,
+}
+
+impl Shared {
+	pub fn new(container: Box<dyn Container>) -> Arc<Mutex<Self>> {
+		Arc::new(Mutex::new(Self { container, last_tick: Instant::now() }))
+	}
+	/// Ticks the container once for every 50ms since the last call
+	pub fn tick(&mut self) {
+		let ticks = (self.last_tick.elapsed().as_millis() / 50) as u32;
+		if ticks > 0 {
+			self.last_tick += Duration::from_millis(u64::from(ticks) * 50);
+			self.container.tick(ticks);
+		}
+	}
+	pub fn container(&mut self) -> &mut dyn Container {
+		&mut *self.container
+	}
+}
+
+/// Takes the items out of the slots
+fn take_all(slots: &mut [Option<ItemStack>]) -> Vec<ItemStack> {
+	slots.iter_mut().filter_map(Option::take).collect()
+}
+
+/// Uses up one of each item in a crafting grid
+fn use_ingredients(grid: &mut [Option<ItemStack>]) {
+	for slot in grid {
+		if let Some(stack) = slot {
+			stack.count -= 1;
+			if stack.count == 0 {
+				*slot = None;
+			}
+		}
+	}
+}
+
+/// A window along with the player's inventory, indexed like the client does
+pub struct View<'a> {
+	pub window: &'a mut dyn Window,
+	/// The main inventory and then the hotbar. Empty for the player's own inventory window, which has them in its own slots.
+	pub player: &'a mut [Option<ItemStack>],
+}
+
+impl View<'_> {
+	fn size(&self) -> usize {
+		self.window.slots().len()
+	}
+	pub fn len(&self) -> usize {
+		self.size() + self.player.len()
+	}
+	pub fn get(&self, index: usize) -> Option<&ItemStack> {
+		let size = self.size();
+		if index < size {
+			self.window.slots()[index].as_ref()
+		} else {
+			self.player[index - size].as_ref()
+		}
+	}
+	fn slot(&mut self, index: usize) -> &mut Option<ItemStack> {
+		let size = self.size();
+		if index < size {
+			&mut self.window.slots_mut()[index]
+		} else {
+			&mut self.player[index - size]
+		}
+	}
+	fn hotbar(&self) -> usize {
+		self.window.hotbar().unwrap_or(self.size() + PLAYER_SLOTS - 9)
+	}
+	/// Whether the slot can hold the stack. An empty stack fits anywhere.
+	fn accepts(&self, index: usize, stack: Option<&ItemStack>) -> bool {
+		match stack {
+			None => true,
+			Some(stack) if index < self.size() => !self.window.is_output(index) && self.window.accepts(index, stack),
+			Some(_) => true,
+		}
+	}
+	fn capacity(&self, index: usize, item: Item) -> u8 {
+		if index < self.size() {
+			self.window.capacity(index, item)
+		} else {
+			item.max_stack_size()
+		}
+	}
+	fn is_output(&self, index: usize) -> bool {
+		index < self.size() && self.window.is_output(index)
+	}
+	/// The main inventory and the hotbar
+	fn player_slots(&self) -> Range<usize> {
+		let hotbar = self.hotbar();
+		hotbar + 9 - PLAYER_SLOTS..hotbar + 9
+	}
+
+	/// Moves the stack into the player's inventory, merging it with existing stacks first. Returns whatever doesn't fit.
+	fn give(&mut self, stack: ItemStack) -> Option<ItemStack> {
+		let mut stack = Some(stack);
+		for merge in [true, false] {
+			for slot in self.player_slots() {
+				if self.get(slot).is_some() == merge {
+					let count = stack.as_ref().map_or(0, |stack| stack.count);
+					let capacity = stack.as_ref().map_or(0, |stack| stack.item.max_stack_size());
+					transfer(&mut stack, self.slot(slot), count, capacity);
+				}
+			}
+		}
+		stack
+	}
+
+	/// Fills the crafting grid with ingredients from the player's inventory, like the recipe book does.
+	/// `all` makes as many as possible, rather than one. Returns false if the player doesn't have the ingredients.
+	pub fn place_recipe(&mut self, recipe: &Recipe, all: bool) -> bool {
+		let (grid, width) = match self.window.crafting_grid() {
+			Some(grid) => grid,
+			None => return false,
+		};
+		let layout = match recipes::layout(&recipe.data, width, grid.len() / width) {
+			Some(layout) => layout,
+			None => return false,
+		};
+		// whatever is already in the grid goes back first
+		for slot in grid.clone() {
+			if let Some(stack) = self.slot(slot).take() {
+				if let Some(leftover) = self.give(stack) {
+					*self.slot(slot) = Some(leftover);
+					return false;
+				}
+			}
+		}
+
+		// only plain items are used up, like in vanilla
+		let mut available: HashMap<Item, u32> = HashMap::new();
+		for slot in self.player_slots() {
+			if let Some(stack) = self.get(slot).filter(|stack| stack.nbt.is_empty()) {
+				*available.entry(stack.item).or_default() += u32::from(stack.count);
+			}
+		}
+		// pick the most plentiful choice for each ingredient
+		let mut left = available.clone();
+		let mut choices = Vec::with_capacity(layout.len());
+		for ingredient in layout {
+			let choice = match ingredient {
+				Some(ingredient) => match recipes::choices(ingredient).filter(|item| left.get(item).is_some_and(|&count| count > 0)).max_by_key(|item| left[item]) {
+					Some(item) => {
+						// PANICS: the item was just found in the map
+						*left.get_mut(&item).unwrap() -= 1;
+						Some(item)
+					}
+					None => return false,
+				},
+				None => None,
+			};
+			choices.push(choice);
+		}
+		let mut needed: HashMap<Item, u32> = HashMap::new();
+		for &item in choices.iter().flatten() {
+			*needed.entry(item).or_default() += 1;
+		}
+		let sets = if all {
+			needed.iter().map(|(item, &count)| (available[item] / count).min(item.max_stack_size().into())).min().unwrap_or(1)
+		} else {
+			1
+		};
+
+		for (item, count) in needed {
+			let mut count = count * sets;
+			for slot in self.player_slots() {
+				if let Some(stack) = self.slot(slot).as_mut().filter(|stack| stack.item == item && stack.nbt.is_empty()) {
+					let used = count.min(stack.count.into());
+					// PANICS: `used` is at most the stack's count
+					stack.count -= u8::try_from(used).unwrap();
+					count -= used;
+					if stack.count == 0 {
+						*self.slot(slot) = None;
+					}
+				}
+			}
+		}
+		for (slot, choice) in grid.zip(choices) {
+			// PANICS: `sets` is at most a stack size
+			*self.slot(slot) = choice.map(|item| ItemStack::new(item, u8::try_from(sets).unwrap()));
+		}
+		self.window.changed();
+		true
+	}
+}
@@ -0,0 +1,470 @@
+use super::View;
+use crate::items::stack::ItemStack;
+use crate::packets::helpers::game::slot::IndexedSlot;
+use crate::packets::helpers::varint::VarInt;
+use crate::packets::helpers::wrappers::std::PrefixedVec;
+use crate::packets::play::send::Packet as Send;
+use crate::packets::play::{receive, send};
+use log::debug;
+use std::ops::Range;
+
+/// The slot index of clicks outside of the window
+const OUTSIDE: i16 = -999;
+
+/// Moves up to `count` items from one stack onto another, if they can be stacked, without going over `capacity`. Returns how many were moved.
+pub(super) fn transfer(from: &mut Option<ItemStack>, to: &mut Option<ItemStack>, count: u8, capacity: u8) -> u8 {
+	let source = match from {
+		Some(source) => source,
+		None => return 0,
+	};
+	let existing = match to {
+		Some(target) if !target.stacks_with(source) => return 0,
+		Some(target) => target.count,
+		None => 0,
+	};
+	let moved = count.min(source.count).min(capacity.saturating_sub(existing));
+	if moved == 0 {
+		return 0;
+	}
+	match to {
+		Some(target) => target.count += moved,
+		None => *to = Some(ItemStack { count: moved, ..source.clone() }),
+	}
+	source.count -= moved;
+	if source.count == 0 {
+		*from = None;
+	}
+	moved
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DragKind {
+	/// Left button, which splits the cursor evenly
+	Even,
+	/// Right button, which puts one item in each slot
+	One,
+	/// Middle button, which puts a full stack in each slot, in creative
+	Full,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Click {
+	/// `None` is outside of the window, which drops the cursor
+	Pickup {
+		slot: Option<usize>,
+		right: bool,
+	},
+	/// Shift-click
+	QuickMove(usize),
+	/// Number keys, or F for the offhand
+	Swap {
+		slot: usize,
+		with: usize,
+	},
+	/// Middle click, in creative
+	Clone(usize),
+	/// Q, or control-Q for the whole stack
+	Drop {
+		slot: usize,
+		all: bool,
+	},
+	DragStart(DragKind),
+	DragAdd(DragKind, usize),
+	DragEnd(DragKind),
+	/// Double click
+	PickupAll,
+}
+
+impl Click {
+	/// `None` if the mode, button, and slot don't make sense together in the window
+	pub fn parse(mode: i32, button: i8, slot: i16, view: &View<'_>) -> Option<Self> {
+		let index = usize::try_from(slot).ok().filter(|&index| index < view.len());
+		let drag_kind = |button: i8| match button / 4 {
+			0 => DragKind::Even,
+			1 => DragKind::One,
+			_ => DragKind::Full,
+		};
+		Some(match (mode, button) {
+			(0, 0 | 1) if slot == OUTSIDE => Self::Pickup { slot: None, right: button == 1 },
+			(0, 0 | 1) => Self::Pickup { slot: Some(index?), right: button == 1 },
+			(1, 0 | 1) => Self::QuickMove(index?),
+			(2, 0..=8) => Self::Swap {
+				slot: index?,
+				with: view.hotbar() + button as usize,
+			},
+			(2, 40) => Self::Swap { slot: index?, with: view.window.offhand()? },
+			(3, 2) => Self::Clone(index?),
+			(4, 0 | 1) => Self::Drop { slot: index?, all: button == 1 },
+			(5, 0 | 4 | 8) if slot == OUTSIDE => Self::DragStart(drag_kind(button)),
+			(5, 1 | 5 | 9) => Self::DragAdd(drag_kind(button), index?),
+			(5, 2 | 6 | 10) if slot == OUTSIDE => Self::DragEnd(drag_kind(button)),
+			(6, 0) => Self::PickupAll,
+			_ => return None,
+		})
+	}
+}
+
+/// One open window: the item on the player's cursor, and what the client thinks the slots hold
+pub struct Session {
+	window_id: u8,
+	cursor: Option<ItemStack>,
+	remote_slots: Vec<Option<ItemStack>>,
+	remote_cursor: Option<ItemStack>,
+	remote_properties: Vec<i16>,
+	/// Echoed back by the client in clicks; if it doesn't match, the client missed an update
+	state_id: i32,
+	needs_full_sync: bool,
+	drag: Option<(DragKind, Vec<usize>)>,
+}
+
+impl Session {
+	pub fn new(window_id: u8) -> Self {
+		Self {
+			window_id,
+			cursor: None,
+			remote_slots: Vec::new(),
+			remote_cursor: None,
+			remote_properties: Vec::new(),
+			state_id: 0,
+			needs_full_sync: true,
+			drag: None,
+		}
+	}
+
+	pub fn window_id(&self) -> u8 {
+		self.window_id
+	}
+	pub fn cursor(&self) -> Option<&ItemStack> {
+		self.cursor.as_ref()
+	}
+
+	/// Applies the click, returning any items that were dropped
+	pub fn click(&mut self, view: &mut View<'_>, click: Click, creative: bool) -> Vec<ItemStack> {
+		let mut dropped = Vec::new();
+		// any other click cancels a drag
+		let drag = self.drag.take();
+		match click {
+			Click::Pickup { slot: None, right } => {
+				let count = if right { 1 } else { u8::MAX };
+				let mut stack = None;
+				transfer(&mut self.cursor, &mut stack, count, u8::MAX);
+				dropped.extend(stack);
+			}
+			Click::Pickup { slot: Some(slot), right } => self.pickup(view, slot, right),
+			Click::QuickMove(slot) => Self::quick_move(view, slot),
+			Click::Swap { slot, with } => Self::swap(view, slot, with),
+			Click::Clone(slot) => {
+				if creative && self.cursor.is_none() {
+					self.cursor = view.get(slot).map(|stack| ItemStack {
+						count: stack.item.max_stack_size(),
+						..stack.clone()
+					});
+				}
+			}
+			Click::Drop { slot, all } => {
+				if self.cursor.is_none() {
+					let mut stack = None;
+					// outputs are dropped whole
+					let count = if all || view.is_output(slot) { u8::MAX } else { 1 };
+					transfer(view.slot(slot), &mut stack, count, u8::MAX);
+					if stack.is_some() && view.is_output(slot) {
+						view.window.take_output(slot);
+					}
+					dropped.extend(stack);
+				}
+			}
+			Click::DragStart(kind) => {
+				if self.cursor.is_some() && (kind != DragKind::Full || creative) {
+					self.drag = Some((kind, Vec::new()));
+				}
+			}
+			Click::DragAdd(kind, slot) => {
+				if let (Some((drag_kind, mut slots)), Some(cursor)) = (drag, &self.cursor) {
+					let fits = view.accepts(slot, Some(cursor)) && view.get(slot).is_none_or(|stack| stack.stacks_with(cursor));
+					// each slot needs at least one item, except when they're being cloned
+					let enough = kind == DragKind::Full || usize::from(cursor.count) > slots.len();
+					if drag_kind == kind && fits && enough && !slots.contains(&slot) {
+						slots.push(slot);
+					}
+					if drag_kind == kind {
+						self.drag = Some((drag_kind, slots));
+					}
+				}
+			}
+			Click::DragEnd(kind) => match drag {
+				Some((drag_kind, slots)) if drag_kind == kind && slots.len() == 1 && kind != DragKind::Full => self.pickup(view, slots[0], kind == DragKind::One),
+				Some((drag_kind, slots)) if drag_kind == kind => self.distribute(view, kind, &slots),
+				_ => {}
+			},
+			Click::PickupAll => self.pickup_all(view),
+		}
+		view.window.changed();
+		dropped
+	}
+
+	fn pickup(&mut self, view: &mut View<'_>, slot: usize, right: bool) {
+		if view.is_output(slot) {
+			let output = match view.get(slot) {
+				Some(output) => output.clone(),
+				None => return,
+			};
+			// the whole output has to fit on the cursor
+			let fits = self.cursor.as_ref().is_none_or(|cursor| cursor.stacks_with(&output) && cursor.count + output.count <= cursor.item.max_stack_size());
+			if fits {
+				transfer(view.slot(slot), &mut self.cursor, output.count, u8::MAX);
+				view.window.take_output(slot);
+			}
+			return;
+		}
+		let accepts = view.accepts(slot, self.cursor.as_ref());
+		let capacity = self.cursor.as_ref().map_or(0, |cursor| view.capacity(slot, cursor.item));
+		let cursor = &mut self.cursor;
+		let stack = view.slot(slot);
+		match (cursor.as_ref(), stack.as_ref()) {
+			(_, None) if !accepts => {}
+			(None, Some(taken)) => {
+				let count = if right { taken.count.div_ceil(2) } else { taken.count };
+				transfer(stack, cursor, count, u8::MAX);
+			}
+			(Some(held), existing) if existing.is_none_or(|existing| existing.stacks_with(held)) => {
+				let count = if right { 1 } else { held.count };
+				transfer(cursor, stack, count, capacity);
+			}
+			(Some(_), _) => {
+				if accepts {
+					std::mem::swap(cursor, stack);
+				}
+			}
+			(None, None) => {}
+		}
+	}
+
+	/// Moves the stack into the first slots in the range that can take it, merging it with existing stacks first
+	fn move_into(view: &mut View<'_>, from: usize, range: Range<usize>) {
+		let mut stack = view.slot(from).take();
+		for merge in [true, false] {
+			for slot in range.clone() {
+				if slot != from && view.get(slot).is_some() == merge && view.accepts(slot, stack.as_ref()) {
+					let count = stack.as_ref().map_or(0, |stack| stack.count);
+					let capacity = stack.as_ref().map_or(0, |stack| view.capacity(slot, stack.item));
+					transfer(&mut stack, view.slot(slot), count, capacity);
+				}
+			}
+		}
+		*view.slot(from) = stack;
+	}
+
+	fn quick_move(view: &mut View<'_>, slot: usize) {
+		let output = view.is_output(slot);
+		loop {
+			let stack = match view.get(slot) {
+				Some(stack) => stack.clone(),
+				None => return,
+			};
+			// outputs are all or nothing
+			let backup: Option<Vec<_>> = output.then(|| (0..view.len()).map(|index| view.get(index).cloned()).collect());
+			let range = view.window.quick_move_target(slot, &stack);
+			Self::move_into(view, slot, range);
+			let backup = match backup {
+				Some(backup) => backup,
+				None => return,
+			};
+			if view.get(slot).is_some() {
+				for (index, stack) in backup.into_iter().enumerate() {
+					*view.slot(index) = stack;
+				}
+				return;
+			}
+			// keep crafting until the output changes
+			view.window.take_output(slot);
+			view.window.changed();
+			if !view.get(slot).is_some_and(|next| next.stacks_with(&stack)) {
+				return;
+			}
+		}
+	}
+
+	fn swap(view: &mut View<'_>, slot: usize, with: usize) {
+		if slot == with || with >= view.len() || !view.accepts(slot, view.get(with)) || !view.accepts(with, view.get(slot)) {
+			return;
+		}
+		let took_output = view.is_output(slot) && view.get(slot).is_some();
+		let first = view.slot(slot).take();
+		let second = std::mem::replace(view.slot(with), first);
+		*view.slot(slot) = second;
+		if took_output {
+			view.window.take_output(slot);
+		}
+	}
+
+	fn distribute(&mut self, view: &mut View<'_>, kind: DragKind, slots: &[usize]) {
+		let cursor = match &mut self.cursor {
+			Some(cursor) if !slots.is_empty() => cursor,
+			_ => return,
+		};
+		let per_slot = match kind {
+			// PANICS: the cursor had more items than there are slots when they were added
+			DragKind::Even => cursor.count / u8::try_from(slots.len()).unwrap(),
+			DragKind::One => 1,
+			DragKind::Full => cursor.item.max_stack_size(),
+		};
+		for &slot in slots {
+			let mut source = Some(ItemStack {
+				count: if kind == DragKind::Full { per_slot } else { per_slot.min(cursor.count) },
+				..cursor.clone()
+			});
+			let capacity = view.capacity(slot, cursor.item);
+			let moved = transfer(&mut source, view.slot(slot), per_slot, capacity);
+			if kind != DragKind::Full {
+				cursor.count -= moved;
+			}
+		}
+		if cursor.count == 0 {
+			self.cursor = None;
+		}
+	}
+
+	/// Gathers stacks of the cursor's item onto it, partial stacks first
+	fn pickup_all(&mut self, view: &mut View<'_>) {
+		let max = match &self.cursor {
+			Some(cursor) => cursor.item.max_stack_size(),
+			None => return,
+		};
+		for full in [false, true] {
+			for slot in 0..view.len() {
+				let is_full = view.get(slot).is_some_and(|stack| stack.count == stack.item.max_stack_size());
+				if is_full == full && !view.is_output(slot) {
+					transfer(view.slot(slot), &mut self.cursor, u8::MAX, max);
+				}
+			}
+		}
+	}
+
+	/// Applies a click from the client, and keeps track of what it thinks happened so that the next `sync` can correct it. Returns any items that were dropped.
+	pub fn handle_click(&mut self, view: &mut View<'_>, packet: receive::ClickWindowSlot, creative: bool) -> Vec<ItemStack> {
+		if packet.state_id.0 != self.state_id {
+			debug!("Client sent a click with state ID {} but we're at {}", packet.state_id.0, self.state_id);
+			self.needs_full_sync = true;
+		}
+		let dropped = match Click::parse(packet.mode.0, packet.button, packet.clicked_slot_index, view) {
+			Some(click) => self.click(view, click, creative),
+			None => {
+				debug!("Ignoring invalid click with mode {}, button {}, and slot {}", packet.mode.0, packet.button, packet.clicked_slot_index);
+				self.needs_full_sync = true;
+				Vec::new()
+			}
+		};
+		for IndexedSlot { slot_index, slot_data } in packet.updated_slots.0 {
+			match (usize::try_from(slot_index).ok().filter(|&slot| slot < self.remote_slots.len()), ItemStack::from_slot(slot_data)) {
+				(Some(slot), Ok(stack)) => self.remote_slots[slot] = stack,
+				_ => self.needs_full_sync = true,
+			}
+		}
+		match ItemStack::from_slot(packet.clicked_slot_data) {
+			Ok(cursor) => self.remote_cursor = cursor,
+			Err(_) => self.needs_full_sync = true,
+		}
+		dropped
+	}
+
+	/// Sets a slot that the client has already set on its side. Stacks that are too big are rejected, and the client is corrected on the next sync.
+	pub fn set_from_client(&mut self, view: &mut View<'_>, slot: usize, stack: Option<ItemStack>) {
+		if stack.as_ref().is_some_and(|stack| stack.count > view.capacity(slot, stack.item)) {
+			self.needs_full_sync = true;
+			return;
+		}
+		if let Some(remote) = self.remote_slots.get_mut(slot) {
+			remote.clone_from(&stack);
+		}
+		*view.slot(slot) = stack;
+		view.window.changed();
+	}
+
+	/// For changes the client made on its side that were refused, so that the next sync corrects every slot
+	pub fn resync(&mut self) {
+		self.needs_full_sync = true;
+	}
+
+	/// Takes the cursor when the window is closed
+	pub fn close(&mut self) -> Option<ItemStack> {
+		self.drag = None;
+		self.cursor.take()
+	}
+
+	fn next_state_id(&mut self) -> VarInt {
+		// vanilla keeps it within 15 bits
+		self.state_id = (self.state_id + 1) & 0x7fff;
+		VarInt(self.state_id)
+	}
+
+	fn property_packets(&mut self, view: &View<'_>, all: bool) -> Vec<Send<'static>> {
+		let properties = view.window.properties();
+		let packets = properties
+			.iter()
+			.enumerate()
+			.filter(|&(id, value)| all || self.remote_properties.get(id) != Some(value))
+			.map(|(id, &value)| {
+				Send::UpdateWindowProperty(send::UpdateWindowProperty {
+					window_id: self.window_id,
+					property_id: id as i16,
+					new_value: value,
+				})
+			})
+			.collect();
+		self.remote_properties = properties;
+		packets
+	}
+
+	/// Every slot, the cursor, and every property
+	pub fn full_sync(&mut self, view: &View<'_>) -> Vec<Send<'static>> {
+		self.needs_full_sync = false;
+		self.remote_slots = (0..view.len()).map(|index| view.get(index).cloned()).collect();
+		self.remote_cursor.clone_from(&self.cursor);
+		let mut packets = vec![Send::UpdateWindowItems(send::UpdateWindowItems {
+			window_id: self.window_id,
+			state_id: self.next_state_id(),
+			slot_data: PrefixedVec::new(self.remote_slots.iter().map(|stack| ItemStack::to_slot(stack.as_ref())).collect()),
+			floating_item: ItemStack::to_slot(self.cursor.as_ref()),
+		})];
+		packets.extend(self.property_packets(view, true));
+		packets
+	}
+
+	/// The packets that bring the client up to date with the slots and properties that it's wrong about
+	pub fn sync(&mut self, view: &View<'_>) -> Vec<Send<'static>> {
+		if self.needs_full_sync || self.remote_slots.len() != view.len() {
+			return self.full_sync(view);
+		}
+		let changed: Vec<_> = (0..view.len()).filter(|&slot| view.get(slot) != self.remote_slots[slot].as_ref()).collect();
+		let cursor_changed = self.cursor != self.remote_cursor;
+		let mut packets = Vec::new();
+		if !changed.is_empty() || cursor_changed {
+			let state_id = self.next_state_id();
+			for slot in changed {
+				self.remote_slots[slot] = view.get(slot).cloned();
+				packets.push(Send::SetWindowSlot(send::SetWindowSlot {
+					window_id: self.window_id as i8,
+					state_id,
+					slot: IndexedSlot {
+						slot_index: slot as i16,
+						slot_data: ItemStack::to_slot(view.get(slot)),
+					},
+				}));
+			}
+			if cursor_changed {
+				self.remote_cursor.clone_from(&self.cursor);
+				// window -1 and slot -1 is the cursor
+				packets.push(Send::SetWindowSlot(send::SetWindowSlot {
+					window_id: -1,
+					state_id,
+					slot: IndexedSlot {
+						slot_index: -1,
+						slot_data: ItemStack::to_slot(self.cursor.as_ref()),
+					},
+				}));
+			}
+		}
+		packets.extend(self.property_packets(view, false));
+		packets
+	}
+}
@@ -0,0 +1,667 @@
+use super::{block_states, level, BlockState, ChunkPosition, Column, Dimension, Section};
+use crate::packets::helpers::game::chunk::{build_palette, ceil_log2, pack_entries, unpack_entries};
+use log::{debug, warn};
+use serde::Deserialize;
+use std::cell::Cell;
+use std::collections::{HashMap, HashSet};
+use std::fs::{File, OpenOptions};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+const SECTOR_SIZE: u64 = 4096;
+/// Chunks per region, along each axis
+const REGION_SIZE: i32 = 32;
+/// 21w43a, the first version to use the current chunk layout (no `Level` compound, `sections` with paletted `block_states` and `biomes`)
+const MIN_DATA_VERSION: i32 = 2844;
+
+#[derive(Deserialize)]
+struct ChunkNbt {
+	#[serde(rename = "DataVersion")]
+	data_version: i32,
+	#[serde(rename = "Status", default)]
+	status: String,
+	#[serde(default)]
+	sections: Vec<SectionNbt>,
+}
+
+#[derive(Deserialize)]
+struct SectionNbt {
+	#[serde(rename = "Y")]
+	y: i8,
+	/// Absent for the sections above and below the world that only hold light
+	block_states: Option<PalettedNbt<BlockStateNbt>>,
+	biomes: Option<PalettedNbt<String>>,
+}
+
+#[derive(Deserialize)]
+struct PalettedNbt<T> {
+	palette: Vec<T>,
+	/// Absent if the palette has a single entry
+	#[serde(default)]
+	data: Vec<i64>,
+}
+
+#[derive(Deserialize)]
+struct BlockStateNbt {
+	#[serde(rename = "Name")]
+	name: String,
+	#[serde(rename = "Properties", default)]
+	properties: HashMap<String, String>,
+}
+
+/// Resolves a paletted container from storage into one global ID per entry.
+/// `min_bits` is the minimum number of bits per entry, which is 4 for block states and 0 for biomes.
+fn unpalette<T>(container: &PalettedNbt<T>, count: usize, min_bits: u8, resolve: impl Fn(&T) -> i32) -> anyhow::Result<Vec<i32>> {
+	let palette: Vec<i32> = container.palette.iter().map(resolve).collect();
+	match palette.as_slice() {
+		[] => anyhow::bail!("Empty palette"),
+		[single] => Ok(vec![*single; count]),
+		_ => {
+			let bits_per_entry = ceil_log2(palette.len()).max(min_bits);
+			let data: Vec<u64> = container.data.iter().map(|&long| long as u64).collect();
+			let indices = unpack_entries(&data, bits_per_entry, count);
+			anyhow::ensure!(indices.len() == count, "Expected {} entries but the data only holds {}", count, indices.len());
+			indices.into_iter().map(|idx| palette.get(idx as usize).copied().ok_or_else(|| anyhow::anyhow!("Palette index {} is out of range", idx))).collect()
+		}
+	}
+}
+
+/// Everything in a stored chunk that isn't loaded into a `Column`, so that it survives being saved again
+pub(super) struct StoredChunk {
+	/// The chunk compound as it was loaded, including block entities, entities, heightmaps, and the light and biomes of each section
+	compound: HashMap<String, nbt::Value>,
+	/// The Y of each section with block states that aren't in the registry. Their blocks are always saved as they were loaded.
+	unknown_states: HashSet<i8>,
+}
+
+/// The root compound of stored NBT, read as-is since deserializing into `nbt::Value` can't tell lists and arrays apart.
+/// Also used for other files that are a single compound, like player data.
+pub(super) fn read_root_compound(mut reader: impl Read) -> anyhow::Result<HashMap<String, nbt::Value>> {
+	const TAG_COMPOUND: u8 = 0x0a;
+	// the compound's tag type, then its name
+	let mut header = [0; 3];
+	reader.read_exact(&mut header).map_err(|err| anyhow::Error::new(err).context("The root tag is cut off"))?;
+	anyhow::ensure!(header[0] == TAG_COMPOUND, "The root tag is not a compound");
+	let name_len = u16::from_be_bytes([header[1], header[2]]);
+	let skipped = std::io::copy(&mut (&mut reader).take(name_len.into()), &mut std::io::sink())?;
+	anyhow::ensure!(skipped == u64::from(name_len), "The root tag's name is cut off");
+	match nbt::Value::from_reader(TAG_COMPOUND, &mut reader)? {
+		nbt::Value::Compound(compound) => Ok(compound.into_iter().collect()),
+		_ => anyhow::bail!("The root tag is not a compound"),
+	}
+}
+
+impl SectionNbt {
+	/// Block states that aren't in the registry are replaced, and `unknown` is set
+	fn to_section(&self, dimension: &Dimension, unknown: &Cell<bool>) -> anyhow::Result<Section> {
+		let block_states = match &self.block_states {
+			Some(block_states) => unpalette(block_states, 4096, 4, |entry| {
+				let registry = block_states::registry();
+				let state = registry.state_of(&entry.name, entry.properties.iter().map(|(key, value)| (key.as_str(), value.as_str())));
+				if state.is_none() {
+					unknown.set(true);
+				}
+				let state = state.or_else(|| {
+					debug!("Unknown block state {} {:?}; loading the block's default state", entry.name, entry.properties);
+					registry.default_state(&entry.name)
+				});
+				let state = state.unwrap_or_else(|| {
+					debug!("Unknown block {}; loading it as stone", entry.name);
+					// PANICS: stone is in the registry
+					registry.default_state("minecraft:stone").unwrap()
+				});
+				i32::from(state.id())
+			})?,
+			None => vec![BlockState::AIR.id().into(); 4096],
+		};
+		let biomes = match &self.biomes {
+			Some(biomes) => unpalette(biomes, 64, 0, |name| {
+				dimension.biome_ids.get(name).copied().unwrap_or_else(|| {
+					debug!("Unknown biome {}; loading it as the default biome", name);
+					dimension.default_biome
+				})
+			})?,
+			None => vec![dimension.default_biome; 64],
+		};
+		// PANICS: unpalette returns exactly as many entries as requested
+		Ok(Section::new(block_states.into_iter().map(|state| BlockState(state as u16)).collect(), biomes.try_into().unwrap()))
+	}
+}
+
+/// The inverse of `unpalette`; `resolve` turns a global ID into its palette entry
+fn palette_to_nbt(entries: &[i32], min_bits: u8, resolve: impl Fn(i32) -> anyhow::Result<nbt::Value>) -> anyhow::Result<nbt::Value> {
+	let (palette, indices) = build_palette(entries);
+	let mut container = HashMap::new();
+	if palette.len() > 1 {
+		let bits_per_entry = ceil_log2(palette.len()).max(min_bits);
+		container.insert("data".to_owned(), nbt::Value::LongArray(pack_entries(&indices, bits_per_entry).into_iter().map(|long| long as i64).collect()));
+	}
+	container.insert("palette".to_owned(), nbt::Value::List(palette.into_iter().map(resolve).collect::<anyhow::Result<_>>()?));
+	Ok(nbt::Value::Compound(container.into_iter().collect()))
+}
+
+impl Section {
+	/// Fails if a block state isn't in the registry, rather than saving something else in its place
+	fn block_states_to_nbt(&self) -> anyhow::Result<nbt::Value> {
+		let block_states: Vec<i32> = match &self.block_states {
+			Some(block_states) => block_states.iter().map(|state| i32::from(state.id())).collect(),
+			None => vec![BlockState::AIR.id().into(); 4096],
+		};
+		palette_to_nbt(&block_states, 4, |state| {
+			let state = BlockState(state as u16);
+			let (name, properties) = state.name().zip(state.properties()).ok_or_else(|| anyhow::anyhow!("Block state {} isn't in the registry", state))?;
+			let mut entry = HashMap::new();
+			entry.insert("Name".to_owned(), nbt::Value::String(name.to_owned()));
+			if !properties.is_empty() {
+				entry.insert("Properties".to_owned(), nbt::Value::Compound(properties.iter().map(|(key, value)| (key.clone(), nbt::Value::String(value.clone()))).collect()));
+			}
+			Ok(nbt::Value::Compound(entry.into_iter().collect()))
+		})
+	}
+	fn biomes_to_nbt(&self, dimension: &Dimension) -> nbt::Value {
+		let biomes = palette_to_nbt(&self.biomes, 0, |biome| {
+			let name = dimension.biome_names.get(&biome).or_else(|| dimension.biome_names.get(&dimension.default_biome));
+			Ok(nbt::Value::String(name.cloned().unwrap_or_else(|| "minecraft:plains".to_owned())))
+		});
+		// PANICS: resolving biomes can't fail
+		biomes.unwrap()
+	}
+}
+
+/// The start of the first run of `length` unused sectors, which may continue past the end of `used`
+fn first_fit(used: &[bool], length: usize) -> usize {
+	let mut start = 0;
+	for (sector, &used) in used.iter().enumerate() {
+		if used {
+			start = sector + 1;
+		} else if sector + 1 - start == length {
+			return start;
+		}
+	}
+	start
+}
+
+struct RegionFile {
+	file: File,
+	/// Region files are opened read-only until something is saved in them
+	writable: bool,
+}
+
+/// Chunk columns stored in the Anvil format used by vanilla: `region/r.<x>.<z>.mca` files of 32x32 columns, each stored as compressed NBT
+pub struct RegionStorage {
+	/// `<world>/region`
+	directory: PathBuf,
+	/// `None` if the region file doesn't exist
+	regions: HashMap<(i32, i32), Option<RegionFile>>,
+}
+
+impl RegionStorage {
+	/// `world_directory` is the directory containing `level.dat`
+	pub fn new(world_directory: &Path) -> Self {
+		Self {
+			directory: world_directory.join("region"),
+			regions: HashMap::new(),
+		}
+	}
+	fn region_path(&self, region_x: i32, region_z: i32) -> PathBuf {
+		self.directory.join(format!("r.{}.{}.mca", region_x, region_z))
+	}
+	/// Opened read-only, so that worlds can be loaded from read-only directories
+	fn region(&mut self, region_x: i32, region_z: i32) -> anyhow::Result<Option<&mut File>> {
+		let path = self.region_path(region_x, region_z);
+		let region = match self.regions.entry((region_x, region_z)) {
+			std::collections::hash_map::Entry::Occupied(entry) => entry.into_mut(),
+			std::collections::hash_map::Entry::Vacant(entry) => entry.insert(match File::open(&path) {
+				Ok(file) => Some(RegionFile { file, writable: false }),
+				Err(err) if err.kind() == std::io::ErrorKind::NotFound => None,
+				Err(err) => return Err(anyhow::Error::new(err).context(format!("Opening region file {}", path.display()))),
+			}),
+		};
+		Ok(region.as_mut().map(|region| &mut region.file))
+	}
+	/// Like `region`, but opened for writing too, and the region file is created if it doesn't exist
+	fn region_for_writing(&mut self, region_x: i32, region_z: i32) -> anyhow::Result<&mut File> {
+		let exists = self.region(region_x, region_z)?.is_some();
+		let writable = matches!(self.regions.get(&(region_x, region_z)), Some(Some(region)) if region.writable);
+		if !writable {
+			let path = self.region_path(region_x, region_z);
+			if !exists {
+				std::fs::create_dir_all(&self.directory)?;
+			}
+			let file = OpenOptions::new()
+				.read(true)
+				.write(true)
+				.create(true)
+				.truncate(false)
+				.open(&path)
+				.map_err(|err| anyhow::Error::new(err).context(format!("Opening region file {} for writing", path.display())))?;
+			if !exists {
+				// the location and timestamp tables
+				file.set_len(2 * SECTOR_SIZE)?;
+			}
+			self.regions.insert((region_x, region_z), Some(RegionFile { file, writable: true }));
+		}
+		// PANICS: the region was inserted above if it wasn't writable
+		Ok(&mut self.regions.get_mut(&(region_x, region_z)).unwrap().as_mut().unwrap().file)
+	}
+	/// The uncompressed NBT data of a column, or `None` if it has never been saved
+	fn read_chunk(&mut self, position: ChunkPosition) -> anyhow::Result<Option<Vec<u8>>> {
+		let external_path = self.directory.join(format!("c.{}.{}.mcc", position.x, position.z));
+		let file = match self.region(position.x.div_euclid(REGION_SIZE), position.z.div_euclid(REGION_SIZE))? {
+			Some(file) => file,
+			None => return Ok(None),
+		};
+		let index = position.x.rem_euclid(REGION_SIZE) + position.z.rem_euclid(REGION_SIZE) * REGION_SIZE;
+		file.seek(SeekFrom::Start(index as u64 * 4))?;
+		let mut location = [0u8; 4];
+		file.read_exact(&mut location)?;
+		// 3 bytes of offset, 1 byte of length, both in sectors
+		let offset = u64::from(u32::from_be_bytes([0, location[0], location[1], location[2]]));
+		if offset == 0 {
+			return Ok(None);
+		}
+		file.seek(SeekFrom::Start(offset * SECTOR_SIZE))?;
+		let mut header = [0u8; 5];
+		file.read_exact(&mut header)?;
+		let length = u32::from_be_bytes([header[0], header[1], header[2], header[3]]);
+		let compression = header[4];
+		let compressed = if compression & 0x80 != 0 {
+			// too big for the region file, so stored separately
+			std::fs::read(&external_path).map_err(|err| anyhow::Error::new(err).context(format!("Reading external chunk {}", external_path.display())))?
+		} else {
+			// the length includes the compression byte
+			let mut compressed = vec![0u8; (length as usize).saturating_sub(1)];
+			file.read_exact(&mut compressed)?;
+			compressed
+		};
+		let mut data = Vec::new();
+		match compression & 0x7f {
+			1 => {
+				flate2::read::GzDecoder::new(compressed.as_slice()).read_to_end(&mut data)?;
+			}
+			2 => {
+				flate2::read::ZlibDecoder::new(compressed.as_slice()).read_to_end(&mut data)?;
+			}
+			3 => data = compressed,
+			other => anyhow::bail!("Unknown chunk compression type {}", other),
+		}
+		Ok(Some(data))
+	}
+	/// Stores the uncompressed NBT data of a column, replacing the old data if there was any.
+	/// Data is rewritten in place if it still fits and moved to the first gap that's big enough otherwise; like in vanilla, chunks bigger than 1 MiB go in a separate file.
+	fn write_chunk(&mut self, position: ChunkPosition, data: &[u8]) -> anyhow::Result<()> {
+		let mut compressed = Vec::new();
+		{
+			let mut encoder = flate2::write::ZlibEncoder::new(&mut compressed, flate2::Compression::default());
+			encoder.write_all(data)?;
+			encoder.finish()?;
+		}
+		let external_path = self.directory.join(format!("c.{}.{}.mcc", position.x, position.z));
+		// the header is the 4-byte length and the compression type
+		let sectors_needed = (compressed.len() as u64 + 5).div_ceil(SECTOR_SIZE);
+		let (compression, payload) = if sectors_needed > 255 {
+			std::fs::write(&external_path, &compressed)?;
+			(2 | 0x80, Vec::new())
+		} else {
+			match std::fs::remove_file(&external_path) {
+				Err(err) if err.kind() != std::io::ErrorKind::NotFound => return Err(err.into()),
+				_ => {}
+			}
+			(2, compressed)
+		};
+		let sectors_needed = (payload.len() as u64 + 5).div_ceil(SECTOR_SIZE);
+
+		let file = self.region_for_writing(position.x.div_euclid(REGION_SIZE), position.z.div_euclid(REGION_SIZE))?;
+		let index = (position.x.rem_euclid(REGION_SIZE) + position.z.rem_euclid(REGION_SIZE) * REGION_SIZE) as u64;
+		file.seek(SeekFrom::Start(index * 4))?;
+		let mut location = [0u8; 4];
+		file.read_exact(&mut location)?;
+		let old_offset = u64::from(u32::from_be_bytes([0, location[0], location[1], location[2]]));
+		let old_sectors = u64::from(location[3]);
+		let offset = if old_offset != 0 && sectors_needed <= old_sectors {
+			old_offset
+		} else {
+			// the old sectors are still marked as used, so the column isn't lost if writing the new data fails
+			let mut locations = [0u8; 4 * (REGION_SIZE * REGION_SIZE) as usize];
+			file.seek(SeekFrom::Start(0))?;
+			file.read_exact(&mut locations)?;
+			// the first two sectors are the location and timestamp tables
+			let mut used = vec![true; 2];
+			for location in locations.chunks_exact(4) {
+				let offset = u32::from_be_bytes([0, location[0], location[1], location[2]]) as usize;
+				let end = offset + usize::from(location[3]);
+				if offset == 0 {
+					continue;
+				}
+				if used.len() < end {
+					used.resize(end, false);
+				}
+				used[offset..end].fill(true);
+			}
+			first_fit(&used, sectors_needed as usize) as u64
+		};
+
+		let mut sectors = Vec::with_capacity((sectors_needed * SECTOR_SIZE) as usize);
+		sectors.extend_from_slice(&(payload.len() as u32 + 1).to_be_bytes());
+		sectors.push(compression);
+		sectors.extend_from_slice(&payload);
+		sectors.resize((sectors_needed * SECTOR_SIZE) as usize, 0);
+		file.seek(SeekFrom::Start(offset * SECTOR_SIZE))?;
+		file.write_all(&sectors)?;
+
+		let offset_bytes = (offset as u32).to_be_bytes();
+		file.seek(SeekFrom::Start(index * 4))?;
+		file.write_all(&[offset_bytes[1], offset_bytes[2], offset_bytes[3], sectors_needed as u8])?;
+		let timestamp = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).map_or(0, |time| time.as_secs() as u32);
+		file.seek(SeekFrom::Start(SECTOR_SIZE + index * 4))?;
+		file.write_all(&timestamp.to_be_bytes())?;
+		Ok(())
+	}
+	/// A column that was loaded from storage is saved on top of what was loaded, so only the blocks of the sections that have changed are replaced
+	pub(super) fn save_column(&mut self, position: ChunkPosition, column: &Column, dimension: &Dimension) -> anyhow::Result<()> {
+		let no_unknown_states = HashSet::new();
+		let (mut chunk, unknown_states) = match &column.stored {
+			Some(stored) => (stored.compound.clone(), &stored.unknown_states),
+			None => (HashMap::new(), &no_unknown_states),
+		};
+		let mut stored_sections: HashMap<i8, HashMap<String, nbt::Value>> = match chunk.remove("sections") {
+			Some(nbt::Value::List(sections)) => sections
+				.into_iter()
+				.filter_map(|section| match section {
+					nbt::Value::Compound(section) => match section.get("Y") {
+						Some(&nbt::Value::Byte(y)) => Some((y, section.into_iter().collect())),
+						_ => None,
+					},
+					_ => None,
+				})
+				.collect(),
+			_ => HashMap::new(),
+		};
+		let min_section = dimension.min_y >> 4;
+		let mut sections = Vec::with_capacity(column.sections.len());
+		for (idx, section) in column.sections.iter().enumerate() {
+			let y = (min_section + idx as i32) as i8;
+			let mut compound = stored_sections.remove(&y).unwrap_or_default();
+			if section.modified || !compound.contains_key("block_states") {
+				let block_states = if unknown_states.contains(&y) {
+					Err(anyhow::anyhow!("It has block states that aren't in the registry"))
+				} else {
+					section.block_states_to_nbt()
+				};
+				match block_states {
+					Ok(block_states) => {
+						compound.insert("block_states".to_owned(), block_states);
+					}
+					Err(err) if compound.contains_key("block_states") => warn!("Not saving the changes to section {} of chunk at {:?}: {:#}", y, position, err),
+					Err(err) => return Err(err.context(format!("Saving section {} of chunk at {:?}", y, position))),
+				}
+			}
+			// biomes never change once they're loaded
+			if !compound.contains_key("biomes") {
+				compound.insert("biomes".to_owned(), section.biomes_to_nbt(dimension));
+			}
+			compound.insert("Y".to_owned(), nbt::Value::Byte(y));
+			sections.push((y, compound));
+		}
+		// the light-only sections above and below the world
+		sections.extend(stored_sections);
+		sections.sort_unstable_by_key(|&(y, _)| y);
+		let sections = sections.into_iter().map(|(_, section)| nbt::Value::Compound(section.into_iter().collect())).collect();
+
+		chunk.insert("DataVersion".to_owned(), nbt::Value::Int(level::DATA_VERSION));
+		chunk.insert("xPos".to_owned(), nbt::Value::Int(position.x));
+		chunk.insert("yPos".to_owned(), nbt::Value::Int(min_section));
+		chunk.insert("zPos".to_owned(), nbt::Value::Int(position.z));
+		chunk.insert("sections".to_owned(), nbt::Value::List(sections));
+		chunk.entry("Status".to_owned()).or_insert_with(|| nbt::Value::String("full".to_owned()));
+		chunk.entry("LastUpdate".to_owned()).or_insert(nbt::Value::Long(0));
+		chunk.entry("InhabitedTime".to_owned()).or_insert(nbt::Value::Long(0));
+		chunk.entry("block_entities".to_owned()).or_insert_with(|| nbt::Value::List(Vec::new()));
+		// the stored light is out of date once blocks have changed, and new columns have none, so make vanilla compute it
+		if column.stored.is_none() || column.sections.iter().any(|section| section.modified) {
+			chunk.insert("isLightOn".to_owned(), nbt::Value::Byte(0));
+		}
+		let mut blob = nbt::Blob::new();
+		for (key, value) in chunk {
+			blob.insert(key, value)?;
+		}
+		let mut data = Vec::new();
+		blob.to_writer(&mut data)?;
+		self.write_chunk(position, &data).map_err(|err| err.context(format!("Saving chunk at {:?}", position)))
+	}
+	/// `None` if the column has never been saved or was not fully generated
+	pub(super) fn load_column(&mut self, position: ChunkPosition, dimension: &Dimension) -> anyhow::Result<Option<Column>> {
+		let data = match self.read_chunk(position)? {
+			Some(data) => data,
+			None => return Ok(None),
+		};
+		let chunk: ChunkNbt = nbt::from_reader(data.as_slice()).map_err(|err| anyhow::Error::new(err).context(format!("Parsing chunk at {:?}", position)))?;
+		anyhow::ensure!(
+			chunk.data_version >= MIN_DATA_VERSION,
+			"Chunk at {:?} has data version {}, which predates the 1.18 chunk format; upgrade the world with a vanilla 1.18 server first",
+			position,
+			chunk.data_version
+		);
+		if chunk.status.trim_start_matches("minecraft:") != "full" {
+			debug!("Chunk at {:?} is not fully generated ({}); ignoring it", position, chunk.status);
+			return Ok(None);
+		}
+		let mut column = Column::empty(dimension.num_sections, dimension.default_biome);
+		let mut unknown_states = HashSet::new();
+		let min_section = dimension.min_y >> 4;
+		for section in chunk.sections.iter() {
+			let idx = match usize::try_from(i32::from(section.y) - min_section) {
+				Ok(idx) if idx < dimension.num_sections => idx,
+				// light-only sections
+				_ => continue,
+			};
+			let unknown = Cell::new(false);
+			column.sections[idx] = section.to_section(dimension, &unknown).map_err(|err| err.context(format!("Section {} of chunk at {:?}", section.y, position)))?;
+			if unknown.get() {
+				unknown_states.insert(section.y);
+			}
+		}
+		column.stored = Some(StoredChunk {
+			compound: read_root_compound(data.as_slice()).map_err(|err| err.context(format!("Parsing chunk at {:?}", position)))?,
+			unknown_states,
+		});
+		Ok(Some(column))
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::super::{BlockState, ChunkPosition, Column, Dimension, Section};
+	use super::{first_fit, read_root_compound, RegionStorage, SECTOR_SIZE};
+	use crate::packets::helpers::game::chunk::pack_entries;
+	use std::collections::HashMap;
+	use std::io::Write;
+
+	fn compound(entries: Vec<(&str, nbt::Value)>) -> nbt::Value {
+		nbt::Value::Compound(entries.into_iter().map(|(key, value)| (key.to_owned(), value)).collect())
+	}
+
+	fn block_state(name: &str) -> nbt::Value {
+		compound(vec![("Name", nbt::Value::String(name.to_owned()))])
+	}
+
+	fn unknown_block_states() -> nbt::Value {
+		compound(vec![("palette", nbt::Value::List(vec![block_state("minecraft:not_a_block")]))])
+	}
+
+	/// A region file containing a single chunk at (1, 2) whose lowest section is bedrock at the bottom layer and stone above.
+	/// Section 0 is a block that isn't in the registry, and there is a block entity, heightmaps, and light, which the server doesn't use.
+	fn write_region(directory: &std::path::Path) {
+		let mut indices = [1; 4096];
+		indices[..256].fill(0);
+		let block_data = pack_entries(&indices, 4).into_iter().map(|long| long as i64).collect();
+		let section = compound(vec![
+			("Y", nbt::Value::Byte(-4)),
+			(
+				"block_states",
+				compound(vec![
+					("palette", nbt::Value::List(vec![block_state("minecraft:bedrock"), block_state("minecraft:stone")])),
+					("data", nbt::Value::LongArray(block_data)),
+				]),
+			),
+			("biomes", compound(vec![("palette", nbt::Value::List(vec![nbt::Value::String("minecraft:desert".to_owned())]))])),
+		]);
+		// light only
+		let below = compound(vec![("Y", nbt::Value::Byte(-5)), ("SkyLight", nbt::Value::ByteArray(vec![0; 2048]))]);
+		let unknown = compound(vec![("Y", nbt::Value::Byte(0)), ("block_states", unknown_block_states())]);
+		let mut chunk = nbt::Blob::new();
+		chunk.insert("DataVersion", nbt::Value::Int(2865)).unwrap();
+		chunk.insert("Status", nbt::Value::String("full".to_owned())).unwrap();
+		chunk.insert("isLightOn", nbt::Value::Byte(1)).unwrap();
+		chunk.insert("sections", nbt::Value::List(vec![below, section, unknown])).unwrap();
+		chunk.insert("block_entities", nbt::Value::List(vec![compound(vec![("id", nbt::Value::String("minecraft:chest".to_owned()))])])).unwrap();
+		chunk.insert("Heightmaps", compound(vec![("WORLD_SURFACE", nbt::Value::LongArray(vec![0; 37]))])).unwrap();
+		let mut compressed = Vec::new();
+		chunk.to_zlib_writer(&mut compressed).unwrap();
+
+		let mut region = vec![0u8; 8192];
+		let index = 1 + 2 * 32;
+		// sector 2, 1 sector long
+		region[index * 4..index * 4 + 4].copy_from_slice(&[0, 0, 2, 1]);
+		region.extend_from_slice(&(compressed.len() as u32 + 1).to_be_bytes());
+		region.push(2);
+		region.extend_from_slice(&compressed);
+		region.resize(8192 + 4096, 0);
+		std::fs::create_dir_all(directory.join("region")).unwrap();
+		std::fs::File::create(directory.join("region/r.0.0.mca")).unwrap().write_all(&region).unwrap();
+	}
+
+	fn dimension() -> Dimension {
+		Dimension {
+			min_y: -64,
+			num_sections: 24,
+			biome_count: 15,
+			biome_ids: HashMap::from([("minecraft:plains".to_owned(), 1), ("minecraft:desert".to_owned(), 3)]),
+			biome_names: HashMap::from([(1, "minecraft:plains".to_owned()), (3, "minecraft:desert".to_owned())]),
+			default_biome: 1,
+		}
+	}
+
+	#[test]
+	pub fn load() {
+		let directory = std::env::temp_dir().join(format!("rust-mc-anvil-load-{}", std::process::id()));
+		write_region(&directory);
+		let dimension = dimension();
+		let mut storage = RegionStorage::new(&directory);
+		let column = storage.load_column(ChunkPosition { x: 1, z: 2 }, &dimension).unwrap().unwrap();
+		let bottom = &column.sections()[0];
+		assert_eq!(bottom.get_block(0, 0, 0), BlockState(33));
+		assert_eq!(bottom.get_block(15, 1, 15), BlockState(1));
+		assert_eq!(bottom.block_count(), 4096);
+		assert_eq!(bottom.biomes[0], 3);
+		assert_eq!(column.sections()[1].block_count(), 0);
+		assert_eq!(column.sections()[1].biomes[0], 1);
+		// not in the region file, and no region file at all
+		assert!(storage.load_column(ChunkPosition { x: 0, z: 0 }, &dimension).unwrap().is_none());
+		assert!(storage.load_column(ChunkPosition { x: -1, z: 0 }, &dimension).unwrap().is_none());
+		std::fs::remove_dir_all(&directory).unwrap();
+	}
+
+	#[test]
+	pub fn save_and_reload() {
+		let directory = std::env::temp_dir().join(format!("rust-mc-anvil-save-{}", std::process::id()));
+		let dimension = dimension();
+		let mut column = Column::empty(dimension.num_sections, 1);
+		column.sections_mut()[4].set_block(1, 2, 3, BlockState(9));
+		column.sections_mut()[4].set_biome(0, 0, 0, 3);
+		let mut storage = RegionStorage::new(&directory);
+		storage.save_column(ChunkPosition { x: -33, z: 5 }, &column, &dimension).unwrap();
+		// saving again replaces the previous data
+		let noisy: Box<[BlockState]> = (0..4096).map(|idx| BlockState([1, 10, 14, 66, 68][idx * 7919 % 5])).collect();
+		column.sections_mut()[0] = Section::new(noisy, [1; 64]);
+		storage.save_column(ChunkPosition { x: -33, z: 5 }, &column, &dimension).unwrap();
+		assert!(directory.join("region/r.-2.0.mca").exists());
+
+		let reloaded = RegionStorage::new(&directory).load_column(ChunkPosition { x: -33, z: 5 }, &dimension).unwrap().unwrap();
+		assert_eq!(reloaded.sections()[4].get_block(1, 2, 3), BlockState(9));
+		assert_eq!(reloaded.sections()[4].block_count(), 1);
+		assert_eq!(reloaded.sections()[4].biomes[0], 3);
+		assert_eq!(reloaded.sections()[4].biomes[1], 1);
+		assert_eq!(reloaded.sections()[0].block_states, column.sections()[0].block_states);
+		std::fs::remove_dir_all(&directory).unwrap();
+	}
+
+	#[test]
+	pub fn save_keeps_stored_data() {
+		let directory = std::env::temp_dir().join(format!("rust-mc-anvil-keep-{}", std::process::id()));
+		write_region(&directory);
+		let dimension = dimension();
+		let position = ChunkPosition { x: 1, z: 2 };
+		let mut storage = RegionStorage::new(&directory);
+		let mut column = storage.load_column(position, &dimension).unwrap().unwrap();
+		column.sections_mut()[0].set_block(0, 0, 0, BlockState(1));
+		column.sections_mut()[4].set_block(0, 0, 0, BlockState(0));
+		storage.save_column(position, &column, &dimension).unwrap();
+
+		let chunk = read_root_compound(storage.read_chunk(position).unwrap().unwrap().as_slice()).unwrap();
+		assert!(matches!(chunk.get("block_entities"), Some(nbt::Value::List(entities)) if entities.len() == 1));
+		assert!(matches!(chunk.get("Heightmaps"), Some(nbt::Value::Compound(_))));
+		// blocks have changed, so the stored light is out of date
+		assert_eq!(chunk.get("isLightOn"), Some(&nbt::Value::Byte(0)));
+		let sections = match chunk.get("sections") {
+			Some(nbt::Value::List(sections)) => sections,
+			_ => panic!("no sections"),
+		};
+		let section = |y: i8| {
+			sections
+				.iter()
+				.find_map(|section| match section {
+					nbt::Value::Compound(section) if section.get("Y") == Some(&nbt::Value::Byte(y)) => Some(section),
+					_ => None,
+				})
+				.unwrap()
+		};
+		assert!(section(-5).contains_key("SkyLight"));
+		assert_eq!(section(0).get("block_states"), Some(&unknown_block_states()));
+
+		let reloaded = RegionStorage::new(&directory).load_column(position, &dimension).unwrap().unwrap();
+		assert_eq!(reloaded.sections()[0].get_block(0, 0, 0), BlockState(1));
+		assert_eq!(reloaded.sections()[0].get_block(1, 0, 0), BlockState(33));
+		std::fs::remove_dir_all(&directory).unwrap();
+	}
+
+	#[test]
+	pub fn reuse_sectors() {
+		assert_eq!(first_fit(&[true, true, false, true, false, false, true], 1), 2);
+		assert_eq!(first_fit(&[true, true, false, true, false, false, true], 2), 4);
+		assert_eq!(first_fit(&[true, true, false, true, false, false, true], 3), 7);
+		assert_eq!(first_fit(&[true, true, false, true, false, false], 3), 4);
+
+		let directory = std::env::temp_dir().join(format!("rust-mc-anvil-reuse-{}", std::process::id()));
+		let dimension = dimension();
+		let mut storage = RegionStorage::new(&directory);
+		let empty = Column::empty(dimension.num_sections, 1);
+		storage.save_column(ChunkPosition { x: 0, z: 0 }, &empty, &dimension).unwrap();
+		// too noisy to fit in one sector, so it's moved after the first save
+		let mut noisy = Column::empty(dimension.num_sections, 1);
+		let mut seed = 1u32;
+		for section in noisy.sections_mut() {
+			let states: Box<[BlockState]> = (0..4096)
+				.map(|_| {
+					seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12345);
+					BlockState([1, 10, 14, 66, 68][(seed >> 16) as usize % 5])
+				})
+				.collect();
+			*section = Section::new(states, [1; 64]);
+		}
+		storage.save_column(ChunkPosition { x: 0, z: 0 }, &noisy, &dimension).unwrap();
+		let path = directory.join("region/r.0.0.mca");
+		let length = std::fs::metadata(&path).unwrap().len();
+		assert!(length > 4 * SECTOR_SIZE);
+		// goes in the sector the empty column used to be in
+		storage.save_column(ChunkPosition { x: 1, z: 0 }, &empty, &dimension).unwrap();
+		assert_eq!(std::fs::metadata(&path).unwrap().len(), length);
+		let locations = std::fs::read(&path).unwrap();
+		assert_eq!(locations[4..8], [0, 0, 2, 1]);
+
+		let mut reloaded = RegionStorage::new(&directory);
+		assert_eq!(reloaded.load_column(ChunkPosition { x: 0, z: 0 }, &dimension).unwrap().unwrap().sections()[3].block_states, noisy.sections()[3].block_states);
+		assert_eq!(reloaded.load_column(ChunkPosition { x: 1, z: 0 }, &dimension).unwrap().unwrap().sections()[3].block_count(), 0);
+		std::fs::remove_dir_all(&directory).unwrap();
+	}
+}
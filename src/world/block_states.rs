@@ -0,0 +1,281 @@
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::{self, Display, Formatter};
+use std::str::FromStr;
+use std::sync::OnceLock;
+
+/// A global block state ID
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Default, PartialOrd, Ord)]
+pub struct BlockState(pub u16);
+
+impl BlockState {
+	pub const AIR: Self = Self(0);
+
+	pub fn id(self) -> u16 {
+		self.0
+	}
+	/// `None` if the state isn't in the registry
+	pub fn name(self) -> Option<&'static str> {
+		registry().state(self).map(|state| registry().blocks[state.block].name.as_str())
+	}
+	pub fn properties(self) -> Option<&'static BTreeMap<String, String>> {
+		registry().state(self).map(|state| &state.properties)
+	}
+	/// Includes `cave_air` and `void_air`
+	pub fn is_air(self) -> bool {
+		registry().state(self).is_some_and(|state| state.is_air)
+	}
+	/// How much the block dims light passing through it, from 0 (transparent) to 15 (opaque)
+	pub fn opacity(self) -> u8 {
+		registry().state(self).map_or(15, |state| state.opacity)
+	}
+	/// The level of block light that the block gives off
+	pub fn emission(self) -> u8 {
+		registry().state(self).map_or(0, |state| state.emission)
+	}
+	/// Whether the block counts for the `MOTION_BLOCKING` heightmap, which is where rain and snow stop: it blocks movement, or holds a fluid
+	pub fn is_motion_blocking(self) -> bool {
+		registry().state(self).is_none_or(|state| state.motion_blocking)
+	}
+}
+
+/// Like `minecraft:oak_stairs[facing=north,half=top]`, or `#<id>` if the state isn't in the registry
+impl Display for BlockState {
+	fn fmt(&self, formatter: &mut Formatter<'_>) -> fmt::Result {
+		match (self.name(), self.properties()) {
+			(Some(name), Some(properties)) => {
+				formatter.write_str(name)?;
+				if !properties.is_empty() {
+					let properties: Vec<_> = properties.iter().map(|(key, value)| format!("{}={}", key, value)).collect();
+					write!(formatter, "[{}]", properties.join(","))?;
+				}
+				Ok(())
+			}
+			_ => write!(formatter, "#{}", self.0),
+		}
+	}
+}
+
+/// The inverse of `Display`. The namespace defaults to `minecraft`, and properties that are left out take their default values.
+impl FromStr for BlockState {
+	type Err = anyhow::Error;
+	fn from_str(text: &str) -> anyhow::Result<Self> {
+		let (name, properties) = match text.split_once('[') {
+			Some((name, properties)) => (name, properties.strip_suffix(']').ok_or_else(|| anyhow::anyhow!("Missing ] in block state {}", text))?),
+			None => (text, ""),
+		};
+		let name = if name.contains(':') { name.to_owned() } else { format!("minecraft:{}", name) };
+		let properties = properties
+			.split(',')
+			.filter(|property| !property.is_empty())
+			.map(|property| property.split_once('=').ok_or_else(|| anyhow::anyhow!("Expected key=value but got {} in block state {}", property, text)))
+			.collect::<anyhow::Result<Vec<_>>>()?;
+		registry().state_of(&name, properties).ok_or_else(|| anyhow::anyhow!("Unknown block state {}", text))
+	}
+}
+
+#[derive(Deserialize)]
+struct BlockReport {
+	states: Vec<StateReport>,
+}
+
+/// How a block's states interact with light and the heightmaps, in the same order as in `BlockReport`
+#[derive(Deserialize)]
+struct PropertyReport {
+	light_block: Vec<u8>,
+	light_emission: Vec<u8>,
+	motion_blocking: Vec<bool>,
+}
+
+#[derive(Deserialize)]
+struct StateReport {
+	id: u16,
+	#[serde(default)]
+	properties: BTreeMap<String, String>,
+	#[serde(default)]
+	default: bool,
+}
+
+struct Block {
+	name: String,
+	default_state: BlockState,
+	states: Vec<BlockState>,
+}
+
+struct State {
+	/// Index into `Registry::blocks`
+	block: usize,
+	properties: BTreeMap<String, String>,
+	is_air: bool,
+	opacity: u8,
+	emission: u8,
+	motion_blocking: bool,
+}
+
+/// Every block state, from vanilla's `blocks.json` data report and the table in `block_properties.json`
+pub struct Registry {
+	blocks: Vec<Block>,
+	blocks_by_name: HashMap<String, usize>,
+	/// Indexed by state ID
+	states: Vec<State>,
+}
+
+impl Registry {
+	/// The property table has to list every block in the report
+	pub fn from_report(report: &str, properties: &str) -> anyhow::Result<Self> {
+		let report: HashMap<String, BlockReport> = serde_json::from_str(report)?;
+		let mut properties: HashMap<String, PropertyReport> = serde_json::from_str(properties)?;
+		let mut blocks = Vec::with_capacity(report.len());
+		let mut states: Vec<Option<State>> = Vec::new();
+		for (name, block) in report {
+			let table = properties.remove(&name).ok_or_else(|| anyhow::anyhow!("Block {} is missing from the property table", name))?;
+			anyhow::ensure!(
+				[table.light_block.len(), table.light_emission.len(), table.motion_blocking.len()].iter().all(|&len| len == block.states.len()),
+				"The property table has the wrong number of states for block {}",
+				name
+			);
+			let is_air = matches!(name.as_str(), "minecraft:air" | "minecraft:cave_air" | "minecraft:void_air");
+			let default_state = block.states.iter().find(|state| state.default).ok_or_else(|| anyhow::anyhow!("Block {} has no default state", name))?.id;
+			for (idx, state) in block.states.iter().enumerate() {
+				let id = usize::from(state.id);
+				if states.len() <= id {
+					states.resize_with(id + 1, || None);
+				}
+				anyhow::ensure!(states[id].is_none(), "Block state {} is listed twice", id);
+				states[id] = Some(State {
+					block: blocks.len(),
+					properties: state.properties.clone(),
+					is_air,
+					opacity: table.light_block[idx],
+					emission: table.light_emission[idx],
+					motion_blocking: table.motion_blocking[idx],
+				});
+			}
+			blocks.push(Block {
+				name,
+				default_state: BlockState(default_state),
+				states: block.states.iter().map(|state| BlockState(state.id)).collect(),
+			});
+		}
+		let states = states
+			.into_iter()
+			.enumerate()
+			.map(|(id, state)| state.ok_or_else(|| anyhow::anyhow!("Block state {} is missing", id)))
+			.collect::<anyhow::Result<_>>()?;
+		Ok(Self {
+			blocks_by_name: blocks.iter().enumerate().map(|(idx, block)| (block.name.clone(), idx)).collect(),
+			blocks,
+			states,
+		})
+	}
+	fn state(&self, state: BlockState) -> Option<&State> {
+		self.states.get(usize::from(state.0))
+	}
+	/// The number of block states
+	pub fn len(&self) -> usize {
+		self.states.len()
+	}
+	pub fn is_empty(&self) -> bool {
+		self.states.is_empty()
+	}
+	pub fn default_state(&self, name: &str) -> Option<BlockState> {
+		self.blocks_by_name.get(name).map(|&idx| self.blocks[idx].default_state)
+	}
+	/// Properties that are left out take their default values. `None` if the block is unknown, or if it doesn't have one of the properties or values.
+	pub fn state_of<'a>(&self, name: &str, properties: impl IntoIterator<Item = (&'a str, &'a str)>) -> Option<BlockState> {
+		let block = &self.blocks[*self.blocks_by_name.get(name)?];
+		let mut wanted = self.state(block.default_state)?.properties.clone();
+		for (key, value) in properties {
+			*wanted.get_mut(key)? = value.to_owned();
+		}
+		block.states.iter().copied().find(|&state| self.state(state).is_some_and(|state| state.properties == wanted))
+	}
+}
+
+/// Loaded from `assets/blocks.json` and `assets/block_properties.json` the first time it's used
+pub fn registry() -> &'static Registry {
+	static REGISTRY: OnceLock<Registry> = OnceLock::new();
+	// PANICS: the reports are part of the source tree, and the tests load them
+	REGISTRY.get_or_init(|| Registry::from_report(include_str!("../../assets/blocks.json"), include_str!("../../assets/block_properties.json")).unwrap())
+}
+
+#[cfg(test)]
+mod test {
+	use super::{registry, BlockState};
+
+	#[test]
+	pub fn lookup() {
+		let snowy: BlockState = "minecraft:grass_block[snowy=true]".parse().unwrap();
+		assert_eq!(snowy, BlockState(8));
+		assert_eq!("grass_block".parse::<BlockState>().unwrap(), BlockState(9));
+		assert_eq!(registry().default_state("minecraft:oak_log"), Some(BlockState(77)));
+		assert_eq!(registry().state_of("minecraft:water", [("level", "3")]), Some(BlockState(37)));
+		assert_eq!(registry().state_of("minecraft:water", [("level", "16")]), None);
+		assert_eq!(registry().state_of("minecraft:stone", [("facing", "north")]), None);
+		assert!("minecraft:not_a_block".parse::<BlockState>().is_err());
+		assert!("minecraft:oak_log[axis=x".parse::<BlockState>().is_err());
+
+		assert_eq!(snowy.to_string(), "minecraft:grass_block[snowy=true]");
+		assert_eq!(BlockState(1).to_string(), "minecraft:stone");
+		assert_eq!(BlockState(u16::MAX).to_string(), "#65535");
+		assert_eq!(BlockState(78).to_string().parse::<BlockState>().unwrap(), BlockState(78));
+		assert!(BlockState::AIR.is_air() && !BlockState(1).is_air());
+		assert_eq!((BlockState(50).opacity(), BlockState(50).emission()), (1, 15));
+	}
+
+	#[test]
+	pub fn full_report() {
+		assert_eq!(registry().len(), 20342);
+		let stairs: BlockState = "minecraft:oak_stairs[facing=north,half=top]".parse().unwrap();
+		assert_eq!(stairs, BlockState(2011));
+		assert_eq!(stairs.to_string(), "minecraft:oak_stairs[facing=north,half=top,shape=straight,waterlogged=false]");
+		let cave_air: BlockState = "cave_air".parse().unwrap();
+		assert_eq!(cave_air, BlockState(9916));
+		assert!(cave_air.is_air() && cave_air.opacity() == 0);
+		assert!(registry().default_state("minecraft:crafting_table").is_some() && registry().default_state("minecraft:anvil").is_some());
+		assert_eq!("torch".parse::<BlockState>().unwrap().emission(), 14);
+	}
+
+	#[test]
+	pub fn light() {
+		let light = |state: &str| {
+			let state: BlockState = state.parse().unwrap();
+			(state.opacity(), state.emission())
+		};
+		// blocks that aren't full cubes let skylight straight through, unless they hold water
+		for state in ["dandelion", "grass", "snow[layers=3]", "oak_stairs", "stone_slab[type=top]", "oak_fence", "oak_door", "rail", "ladder", "oak_sign", "glass_pane"] {
+			assert_eq!(light(state), (0, 0), "{}", state);
+		}
+		assert_eq!(light("oak_stairs[waterlogged=true]"), (1, 0));
+		assert_eq!(light("seagrass"), (1, 0));
+		assert_eq!(light("snow[layers=8]"), (15, 0));
+		assert_eq!(light("stone_slab[type=double]"), (15, 0));
+		assert_eq!(light("oak_leaves"), (1, 0));
+		assert_eq!(light("ice"), (1, 0));
+		assert_eq!(light("glass"), (0, 0));
+		assert_eq!(light("tinted_glass"), (15, 0));
+		assert_eq!(light("soul_torch"), (0, 10));
+		assert_eq!(light("soul_lantern[hanging=true]"), (0, 10));
+		assert_eq!(light("campfire[lit=true]"), (0, 15));
+		assert_eq!(light("campfire[lit=false]"), (0, 0));
+		assert_eq!(light("redstone_lamp[lit=true]"), (15, 15));
+		assert_eq!(light("furnace[lit=true]"), (15, 13));
+		assert_eq!(light("shroomlight"), (15, 15));
+		assert_eq!(light("magma_block"), (15, 3));
+		assert_eq!(light("sea_pickle[pickles=4,waterlogged=true]"), (1, 15));
+		assert_eq!(light("sea_pickle[pickles=4,waterlogged=false]"), (0, 0));
+		assert_eq!(light("beacon"), (1, 15));
+		assert_eq!(light("lava"), (1, 15));
+	}
+
+	#[test]
+	pub fn motion_blocking() {
+		let blocking = |state: &str| state.parse::<BlockState>().unwrap().is_motion_blocking();
+		for state in ["stone", "oak_leaves", "glass", "oak_slab", "oak_sign", "water", "seagrass", "ladder[waterlogged=true]", "dead_brain_coral"] {
+			assert!(blocking(state), "{}", state);
+		}
+		for state in ["air", "grass", "torch", "snow", "rail", "white_carpet", "cobweb", "player_head", "brain_coral[waterlogged=false]"] {
+			assert!(!blocking(state), "{}", state);
+		}
+	}
+}
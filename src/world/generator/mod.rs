@@ -0,0 +1,121 @@
+use super::{BlockState, ChunkPosition, Column};
+use crate::config::{FlatLayer, GeneratorConfig};
+use crate::packets::helpers::game::biome::BiomeRegistry;
+
+mod noise;
+pub use noise::Noise;
+
+/// Creates the columns that aren't in storage yet
+pub trait WorldGenerator: Send + Sync {
+	fn generate(&self, position: ChunkPosition, min_y: i32, num_sections: usize) -> Column;
+	/// The Y coordinate to spawn players at in a new world, at X = Z = 0
+	fn spawn_y(&self, min_y: i32) -> i32;
+	/// Tells the client to use a lower horizon, like in vanilla superflat worlds
+	fn is_flat(&self) -> bool {
+		false
+	}
+}
+
+fn biome_id(biomes: &BiomeRegistry, name: &str) -> anyhow::Result<i32> {
+	biomes.id_of(name).ok_or_else(|| anyhow::anyhow!("Biome {} is not in the biome registry", name))
+}
+
+impl dyn WorldGenerator {
+	pub fn from_config(config: &GeneratorConfig, biomes: &BiomeRegistry, seed: i64) -> anyhow::Result<Box<Self>> {
+		Ok(match config {
+			GeneratorConfig::Void { biome } => Box::new(Void::new(biome_id(biomes, biome)?)),
+			GeneratorConfig::Flat { layers, biome } => Box::new(Flat::new(layers, biome_id(biomes, biome)?)?),
+			GeneratorConfig::Noise { caves } => Box::new(Noise::new(seed, *caves, biomes)?),
+		})
+	}
+}
+
+/// Nothing but air
+pub struct Void {
+	biome: i32,
+}
+
+impl Void {
+	pub fn new(biome: i32) -> Self {
+		Self { biome }
+	}
+}
+
+impl WorldGenerator for Void {
+	fn generate(&self, _position: ChunkPosition, _min_y: i32, num_sections: usize) -> Column {
+		Column::empty(num_sections, self.biome)
+	}
+	fn spawn_y(&self, _min_y: i32) -> i32 {
+		64
+	}
+}
+
+/// Horizontal layers of blocks starting at the bottom of the world, like vanilla's superflat
+pub struct Flat {
+	/// One entry per Y coordinate, from the bottom of the world up
+	layers: Vec<BlockState>,
+	biome: i32,
+}
+
+impl Flat {
+	pub fn new(layers: &[FlatLayer], biome: i32) -> anyhow::Result<Self> {
+		let layers = layers
+			.iter()
+			.map(|layer| {
+				let state: BlockState = layer.block.parse().map_err(|err: anyhow::Error| err.context("Superflat layers"))?;
+				Ok(std::iter::repeat_n(state, layer.height as usize))
+			})
+			.collect::<anyhow::Result<Vec<_>>>()?
+			.into_iter()
+			.flatten()
+			.collect();
+		Ok(Self { layers, biome })
+	}
+}
+
+impl WorldGenerator for Flat {
+	fn generate(&self, _position: ChunkPosition, _min_y: i32, num_sections: usize) -> Column {
+		let mut column = Column::empty(num_sections, self.biome);
+		for (y, &state) in self.layers.iter().enumerate().take(num_sections * 16) {
+			column.sections[y / 16].fill_layer(y % 16, state);
+		}
+		column
+	}
+	fn spawn_y(&self, min_y: i32) -> i32 {
+		min_y + self.layers.len() as i32
+	}
+	fn is_flat(&self) -> bool {
+		true
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::{Flat, Void, WorldGenerator};
+	use crate::config::FlatLayer;
+	use crate::world::{BlockState, ChunkPosition};
+
+	fn layer(block: &str, height: u32) -> FlatLayer {
+		FlatLayer { block: block.to_owned(), height }
+	}
+
+	#[test]
+	pub fn flat() {
+		let generator = Flat::new(&[layer("minecraft:bedrock", 1), layer("minecraft:dirt", 16), layer("minecraft:grass_block", 1)], 1).unwrap();
+		let column = generator.generate(ChunkPosition { x: 3, z: -7 }, -64, 24);
+		assert_eq!(column.sections()[0].get_block(5, 0, 5), BlockState(33));
+		assert_eq!(column.sections()[0].get_block(5, 15, 5), BlockState(10));
+		assert_eq!(column.sections()[1].get_block(5, 0, 5), BlockState(10));
+		assert_eq!(column.sections()[1].get_block(5, 1, 5), BlockState(9));
+		assert_eq!(column.sections()[1].block_count(), 2 * 256);
+		assert_eq!(column.sections()[2].block_count(), 0);
+		assert_eq!(generator.spawn_y(-64), -46);
+		assert!(Flat::new(&[layer("minecraft:not_a_block", 1)], 1).is_err());
+	}
+
+	#[test]
+	pub fn void() {
+		let column = Void::new(0).generate(ChunkPosition { x: 0, z: 0 }, -64, 24);
+		assert!(column.sections().iter().all(|section| section.block_count() == 0));
+	}
+}
@@ -0,0 +1,374 @@
+use super::WorldGenerator;
+use crate::packets::helpers::game::biome::BiomeRegistry;
+use crate::world::{BlockState, ChunkPosition, Column, Section};
+
+/// The same as vanilla's
+const SEA_LEVEL: i32 = 63;
+
+/// SplitMix64. The generators in `rand` may change between versions, but the same seed must always give the same world.
+struct Random(u64);
+
+impl Random {
+	fn next(&mut self) -> u64 {
+		self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+		let mut z = self.0;
+		z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+		z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+		z ^ (z >> 31)
+	}
+	/// In `[0, 1)`
+	fn next_f64(&mut self) -> f64 {
+		(self.next() >> 11) as f64 / (1u64 << 53) as f64
+	}
+}
+
+fn fade(t: f64) -> f64 {
+	t * t * t * (t * (t * 6.0 - 15.0) + 10.0)
+}
+
+fn lerp(t: f64, a: f64, b: f64) -> f64 {
+	a + t * (b - a)
+}
+
+fn gradient(hash: u8, x: f64, y: f64, z: f64) -> f64 {
+	let hash = hash & 15;
+	let u = if hash < 8 { x } else { y };
+	let v = if hash < 4 {
+		y
+	} else if hash == 12 || hash == 14 {
+		x
+	} else {
+		z
+	};
+	(if hash & 1 == 0 { u } else { -u }) + (if hash & 2 == 0 { v } else { -v })
+}
+
+/// Ken Perlin's improved noise, with a shuffled permutation table
+struct Perlin {
+	/// The shuffled table, repeated so that lookups don't need to wrap
+	permutation: [u8; 512],
+	/// Moves the lattice so that the noise isn't always zero at the origin
+	offset: [f64; 3],
+}
+
+impl Perlin {
+	fn new(random: &mut Random) -> Self {
+		let mut permutation = [0; 512];
+		for (idx, entry) in permutation.iter_mut().take(256).enumerate() {
+			*entry = idx as u8;
+		}
+		for idx in (1..256).rev() {
+			let other = (random.next() % (idx as u64 + 1)) as usize;
+			permutation.swap(idx, other);
+		}
+		permutation.copy_within(0..256, 256);
+		let offset = [random.next_f64() * 256.0, random.next_f64() * 256.0, random.next_f64() * 256.0];
+		Self { permutation, offset }
+	}
+	/// Roughly in `[-1, 1]`
+	fn sample(&self, x: f64, y: f64, z: f64) -> f64 {
+		let (x, y, z) = (x + self.offset[0], y + self.offset[1], z + self.offset[2]);
+		let (x_floor, y_floor, z_floor) = (x.floor(), y.floor(), z.floor());
+		let (xi, yi, zi) = ((x_floor as i64 & 255) as usize, (y_floor as i64 & 255) as usize, (z_floor as i64 & 255) as usize);
+		let (x, y, z) = (x - x_floor, y - y_floor, z - z_floor);
+		let (u, v, w) = (fade(x), fade(y), fade(z));
+		let p = &self.permutation;
+		let a = usize::from(p[xi]) + yi;
+		let aa = usize::from(p[a]) + zi;
+		let ab = usize::from(p[a + 1]) + zi;
+		let b = usize::from(p[xi + 1]) + yi;
+		let ba = usize::from(p[b]) + zi;
+		let bb = usize::from(p[b + 1]) + zi;
+		lerp(
+			w,
+			lerp(v, lerp(u, gradient(p[aa], x, y, z), gradient(p[ba], x - 1.0, y, z)), lerp(u, gradient(p[ab], x, y - 1.0, z), gradient(p[bb], x - 1.0, y - 1.0, z))),
+			lerp(
+				v,
+				lerp(u, gradient(p[aa + 1], x, y, z - 1.0), gradient(p[ba + 1], x - 1.0, y, z - 1.0)),
+				lerp(u, gradient(p[ab + 1], x, y - 1.0, z - 1.0), gradient(p[bb + 1], x - 1.0, y - 1.0, z - 1.0)),
+			),
+		)
+	}
+}
+
+/// Layers of noise, each with double the frequency and half the amplitude of the last
+struct Octaves {
+	layers: Vec<Perlin>,
+	/// The size in blocks of the features of the first layer
+	scale: f64,
+}
+
+impl Octaves {
+	fn new(random: &mut Random, count: usize, scale: f64) -> Self {
+		Self {
+			layers: (0..count).map(|_| Perlin::new(random)).collect(),
+			scale,
+		}
+	}
+	/// Roughly in `[-1, 1]`
+	fn sample(&self, x: f64, y: f64, z: f64) -> f64 {
+		let mut frequency = 1.0 / self.scale;
+		let mut amplitude = 1.0;
+		let (mut sum, mut total_amplitude) = (0.0, 0.0);
+		for layer in &self.layers {
+			sum += layer.sample(x * frequency, y * frequency, z * frequency) * amplitude;
+			total_amplitude += amplitude;
+			frequency *= 2.0;
+			amplitude /= 2.0;
+		}
+		sum / total_amplitude
+	}
+	fn sample_2d(&self, x: f64, z: f64) -> f64 {
+		self.sample(x, 0.0, z)
+	}
+}
+
+struct Biomes {
+	deep_ocean: i32,
+	ocean: i32,
+	plains: i32,
+	forest: i32,
+	desert: i32,
+	snowy_plains: i32,
+	lush_caves: i32,
+	dripstone_caves: i32,
+}
+
+struct Blocks {
+	bedrock: BlockState,
+	stone: BlockState,
+	dirt: BlockState,
+	grass: BlockState,
+	sand: BlockState,
+	gravel: BlockState,
+	water: BlockState,
+}
+
+/// How far below the surface the cave biomes can start, so that the surface and the seabed keep the biome above them
+const CAVE_BIOME_DEPTH: i32 = 16;
+
+/// Hills and oceans from layered Perlin noise, with optional winding caves.
+/// Each 4x4x4 cell has the biome of the surface above it, unless it's deep enough underground for a cave biome.
+pub struct Noise {
+	/// Large-scale height, which decides between ocean and land
+	continents: Octaves,
+	hills: Octaves,
+	temperature: Octaves,
+	humidity: Octaves,
+	/// Tunnels follow the places where both fields are close to zero
+	caves: Option<(Octaves, Octaves)>,
+	/// In three dimensions; lush caves are where it's high and dripstone caves are where it's low
+	cave_biomes: Octaves,
+	biomes: Biomes,
+	blocks: Blocks,
+}
+
+impl Noise {
+	pub fn new(seed: i64, caves: bool, biomes: &BiomeRegistry) -> anyhow::Result<Self> {
+		let mut random = Random(seed as u64);
+		let biome_id = |name| super::biome_id(biomes, name);
+		Ok(Self {
+			continents: Octaves::new(&mut random, 3, 1024.0),
+			hills: Octaves::new(&mut random, 4, 192.0),
+			temperature: Octaves::new(&mut random, 2, 768.0),
+			humidity: Octaves::new(&mut random, 2, 512.0),
+			caves: if caves { Some((Octaves::new(&mut random, 1, 64.0), Octaves::new(&mut random, 1, 64.0))) } else { None },
+			cave_biomes: Octaves::new(&mut random, 2, 96.0),
+			biomes: Biomes {
+				deep_ocean: biome_id("minecraft:deep_ocean")?,
+				ocean: biome_id("minecraft:ocean")?,
+				plains: biome_id("minecraft:plains")?,
+				forest: biome_id("minecraft:forest")?,
+				desert: biome_id("minecraft:desert")?,
+				snowy_plains: biome_id("minecraft:snowy_plains")?,
+				lush_caves: biome_id("minecraft:lush_caves")?,
+				dripstone_caves: biome_id("minecraft:dripstone_caves")?,
+			},
+			blocks: Blocks {
+				bedrock: "minecraft:bedrock".parse()?,
+				stone: "minecraft:stone".parse()?,
+				dirt: "minecraft:dirt".parse()?,
+				grass: "minecraft:grass_block".parse()?,
+				sand: "minecraft:sand".parse()?,
+				gravel: "minecraft:gravel".parse()?,
+				water: "minecraft:water".parse()?,
+			},
+		})
+	}
+	/// The Y coordinate of the highest solid block, ignoring caves
+	fn surface_height(&self, x: i32, z: i32) -> i32 {
+		let (x, z) = (f64::from(x), f64::from(z));
+		let continent = self.continents.sample_2d(x, z);
+		// oceans and coasts are flatter than inland areas
+		let roughness = 8.0 + continent.max(0.0) * 120.0;
+		SEA_LEVEL + 4 + (continent * 80.0 + self.hills.sample_2d(x, z) * roughness) as i32
+	}
+	fn biome(&self, x: i32, z: i32, height: i32) -> i32 {
+		if height < SEA_LEVEL - 12 {
+			return self.biomes.deep_ocean;
+		}
+		if height < SEA_LEVEL {
+			return self.biomes.ocean;
+		}
+		let temperature = self.temperature.sample_2d(f64::from(x), f64::from(z));
+		let humidity = self.humidity.sample_2d(f64::from(x), f64::from(z));
+		if temperature < -0.2 {
+			self.biomes.snowy_plains
+		} else if temperature > 0.15 && humidity < 0.0 {
+			self.biomes.desert
+		} else if humidity > 0.1 {
+			self.biomes.forest
+		} else {
+			self.biomes.plains
+		}
+	}
+	/// For the cell centered on the position, which is `CAVE_BIOME_DEPTH` or more below the surface
+	fn cave_biome(&self, x: i32, y: i32, z: i32, surface_biome: i32) -> i32 {
+		let value = self.cave_biomes.sample(f64::from(x), f64::from(y), f64::from(z));
+		if value > 0.25 {
+			self.biomes.lush_caves
+		} else if value < -0.25 {
+			self.biomes.dripstone_caves
+		} else {
+			surface_biome
+		}
+	}
+	/// The top block and the three blocks under it
+	fn surface_blocks(&self, biome: i32, height: i32) -> (BlockState, BlockState) {
+		let blocks = &self.blocks;
+		if biome == self.biomes.deep_ocean {
+			(blocks.gravel, blocks.gravel)
+		} else if biome == self.biomes.desert || height <= SEA_LEVEL + 1 {
+			// includes oceans and beaches
+			(blocks.sand, blocks.sand)
+		} else {
+			(blocks.grass, blocks.dirt)
+		}
+	}
+	fn is_cave(&self, x: i32, y: i32, z: i32, height: i32) -> bool {
+		let (first, second) = match &self.caves {
+			Some(caves) => caves,
+			None => return false,
+		};
+		// keep a floor under the seabed so that water isn't left floating above caves
+		if height < SEA_LEVEL + 2 && y > height - 5 {
+			return false;
+		}
+		// squashed vertically so that tunnels are mostly horizontal
+		let (x, y, z) = (f64::from(x), f64::from(y) * 2.0, f64::from(z));
+		first.sample(x, y, z).abs() < 0.04 && second.sample(x, y, z).abs() < 0.04
+	}
+}
+
+impl WorldGenerator for Noise {
+	fn generate(&self, position: ChunkPosition, min_y: i32, num_sections: usize) -> Column {
+		let (base_x, base_z) = (position.x * 16, position.z * 16);
+		let max_y = min_y + num_sections as i32 * 16 - 1;
+		// indexed by `z * 16 + x`
+		let mut heights = [0; 256];
+		for (idx, height) in heights.iter_mut().enumerate() {
+			*height = self.surface_height(base_x + (idx % 16) as i32, base_z + (idx / 16) as i32).clamp(min_y + 1, max_y);
+		}
+		// the biome at the surface, indexed by `z * 4 + x`
+		let mut cell_biomes = [0; 16];
+		for (idx, biome) in cell_biomes.iter_mut().enumerate() {
+			let (x, z) = (idx % 4 * 4 + 2, idx / 4 * 4 + 2);
+			*biome = self.biome(base_x + x as i32, base_z + z as i32, heights[z * 16 + x]);
+		}
+
+		// all of the sections one after another, each indexed like `Section::block_states`
+		let mut block_states = vec![BlockState::AIR; num_sections * 4096];
+		for (idx, &height) in heights.iter().enumerate() {
+			let (x, z) = (idx % 16, idx / 16);
+			let (top, filler) = self.surface_blocks(cell_biomes[z / 4 * 4 + x / 4], height);
+			for y in min_y..=height.max(SEA_LEVEL).min(max_y) {
+				let state = if y == min_y {
+					self.blocks.bedrock
+				} else if y > height {
+					self.blocks.water
+				} else if y > min_y + 4 && self.is_cave(base_x + x as i32, y, base_z + z as i32, height) {
+					BlockState::AIR
+				} else if y == height {
+					top
+				} else if y > height - 4 {
+					filler
+				} else {
+					self.blocks.stone
+				};
+				block_states[((y - min_y) as usize * 16 + z) * 16 + x] = state;
+			}
+		}
+
+		let sections = block_states
+			.chunks(4096)
+			.enumerate()
+			.map(|(section_idx, states)| {
+				// indexed by `(y * 4 + z) * 4 + x`, like `Section::biomes`
+				let mut biomes = [0; 64];
+				for (idx, biome) in biomes.iter_mut().enumerate() {
+					let (x, y, z) = (idx % 4 * 4 + 2, min_y + section_idx as i32 * 16 + (idx / 16 * 4) as i32 + 2, idx / 4 % 4 * 4 + 2);
+					let surface_biome = cell_biomes[idx % 16];
+					*biome = if y <= heights[z * 16 + x] - CAVE_BIOME_DEPTH {
+						self.cave_biome(base_x + x as i32, y, base_z + z as i32, surface_biome)
+					} else {
+						surface_biome
+					};
+				}
+				Section::new(states.into(), biomes)
+			})
+			.collect();
+		Column { sections, stored: None }
+	}
+	fn spawn_y(&self, min_y: i32) -> i32 {
+		self.surface_height(0, 0).max(SEA_LEVEL).max(min_y) + 1
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::{Noise, SEA_LEVEL};
+	use crate::packets::helpers::game::dimension;
+	use crate::world::generator::WorldGenerator;
+	use crate::world::{BlockState, ChunkPosition};
+
+	#[test]
+	pub fn terrain() {
+		let codec: dimension::Codec = serde_json::from_str(include_str!("../../../assets/dimension_codec.json")).unwrap();
+		let generator = Noise::new(12345, true, codec.biomes()).unwrap();
+		let position = ChunkPosition { x: 2, z: -5 };
+		let column = generator.generate(position, -64, 24);
+		// the same seed always gives the same terrain
+		assert_eq!(column.heights(), generator.generate(position, -64, 24).heights());
+		assert_eq!(column.heights(), Noise::new(12345, true, codec.biomes()).unwrap().generate(position, -64, 24).heights());
+		for (idx, &height) in column.heights().iter().enumerate() {
+			// water fills everything up to sea level
+			assert!(height > SEA_LEVEL + 64, "{}", height);
+			assert_eq!(column.sections()[0].get_block(idx % 16, 0, idx / 16), BlockState(33));
+		}
+		let biome_ids: Vec<_> = codec.biomes().iter().map(|(_, id)| id).collect();
+		assert!(column.sections().iter().all(|section| section.biomes.iter().all(|biome| biome_ids.contains(biome))));
+
+		// oceans, the surface, and the air above it keep the surface biome, but there are cave biomes deep underground
+		let (lush_caves, dripstone_caves) = (codec.biomes().id_of("minecraft:lush_caves").unwrap(), codec.biomes().id_of("minecraft:dripstone_caves").unwrap());
+		let is_cave_biome = |biome: &i32| *biome == lush_caves || *biome == dripstone_caves;
+		let columns: Vec<_> = (0..8).map(|x| generator.generate(ChunkPosition { x: x * 8, z: 0 }, -64, 24)).collect();
+		for column in &columns {
+			let heights = column.heights();
+			for (section_y, section) in column.sections().iter().enumerate() {
+				for (idx, biome) in section.biomes.iter().enumerate() {
+					let (x, y, z) = (idx % 4 * 4 + 2, section_y * 16 + idx / 16 * 4 + 2, idx / 4 % 4 * 4 + 2);
+					assert!(!is_cave_biome(biome) || (y as i32) < heights[z * 16 + x] - 16);
+				}
+			}
+		}
+		for cave_biome in [lush_caves, dripstone_caves] {
+			assert!(columns.iter().any(|column| column.sections()[..4].iter().any(|section| section.biomes.contains(&cave_biome))));
+		}
+
+		let other = Noise::new(54321, true, codec.biomes()).unwrap().generate(position, -64, 24);
+		assert_ne!(column.heights(), other.heights());
+		// deep underground is mostly stone
+		let stone = (0..4096).filter(|&idx| column.sections()[3].get_block(idx % 16, idx / 256, idx / 16 % 16) == BlockState(1)).count();
+		assert!(stone > 3000, "{}", stone);
+	}
+}
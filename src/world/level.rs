@@ -0,0 +1,197 @@
+use crate::packets::helpers::position::PackedPosition;
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+
+/// 1.18.1
+pub const DATA_VERSION: i32 = 2865;
+/// The version of the `level.dat` format itself, which hasn't changed since Anvil was introduced
+const ANVIL_VERSION: i32 = 19133;
+
+/// The parts of `level.dat` that the server uses
+pub struct LevelData {
+	pub spawn: PackedPosition,
+	pub spawn_angle: f32,
+	/// Ticks since the world was created
+	pub time: i64,
+	/// Time of day, in ticks; does not advance if the `doDaylightCycle` game rule is false
+	pub day_time: i64,
+	/// Values are stored as strings, like in vanilla
+	pub game_rules: BTreeMap<String, String>,
+	/// The world generation seed, from `WorldGenSettings`
+	pub seed: i64,
+	/// Everything else from an existing `level.dat`, such as world generation settings, so that it survives being saved again
+	other: HashMap<String, nbt::Value>,
+}
+
+impl Default for LevelData {
+	fn default() -> Self {
+		Self {
+			spawn: PackedPosition::new(0, 64, 0),
+			spawn_angle: 0.0,
+			time: 0,
+			day_time: 0,
+			game_rules: BTreeMap::new(),
+			seed: 0,
+			other: HashMap::new(),
+		}
+	}
+}
+
+fn take_int(data: &mut HashMap<String, nbt::Value>, key: &str) -> Option<i32> {
+	match data.remove(key)? {
+		nbt::Value::Int(value) => Some(value),
+		_ => None,
+	}
+}
+
+fn take_long(data: &mut HashMap<String, nbt::Value>, key: &str) -> Option<i64> {
+	match data.remove(key)? {
+		nbt::Value::Long(value) => Some(value),
+		_ => None,
+	}
+}
+
+impl LevelData {
+	/// `None` if the file doesn't exist
+	pub fn load(path: &Path) -> anyhow::Result<Option<Self>> {
+		let file = match std::fs::File::open(path) {
+			Ok(file) => file,
+			Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+			Err(err) => return Err(anyhow::Error::new(err).context(format!("Opening {}", path.display()))),
+		};
+		let mut root = super::anvil::read_root_compound(flate2::read::GzDecoder::new(file)).map_err(|err| err.context(format!("Reading {}", path.display())))?;
+		let mut data = match root.remove("Data").ok_or_else(|| anyhow::anyhow!("{} has no Data tag", path.display()))? {
+			nbt::Value::Compound(data) => data.into_iter().collect::<HashMap<_, _>>(),
+			_ => anyhow::bail!("The Data tag in {} is not a compound", path.display()),
+		};
+		let defaults = Self::default();
+		let spawn = PackedPosition::new(
+			take_int(&mut data, "SpawnX").unwrap_or_else(|| defaults.spawn.x()),
+			take_int(&mut data, "SpawnY").map_or(defaults.spawn.y(), |y| y as i16),
+			take_int(&mut data, "SpawnZ").unwrap_or_else(|| defaults.spawn.z()),
+		);
+		let spawn_angle = match data.remove("SpawnAngle") {
+			Some(nbt::Value::Float(angle)) => angle,
+			_ => defaults.spawn_angle,
+		};
+		let game_rules = match data.remove("GameRules") {
+			Some(nbt::Value::Compound(rules)) => rules
+				.into_iter()
+				.filter_map(|(rule, value)| match value {
+					nbt::Value::String(value) => Some((rule, value)),
+					_ => None,
+				})
+				.collect(),
+			_ => defaults.game_rules,
+		};
+		// the rest of `WorldGenSettings` describes vanilla's generators, so it's kept as it is
+		let seed = match data.get("WorldGenSettings") {
+			Some(nbt::Value::Compound(settings)) => match settings.get("seed") {
+				Some(nbt::Value::Long(seed)) => *seed,
+				_ => defaults.seed,
+			},
+			_ => defaults.seed,
+		};
+		Ok(Some(Self {
+			spawn,
+			spawn_angle,
+			time: take_long(&mut data, "Time").unwrap_or(defaults.time),
+			day_time: take_long(&mut data, "DayTime").unwrap_or(defaults.day_time),
+			game_rules,
+			seed,
+			other: data,
+		}))
+	}
+
+	/// Writes to a temporary file first and keeps the previous file as `level.dat_old`, like vanilla
+	pub fn save(&self, path: &Path) -> anyhow::Result<()> {
+		let mut data: HashMap<String, nbt::Value> = self.other.clone();
+		data.insert("SpawnX".to_owned(), nbt::Value::Int(self.spawn.x()));
+		data.insert("SpawnY".to_owned(), nbt::Value::Int(self.spawn.y().into()));
+		data.insert("SpawnZ".to_owned(), nbt::Value::Int(self.spawn.z()));
+		data.insert("SpawnAngle".to_owned(), nbt::Value::Float(self.spawn_angle));
+		data.insert("Time".to_owned(), nbt::Value::Long(self.time));
+		data.insert("DayTime".to_owned(), nbt::Value::Long(self.day_time));
+		data.insert("GameRules".to_owned(), nbt::Value::Compound(self.game_rules.iter().map(|(rule, value)| (rule.clone(), nbt::Value::String(value.clone()))).collect()));
+		match data.entry("WorldGenSettings".to_owned()).or_insert_with(|| nbt::Value::Compound(Default::default())) {
+			nbt::Value::Compound(settings) => {
+				settings.insert("seed".to_owned(), nbt::Value::Long(self.seed));
+			}
+			other => *other = nbt::Value::Compound([("seed".to_owned(), nbt::Value::Long(self.seed))].into_iter().collect()),
+		}
+		data.insert("DataVersion".to_owned(), nbt::Value::Int(DATA_VERSION));
+		data.insert("version".to_owned(), nbt::Value::Int(ANVIL_VERSION));
+		data.insert(
+			"Version".to_owned(),
+			nbt::Value::Compound(
+				[
+					("Id".to_owned(), nbt::Value::Int(DATA_VERSION)),
+					("Name".to_owned(), nbt::Value::String(crate::server::SERVER_VERSION.to_owned())),
+					("Snapshot".to_owned(), nbt::Value::Byte(0)),
+				]
+				.into_iter()
+				.collect(),
+			),
+		);
+		data.entry("LevelName".to_owned()).or_insert_with(|| nbt::Value::String("world".to_owned()));
+		let mut blob = nbt::Blob::new();
+		blob.insert("Data", nbt::Value::Compound(data.into_iter().collect()))?;
+
+		let new_path = path.with_file_name("level.dat_new");
+		blob.to_gzip_writer(&mut std::fs::File::create(&new_path)?)?;
+		if path.exists() {
+			std::fs::rename(path, path.with_file_name("level.dat_old"))?;
+		}
+		std::fs::rename(&new_path, path)?;
+		Ok(())
+	}
+
+	/// Moves time forward by `ticks`
+	pub fn advance(&mut self, ticks: i64) {
+		self.time += ticks;
+		if self.game_rules.get("doDaylightCycle").map(String::as_str) != Some("false") {
+			self.day_time += ticks;
+		}
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::LevelData;
+	use crate::packets::helpers::position::PackedPosition;
+
+	#[test]
+	pub fn round_trip() {
+		let directory = std::env::temp_dir().join(format!("rust-mc-level-{}", std::process::id()));
+		std::fs::create_dir_all(&directory).unwrap();
+		let path = directory.join("level.dat");
+		assert!(LevelData::load(&path).unwrap().is_none());
+
+		let mut level = LevelData {
+			spawn: PackedPosition::new(-12, 70, 34),
+			seed: -4_172_144_997_902_289_642,
+			..Default::default()
+		};
+		level.game_rules.insert("doDaylightCycle".to_owned(), "false".to_owned());
+		level.advance(100);
+		level.save(&path).unwrap();
+		// saving again keeps the previous file
+		level.save(&path).unwrap();
+		assert!(directory.join("level.dat_old").exists());
+
+		let loaded = LevelData::load(&path).unwrap().unwrap();
+		assert_eq!(loaded.spawn, PackedPosition::new(-12, 70, 34));
+		assert_eq!(loaded.time, 100);
+		assert_eq!(loaded.day_time, 0);
+		assert_eq!(loaded.seed, -4_172_144_997_902_289_642);
+		assert_eq!(loaded.game_rules.get("doDaylightCycle").unwrap(), "false");
+		assert!(matches!(loaded.other.get("LevelName"), Some(nbt::Value::String(name)) if name == "world"));
+
+		// a file without a Data tag is an error rather than a crash
+		let mut blob = nbt::Blob::new();
+		blob.insert("DataVersion", nbt::Value::Int(super::DATA_VERSION)).unwrap();
+		blob.to_gzip_writer(&mut std::fs::File::create(&path).unwrap()).unwrap();
+		assert!(LevelData::load(&path).is_err());
+		std::fs::remove_dir_all(&directory).unwrap();
+	}
+}
@@ -0,0 +1,347 @@
+use super::{BlockState, ChunkPosition, Column};
+use std::collections::{BTreeSet, HashMap, VecDeque};
+
+pub const MAX_LEVEL: u8 = 15;
+
+/// Light levels for a 16x16x16 section, indexed like its blocks
+#[derive(Clone)]
+pub enum LightSection {
+	Uniform(u8),
+	/// Two levels per byte, the first in the low nibble, like vanilla's `DataLayer`
+	Levels(Box<[u8; 2048]>),
+}
+
+impl LightSection {
+	pub fn get(&self, idx: usize) -> u8 {
+		match self {
+			Self::Uniform(level) => *level,
+			Self::Levels(levels) => (levels[idx / 2] >> ((idx % 2) * 4)) & 0xf,
+		}
+	}
+	pub fn set(&mut self, idx: usize, level: u8) {
+		if let Self::Uniform(uniform) = *self {
+			if uniform == level {
+				return;
+			}
+			*self = Self::Levels(Box::new([uniform | (uniform << 4); 2048]));
+		}
+		if let Self::Levels(levels) = self {
+			let shift = (idx % 2) * 4;
+			levels[idx / 2] = (levels[idx / 2] & !(0xf << shift)) | (level << shift);
+		}
+	}
+	pub fn is_dark(&self) -> bool {
+		match self {
+			Self::Uniform(level) => *level == 0,
+			Self::Levels(levels) => levels.iter().all(|&pair| pair == 0),
+		}
+	}
+	/// `None` if the section is entirely dark, which is sent as an empty section rather than as data
+	pub fn to_packet(&self) -> Option<Vec<u8>> {
+		match self {
+			_ if self.is_dark() => None,
+			Self::Uniform(level) => Some(vec![level | (level << 4); 2048]),
+			Self::Levels(levels) => Some(levels.to_vec()),
+		}
+	}
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum LightKind {
+	Sky,
+	Block,
+}
+
+/// The light in a column, with one extra section below the world and one above it, like vanilla
+pub struct ColumnLight {
+	sky: Vec<LightSection>,
+	block: Vec<LightSection>,
+}
+
+impl ColumnLight {
+	fn new(num_sections: usize) -> Self {
+		Self {
+			sky: vec![LightSection::Uniform(0); num_sections + 2],
+			block: vec![LightSection::Uniform(0); num_sections + 2],
+		}
+	}
+	pub fn sections(&self, kind: LightKind) -> &[LightSection] {
+		match kind {
+			LightKind::Sky => &self.sky,
+			LightKind::Block => &self.block,
+		}
+	}
+	fn sections_mut(&mut self, kind: LightKind) -> &mut [LightSection] {
+		match kind {
+			LightKind::Sky => &mut self.sky,
+			LightKind::Block => &mut self.block,
+		}
+	}
+}
+
+const DIRECTIONS: [(i32, i32, i32); 6] = [(0, -1, 0), (0, 1, 0), (-1, 0, 0), (1, 0, 0), (0, 0, -1), (0, 0, 1)];
+
+/// Flood-fills light over the loaded columns.
+/// Y coordinates are relative to the bottom of the light sections, which is one section below the world.
+pub struct Engine<'a> {
+	pub columns: &'a HashMap<ChunkPosition, Column>,
+	pub light: &'a mut HashMap<ChunkPosition, ColumnLight>,
+	/// Light sections that have changed, for each column
+	pub changes: &'a mut HashMap<ChunkPosition, BTreeSet<usize>>,
+	pub num_sections: usize,
+}
+
+fn column_of(x: i32, z: i32) -> ChunkPosition {
+	ChunkPosition { x: x >> 4, z: z >> 4 }
+}
+
+fn index_in_section(x: i32, y: i32, z: i32) -> usize {
+	(((y & 15) * 16 + (z & 15)) * 16 + (x & 15)) as usize
+}
+
+impl Engine<'_> {
+	fn height(&self) -> i32 {
+		(self.num_sections as i32 + 2) * 16
+	}
+	/// `None` if the column isn't loaded. Outside of the world is air.
+	fn block(&self, x: i32, y: i32, z: i32) -> Option<BlockState> {
+		let column = self.columns.get(&column_of(x, z))?;
+		let section = (y >> 4) - 1;
+		Some(match usize::try_from(section).ok().and_then(|section| column.sections.get(section)) {
+			Some(section) => section.get_block((x & 15) as usize, (y & 15) as usize, (z & 15) as usize),
+			None => BlockState::AIR,
+		})
+	}
+	pub fn level(&self, kind: LightKind, x: i32, y: i32, z: i32) -> Option<u8> {
+		let light = self.light.get(&column_of(x, z))?;
+		Some(light.sections(kind)[(y >> 4) as usize].get(index_in_section(x, y, z)))
+	}
+	fn set_level(&mut self, kind: LightKind, x: i32, y: i32, z: i32, level: u8) {
+		let position = column_of(x, z);
+		if let Some(light) = self.light.get_mut(&position) {
+			light.sections_mut(kind)[(y >> 4) as usize].set(index_in_section(x, y, z), level);
+			self.changes.entry(position).or_default().insert((y >> 4) as usize);
+		}
+	}
+
+	/// Spreads light outwards from each queued position
+	fn increase(&mut self, kind: LightKind, queue: &mut VecDeque<(i32, i32, i32)>) {
+		while let Some((x, y, z)) = queue.pop_front() {
+			let level = self.level(kind, x, y, z).unwrap_or(0);
+			if level <= 1 {
+				continue;
+			}
+			for (dx, dy, dz) in DIRECTIONS {
+				let (nx, ny, nz) = (x + dx, y + dy, z + dz);
+				if ny < 0 || ny >= self.height() {
+					continue;
+				}
+				let (state, current) = match (self.block(nx, ny, nz), self.level(kind, nx, ny, nz)) {
+					(Some(state), Some(current)) => (state, current),
+					_ => continue,
+				};
+				let opacity = state.opacity();
+				// sky light goes straight down through transparent blocks without getting dimmer
+				let new = if kind == LightKind::Sky && dy == -1 && level == MAX_LEVEL && opacity == 0 {
+					MAX_LEVEL
+				} else {
+					level.saturating_sub(opacity.max(1))
+				};
+				if new > current {
+					self.set_level(kind, nx, ny, nz, new);
+					queue.push_back((nx, ny, nz));
+				}
+			}
+		}
+	}
+
+	/// Darkens the light that came from each queued position, which has already been set to zero, with the level it used to have.
+	/// Positions that are lit from elsewhere are added to `relight`.
+	fn decrease(&mut self, kind: LightKind, queue: &mut VecDeque<(i32, i32, i32, u8)>, relight: &mut VecDeque<(i32, i32, i32)>) {
+		while let Some((x, y, z, level)) = queue.pop_front() {
+			for (dx, dy, dz) in DIRECTIONS {
+				let (nx, ny, nz) = (x + dx, y + dy, z + dz);
+				if ny < 0 || ny >= self.height() {
+					continue;
+				}
+				let current = match self.level(kind, nx, ny, nz) {
+					Some(current) if current > 0 => current,
+					_ => continue,
+				};
+				if current < level || (kind == LightKind::Sky && dy == -1 && level == MAX_LEVEL && current == MAX_LEVEL) {
+					self.set_level(kind, nx, ny, nz, 0);
+					queue.push_back((nx, ny, nz, current));
+					// PANICS: the level was just read from the same column
+					let emission = self.block(nx, ny, nz).unwrap().emission();
+					if kind == LightKind::Block && emission > 0 {
+						self.set_level(kind, nx, ny, nz, emission);
+						relight.push_back((nx, ny, nz));
+					}
+				} else {
+					relight.push_back((nx, ny, nz));
+				}
+			}
+		}
+	}
+
+	/// Lights a column that has just been loaded, including the light it receives from and gives to its loaded neighbors
+	pub fn light_column(&mut self, position: ChunkPosition) {
+		let column = match self.columns.get(&position) {
+			Some(column) => column,
+			None => return,
+		};
+		let mut light = ColumnLight::new(self.num_sections);
+		let (base_x, base_z) = (position.x * 16, position.z * 16);
+
+		// sky light comes straight down until it reaches a block that isn't transparent
+		let mut heights = [0; 256];
+		// empty sections are all air
+		let top_section = column.sections.iter().rposition(|section| section.block_count > 0).map_or(0, |idx| idx + 1);
+		for (idx, height) in heights.iter_mut().enumerate() {
+			let (x, z) = (idx % 16, idx / 16);
+			let top = (0..top_section * 16).rev().find(|&y| column.sections[y / 16].get_block(x, y % 16, z).opacity() > 0);
+			// one above the highest blocking block, in light section coordinates
+			*height = top.map_or(0, |y| y as i32 + 17);
+		}
+		let max_height = heights.iter().copied().max().unwrap_or(0);
+		for (section_idx, section) in light.sky.iter_mut().enumerate() {
+			let section_bottom = section_idx as i32 * 16;
+			if section_bottom >= max_height {
+				*section = LightSection::Uniform(MAX_LEVEL);
+				continue;
+			}
+			for (idx, &height) in heights.iter().enumerate() {
+				for y in section_bottom.max(height)..section_bottom + 16 {
+					section.set(((y & 15) * 256) as usize + idx, MAX_LEVEL);
+				}
+			}
+		}
+		let mut sky_queue = VecDeque::new();
+		for (idx, &height) in heights.iter().enumerate() {
+			let (x, z) = ((idx % 16) as i32, (idx / 16) as i32);
+			// the tallest neighbor decides how far up light can spread sideways; neighbors in other columns could be anything
+			let neighbor_height = if x == 0 || x == 15 || z == 0 || z == 15 {
+				max_height
+			} else {
+				DIRECTIONS[2..].iter().map(|(dx, _, dz)| heights[((z + dz) * 16 + x + dx) as usize]).max().unwrap_or(0)
+			};
+			for y in height..=neighbor_height.max(height).min(self.height() - 1) {
+				sky_queue.push_back((base_x + x, y, base_z + z));
+			}
+		}
+
+		let mut block_queue = VecDeque::new();
+		for (section_idx, section) in column.sections.iter().enumerate().filter(|(_, section)| section.block_count > 0) {
+			for idx in 0..4096 {
+				let (x, y, z) = (idx % 16, idx / 256, idx / 16 % 16);
+				let emission = section.get_block(x, y, z).emission();
+				if emission > 0 {
+					light.block[section_idx + 1].set(idx, emission);
+					block_queue.push_back((base_x + x as i32, (section_idx * 16 + 16 + y) as i32, base_z + z as i32));
+				}
+			}
+		}
+
+		// the edges of the neighbors spread light into this column where they're brighter than it
+		for (dx, dz) in [(-1, 0), (1, 0), (0, -1), (0, 1)] {
+			let neighbor = match self.light.get(&ChunkPosition { x: position.x + dx, z: position.z + dz }) {
+				Some(neighbor) => neighbor,
+				None => continue,
+			};
+			for i in 0..16 {
+				// the block in the neighbor, and the one next to it in this column; only the X and Z coordinates
+				let (outside, inside) = match (dx, dz) {
+					(-1, _) => ((base_x - 1, base_z + i), (base_x, base_z + i)),
+					(1, _) => ((base_x + 16, base_z + i), (base_x + 15, base_z + i)),
+					(_, -1) => ((base_x + i, base_z - 1), (base_x + i, base_z)),
+					_ => ((base_x + i, base_z + 16), (base_x + i, base_z + 15)),
+				};
+				for y in 0..self.height() {
+					for (kind, queue) in [(LightKind::Sky, &mut sky_queue), (LightKind::Block, &mut block_queue)] {
+						let section = (y / 16) as usize;
+						let outside_level = neighbor.sections(kind)[section].get(index_in_section(outside.0, y, outside.1));
+						if outside_level > light.sections(kind)[section].get(index_in_section(inside.0, y, inside.1)) + 1 {
+							queue.push_back((outside.0, y, outside.1));
+						}
+					}
+				}
+			}
+		}
+
+		self.light.insert(position, light);
+		self.increase(LightKind::Sky, &mut sky_queue);
+		self.increase(LightKind::Block, &mut block_queue);
+	}
+
+	/// Relights around a block that has changed. `y` is relative to the bottom of the world.
+	pub fn update_block(&mut self, x: i32, y: i32, z: i32, old: BlockState, new: BlockState) {
+		if old.opacity() == new.opacity() && old.emission() == new.emission() {
+			return;
+		}
+		let y = y + 16;
+		for kind in [LightKind::Sky, LightKind::Block] {
+			let mut darken = VecDeque::new();
+			let mut relight = VecDeque::new();
+			if let Some(level) = self.level(kind, x, y, z) {
+				self.set_level(kind, x, y, z, 0);
+				darken.push_back((x, y, z, level));
+			}
+			self.decrease(kind, &mut darken, &mut relight);
+			if kind == LightKind::Block && new.emission() > 0 {
+				self.set_level(kind, x, y, z, new.emission());
+				relight.push_back((x, y, z));
+			}
+			// the neighbors may be able to light the block now
+			relight.extend(DIRECTIONS.iter().map(|(dx, dy, dz)| (x + dx, y + dy, z + dz)).filter(|&(_, y, _)| y >= 0 && y < self.height()));
+			self.increase(kind, &mut relight);
+		}
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::LightKind;
+	use crate::config::GeneratorConfig;
+	use crate::packets::helpers::game::dimension;
+	use crate::packets::helpers::position::PackedPosition;
+	use crate::world::{BlockState, ChunkPosition, World};
+
+	#[test]
+	pub fn propagation() {
+		let codec: dimension::Codec = serde_json::from_str(include_str!("../../assets/dimension_codec.json")).unwrap();
+		let mut world = World::new(codec.dimension_type("minecraft:overworld").unwrap(), codec.biomes(), None, &GeneratorConfig::default(), Some(0)).unwrap();
+		for x in -1..=1 {
+			for z in -1..=1 {
+				world.load_column(ChunkPosition { x, z }).unwrap();
+			}
+		}
+		let light = |world: &World, kind, x, y, z| world.light(kind, PackedPosition::new(x, y, z)).unwrap();
+		// the default superflat world is four blocks deep
+		assert_eq!(light(&world, LightKind::Sky, 0, -60, 0), 15);
+		assert_eq!(light(&world, LightKind::Sky, 0, -61, 0), 0);
+		assert_eq!(light(&world, LightKind::Block, 0, -60, 0), 0);
+
+		// a roof casts a shadow that is lit from the sides
+		for x in -1..=1 {
+			for z in -1..=1 {
+				world.set_block(PackedPosition::new(x, -50, z), BlockState(1));
+			}
+		}
+		assert_eq!(light(&world, LightKind::Sky, 0, -50, 0), 0);
+		assert_eq!(light(&world, LightKind::Sky, 0, -51, 0), 13);
+		assert_eq!(light(&world, LightKind::Sky, 1, -51, 0), 14);
+		assert_eq!(light(&world, LightKind::Sky, 0, -49, 0), 15);
+		world.set_block(PackedPosition::new(0, -50, 0), BlockState(0));
+		assert_eq!(light(&world, LightKind::Sky, 0, -55, 0), 15);
+
+		// light spreads across column borders
+		world.set_block(PackedPosition::new(-1, -60, 5), BlockState(50));
+		assert_eq!(light(&world, LightKind::Block, -1, -60, 5), 15);
+		assert_eq!(light(&world, LightKind::Block, 0, -60, 5), 14);
+		assert_eq!(light(&world, LightKind::Block, 2, -59, 5), 11);
+		world.set_block(PackedPosition::new(-1, -60, 5), BlockState(0));
+		assert_eq!(light(&world, LightKind::Block, 0, -60, 5), 0);
+		assert_eq!(light(&world, LightKind::Block, 2, -59, 5), 0);
+		assert!(!world.take_light_changes().is_empty());
+	}
+}
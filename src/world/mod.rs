@@ -0,0 +1,546 @@
+use crate::config::GeneratorConfig;
+use crate::packets::helpers::game::biome::BiomeRegistry;
+use crate::packets::helpers::game::chunk::{self, MultiBlockChangeEntry, SectionPosition};
+use crate::packets::helpers::game::dimension;
+use crate::packets::helpers::position::{PackedPosition, UnpackedPosition};
+use crate::packets::helpers::varint::VarInt;
+use crate::packets::helpers::wrappers::std::PrefixedVec;
+use crate::packets::play::send::{self, Packet as Send};
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+pub mod anvil;
+pub mod block_states;
+pub mod generator;
+pub mod level;
+pub mod light;
+pub mod player_data;
+
+pub use block_states::BlockState;
+
+pub type ChunkPosition = chunk::Position<i32>;
+
+/// 16x16x16 blocks
+pub struct Section {
+	/// Indexed by `(y * 16 + z) * 16 + x`; `None` if the section is entirely `minecraft:air`
+	block_states: Option<Box<[BlockState]>>,
+	/// One for each 4x4x4 cell, indexed the same way as `block_states`
+	biomes: [i32; 64],
+	/// Non-air blocks
+	block_count: i16,
+	/// Whether any blocks have been set since the section was created, so that unchanged sections can be saved as they were loaded
+	modified: bool,
+}
+
+impl Section {
+	pub fn empty(biome: i32) -> Self {
+		Self {
+			block_states: None,
+			biomes: [biome; 64],
+			block_count: 0,
+			modified: false,
+		}
+	}
+	/// `block_states` must have 4096 entries
+	pub fn new(block_states: Box<[BlockState]>, biomes: [i32; 64]) -> Self {
+		let block_count = block_states.iter().filter(|state| !state.is_air()).count() as i16;
+		Self {
+			block_states: if block_states.iter().any(|&state| state != BlockState::AIR) { Some(block_states) } else { None },
+			biomes,
+			block_count,
+			modified: false,
+		}
+	}
+	fn index(x: usize, y: usize, z: usize) -> usize {
+		(y * 16 + z) * 16 + x
+	}
+	/// Coordinates are relative to the section
+	pub fn get_block(&self, x: usize, y: usize, z: usize) -> BlockState {
+		self.block_states.as_ref().map_or(BlockState::AIR, |block_states| block_states[Self::index(x, y, z)])
+	}
+	/// Coordinates are relative to the section. Returns the previous state.
+	pub fn set_block(&mut self, x: usize, y: usize, z: usize, state: BlockState) -> BlockState {
+		if self.block_states.is_none() && state == BlockState::AIR {
+			return BlockState::AIR;
+		}
+		let block_states = self.block_states.get_or_insert_with(|| vec![BlockState::AIR; 4096].into_boxed_slice());
+		let old = std::mem::replace(&mut block_states[Self::index(x, y, z)], state);
+		self.modified |= old != state;
+		self.block_count += i16::from(!state.is_air()) - i16::from(!old.is_air());
+		// other kinds of air still need to be stored
+		if self.block_count == 0 && block_states.iter().all(|&state| state == BlockState::AIR) {
+			self.block_states = None;
+		}
+		old
+	}
+	/// Sets every block at the section-relative Y coordinate
+	pub fn fill_layer(&mut self, y: usize, state: BlockState) {
+		for z in 0..16 {
+			for x in 0..16 {
+				self.set_block(x, y, z, state);
+			}
+		}
+	}
+	/// Coordinates are in 4x4x4 cells, relative to the section
+	pub fn set_biome(&mut self, x: usize, y: usize, z: usize, biome: i32) {
+		self.biomes[(y * 4 + z) * 4 + x] = biome;
+	}
+	pub fn block_count(&self) -> i16 {
+		self.block_count
+	}
+	fn to_packet(&self, biome_count: usize) -> chunk::Section {
+		let block_states = match &self.block_states {
+			Some(block_states) => chunk::PalettedContainer::block_states(&block_states.iter().map(|state| i32::from(state.id())).collect::<Vec<_>>()),
+			None => chunk::PalettedContainer::Single(VarInt(BlockState::AIR.id().into())),
+		};
+		chunk::Section {
+			block_count: self.block_count,
+			block_states,
+			biomes: chunk::PalettedContainer::biomes(&self.biomes, biome_count),
+		}
+	}
+}
+
+/// A chunk column: all the sections at one chunk position, from the bottom of the world up
+pub struct Column {
+	sections: Vec<Section>,
+	/// What was loaded from storage, if the column was
+	stored: Option<anvil::StoredChunk>,
+}
+
+impl Column {
+	pub fn empty(num_sections: usize, biome: i32) -> Self {
+		Self {
+			sections: (0..num_sections).map(|_| Section::empty(biome)).collect(),
+			stored: None,
+		}
+	}
+	pub fn sections(&self) -> &[Section] {
+		&self.sections
+	}
+	pub fn sections_mut(&mut self) -> &mut [Section] {
+		&mut self.sections
+	}
+	/// The `WORLD_SURFACE` heightmap, of the highest non-air blocks
+	fn heights(&self) -> [i32; 256] {
+		self.heights_where(|block| !block.is_air())
+	}
+	/// For each block column, indexed by `z * 16 + x`, one plus the Y of the highest block that matches, counted from the bottom of the column, or 0 if there is none
+	fn heights_where(&self, matches: impl Fn(BlockState) -> bool) -> [i32; 256] {
+		let mut heights = [0; 256];
+		for (section_y, section) in self.sections.iter().enumerate().rev() {
+			if section.block_count == 0 {
+				continue;
+			}
+			for (idx, height) in heights.iter_mut().enumerate().filter(|(_, height)| **height == 0) {
+				let (x, z) = (idx % 16, idx / 16);
+				if let Some(y) = (0..16).rev().find(|&y| matches(section.get_block(x, y, z))) {
+					*height = (section_y * 16 + y + 1) as i32;
+				}
+			}
+		}
+		heights
+	}
+}
+
+/// A set of changes to blocks in one section that clients haven't been told about yet
+#[derive(Default)]
+struct SectionChanges(HashMap<UnpackedPosition<u8>, BlockState>);
+
+/// What's needed to interpret stored chunks
+struct Dimension {
+	min_y: i32,
+	num_sections: usize,
+	/// Size of the biome registry, which determines how biomes are encoded
+	biome_count: usize,
+	biome_ids: HashMap<String, i32>,
+	biome_names: HashMap<i32, String>,
+	/// Used for empty columns and biomes that aren't in the registry
+	default_biome: i32,
+}
+
+/// The blocks in one dimension
+pub struct World {
+	dimension: Dimension,
+	columns: HashMap<ChunkPosition, Column>,
+	/// Keyed by section position
+	changes: HashMap<UnpackedPosition<i32>, SectionChanges>,
+	/// Columns that have changed since they were last saved
+	dirty: HashSet<ChunkPosition>,
+	/// The clients that have each column loaded, by their player's entity ID
+	viewers: HashMap<ChunkPosition, HashSet<i32>>,
+	/// Present for every loaded column
+	light: HashMap<ChunkPosition, light::ColumnLight>,
+	/// Light sections that clients haven't been told about yet, for each column
+	light_changes: HashMap<ChunkPosition, BTreeSet<usize>>,
+	/// The directory containing `level.dat`, if the world is saved at all
+	directory: Option<PathBuf>,
+	/// Where columns are loaded from and saved to; present iff `directory` is
+	storage: Option<anvil::RegionStorage>,
+	level: level::LevelData,
+	/// When `level.time` was last brought up to date
+	level_clock: Instant,
+	generator: Box<dyn generator::WorldGenerator>,
+}
+
+impl World {
+	/// `directory` is a vanilla world directory (containing `level.dat`) to load from and save to; if it's `None`, nothing is saved.
+	/// `seed` is only used for new worlds, and a random one is chosen if it's `None`.
+	pub fn new(dimension: &dimension::Type, biomes: &BiomeRegistry, directory: Option<&Path>, generator: &GeneratorConfig, seed: Option<i64>) -> anyhow::Result<Self> {
+		let existing_level = match directory {
+			Some(directory) => level::LevelData::load(&directory.join("level.dat"))?,
+			None => None,
+		};
+		let (level, generator) = match existing_level {
+			Some(level) => {
+				if matches!(seed, Some(seed) if seed != level.seed) {
+					log::warn!("Ignoring the configured seed because the world already has the seed {}", level.seed);
+				}
+				let generator = <dyn generator::WorldGenerator>::from_config(generator, biomes, level.seed)?;
+				(level, generator)
+			}
+			None => {
+				let mut level = level::LevelData::default();
+				level.seed = seed.unwrap_or_else(rand::random);
+				let generator = <dyn generator::WorldGenerator>::from_config(generator, biomes, level.seed)?;
+				level.spawn = PackedPosition::new(0, generator.spawn_y(dimension.min_y()) as i16, 0);
+				(level, generator)
+			}
+		};
+		Ok(Self {
+			dimension: Dimension {
+				min_y: dimension.min_y(),
+				num_sections: (dimension.height() / 16) as usize,
+				biome_count: biomes.len(),
+				biome_ids: biomes.iter().map(|(name, id)| (name.to_owned(), id)).collect(),
+				biome_names: biomes.iter().map(|(name, id)| (id, name.to_owned())).collect(),
+				default_biome: biomes.id_of("minecraft:plains").unwrap_or(0),
+			},
+			columns: HashMap::new(),
+			changes: HashMap::new(),
+			dirty: HashSet::new(),
+			viewers: HashMap::new(),
+			light: HashMap::new(),
+			light_changes: HashMap::new(),
+			directory: directory.map(Path::to_owned),
+			storage: directory.map(anvil::RegionStorage::new),
+			level,
+			level_clock: Instant::now(),
+			generator,
+		})
+	}
+	pub fn min_y(&self) -> i32 {
+		self.dimension.min_y
+	}
+	pub fn num_sections(&self) -> usize {
+		self.dimension.num_sections
+	}
+	pub fn column(&self, position: ChunkPosition) -> Option<&Column> {
+		self.columns.get(&position)
+	}
+	/// Loads the column from storage if it isn't loaded already, or generates it if it has never been saved
+	pub fn load_column(&mut self, position: ChunkPosition) -> anyhow::Result<&mut Column> {
+		if !self.columns.contains_key(&position) {
+			let stored = match &mut self.storage {
+				Some(storage) => storage.load_column(position, &self.dimension)?,
+				None => None,
+			};
+			let column = match stored {
+				Some(column) => column,
+				None => {
+					// without storage, only changed columns need to be kept around
+					if self.storage.is_some() {
+						self.dirty.insert(position);
+					}
+					self.generator.generate(position, self.dimension.min_y, self.dimension.num_sections)
+				}
+			};
+			self.insert_column(position, column);
+		}
+		// PANICS: the column was inserted above if it was missing
+		Ok(self.columns.get_mut(&position).unwrap())
+	}
+	pub fn insert_column(&mut self, position: ChunkPosition, column: Column) {
+		self.columns.insert(position, column);
+		self.lighting().light_column(position);
+	}
+	fn lighting(&mut self) -> light::Engine<'_> {
+		light::Engine {
+			columns: &self.columns,
+			light: &mut self.light,
+			changes: &mut self.light_changes,
+			num_sections: self.dimension.num_sections,
+		}
+	}
+	/// Saves the column first if it has changed
+	pub fn unload_column(&mut self, position: ChunkPosition) -> anyhow::Result<()> {
+		self.light.remove(&position);
+		self.light_changes.remove(&position);
+		if let Some(column) = self.columns.remove(&position) {
+			if self.dirty.remove(&position) {
+				if let Some(storage) = &mut self.storage {
+					storage.save_column(position, &column, &self.dimension)?;
+				}
+			}
+		}
+		Ok(())
+	}
+	/// Loads the column for a client that is about to be sent it, returning the packet to send. `viewer` is the entity ID of the client's player.
+	pub fn watch_column(&mut self, position: ChunkPosition, viewer: i32) -> anyhow::Result<send::UpdateChunkData> {
+		self.load_column(position)?;
+		let viewers = self.viewers.entry(position).or_default();
+		// the packet has all of the light, so there is no one else to tell about changes
+		if viewers.is_empty() {
+			self.light_changes.remove(&position);
+		}
+		viewers.insert(viewer);
+		// PANICS: the column was just loaded
+		Ok(self.column_packet(position).unwrap())
+	}
+	/// The opposite of `watch_column`. Once no clients have the column loaded it is unloaded, unless it has unsaved changes and there is nowhere to save them.
+	pub fn unwatch_column(&mut self, position: ChunkPosition, viewer: i32) -> anyhow::Result<()> {
+		let viewers = match self.viewers.get_mut(&position) {
+			Some(viewers) => viewers,
+			None => return Ok(()),
+		};
+		viewers.remove(&viewer);
+		if viewers.is_empty() {
+			self.viewers.remove(&position);
+			if self.storage.is_some() || !self.dirty.contains(&position) {
+				self.unload_column(position)?;
+			}
+		}
+		Ok(())
+	}
+	/// The entity IDs of the players whose clients have the column loaded
+	pub fn viewers(&self, position: ChunkPosition) -> impl Iterator<Item = i32> + '_ {
+		self.viewers.get(&position).into_iter().flatten().copied()
+	}
+	pub fn is_flat(&self) -> bool {
+		self.generator.is_flat()
+	}
+	pub fn hashed_seed(&self) -> dimension::HashedSeed {
+		dimension::HashedSeed::from_seed(self.level.seed)
+	}
+	pub fn level(&self) -> &level::LevelData {
+		&self.level
+	}
+	/// Saves every changed column and `level.dat`. Does nothing if the world has no directory.
+	pub fn save(&mut self) -> anyhow::Result<()> {
+		let (directory, storage) = match (&self.directory, &mut self.storage) {
+			(Some(directory), Some(storage)) => (directory, storage),
+			_ => return Ok(()),
+		};
+		let num_dirty = self.dirty.len();
+		for position in self.dirty.drain() {
+			if let Some(column) = self.columns.get(&position) {
+				storage.save_column(position, column, &self.dimension)?;
+			}
+		}
+		// 20 ticks per second; the remainder carries over to the next save
+		let ticks = self.level_clock.elapsed().as_millis() / 50;
+		self.level_clock += Duration::from_millis(ticks as u64 * 50);
+		self.level.advance(ticks as i64);
+		self.level.save(&directory.join("level.dat"))?;
+		log::debug!("Saved {} chunks", num_dirty);
+		Ok(())
+	}
+	/// Returns the section index and the section-relative Y, or `None` if the position is above or below the world
+	fn section_of(&self, y: i16) -> Option<(usize, usize)> {
+		let relative_y = usize::try_from(i32::from(y) - self.dimension.min_y).ok()?;
+		let section_idx = relative_y / 16;
+		if section_idx < self.dimension.num_sections {
+			Some((section_idx, relative_y % 16))
+		} else {
+			None
+		}
+	}
+	/// `None` if the position is outside the world or in a column that isn't loaded
+	pub fn get_block(&self, position: PackedPosition) -> Option<BlockState> {
+		let (section_idx, y) = self.section_of(position.y())?;
+		let column = self.columns.get(&ChunkPosition { x: position.x() >> 4, z: position.z() >> 4 })?;
+		Some(column.sections[section_idx].get_block((position.x() & 15) as usize, y, (position.z() & 15) as usize))
+	}
+	/// Returns the previous state, or `None` (and does nothing) if the position is outside the world or in a column that isn't loaded.
+	/// Clients are told about the change the next time `take_changes` is called.
+	pub fn set_block(&mut self, position: PackedPosition, state: BlockState) -> Option<BlockState> {
+		let (section_idx, y) = self.section_of(position.y())?;
+		let column_position = ChunkPosition { x: position.x() >> 4, z: position.z() >> 4 };
+		let column = self.columns.get_mut(&column_position)?;
+		let (x, z) = ((position.x() & 15) as u8, (position.z() & 15) as u8);
+		let old = column.sections[section_idx].set_block(x.into(), y, z.into(), state);
+		if old != state {
+			self.dirty.insert(column_position);
+			let section_position = UnpackedPosition {
+				x: column_position.x,
+				y: (self.dimension.min_y >> 4) + section_idx as i32,
+				z: column_position.z,
+			};
+			self.changes.entry(section_position).or_default().0.insert(UnpackedPosition { x, y: y as u8, z }, state);
+			let relative_y = i32::from(position.y()) - self.dimension.min_y;
+			self.lighting().update_block(position.x(), relative_y, position.z(), old, state);
+		}
+		Some(old)
+	}
+	/// Encodes the column for sending to a client, or `None` if it isn't loaded
+	pub fn column_packet(&self, position: ChunkPosition) -> Option<send::UpdateChunkData> {
+		let column = self.columns.get(&position)?;
+		Some(send::UpdateChunkData {
+			chunk_position: position,
+			height_maps: chunk::HeightMaps::new(&column.heights_where(BlockState::is_motion_blocking), &column.heights(), self.dimension.num_sections * 16),
+			chunk_blocks: chunk::Blocks(column.sections.iter().map(|section| section.to_packet(self.dimension.biome_count)).collect()),
+			chunk_block_entities: chunk::BlockEntities::none(),
+			common: self.light_packet(position, None),
+		})
+	}
+	/// `None` if the column isn't loaded. `position` may be up to a section above or below the world.
+	pub fn light(&self, kind: light::LightKind, position: PackedPosition) -> Option<u8> {
+		let y = i32::from(position.y()) - self.dimension.min_y + 16;
+		if y < 0 || y >= (self.dimension.num_sections as i32 + 2) * 16 {
+			return None;
+		}
+		let light = self.light.get(&ChunkPosition { x: position.x() >> 4, z: position.z() >> 4 })?;
+		Some(light.sections(kind)[(y / 16) as usize].get((((y & 15) * 16 + (position.z() & 15)) * 16 + (position.x() & 15)) as usize))
+	}
+	/// Only includes the given light sections, or all of them if `sections` is `None`
+	fn light_packet(&self, position: ChunkPosition, sections: Option<&BTreeSet<usize>>) -> chunk::LightUpdateCommon {
+		let light = match self.light.get(&position) {
+			Some(light) => light,
+			None => return chunk::LightUpdateCommon::empty(),
+		};
+		let entries = |kind| {
+			light
+				.sections(kind)
+				.iter()
+				.enumerate()
+				.filter(|(idx, _)| sections.is_none_or(|sections| sections.contains(idx)))
+				.map(|(idx, section)| (idx, section.to_packet()))
+				.collect()
+		};
+		chunk::LightUpdateCommon::new(entries(light::LightKind::Sky), entries(light::LightKind::Block))
+	}
+	/// Drains the light changes made since the last call, as Update Light packets to send to the clients that have the given column loaded
+	pub fn take_light_changes(&mut self) -> Vec<(ChunkPosition, Send<'static>)> {
+		std::mem::take(&mut self.light_changes)
+			.into_iter()
+			.map(|(position, sections)| {
+				let common = self.light_packet(position, Some(&sections));
+				(
+					position,
+					Send::UpdateLight(send::UpdateLight {
+						chunk_position: chunk::Position { x: VarInt(position.x), z: VarInt(position.z) },
+						common,
+					}),
+				)
+			})
+			.collect()
+	}
+	/// Drains the changes made since the last call, as packets to send to the clients that have the given column loaded.
+	/// Sections with a single change get a Block Change packet, and others get a batched Multi Block Change packet.
+	pub fn take_changes(&mut self) -> Vec<(ChunkPosition, Send<'static>)> {
+		self.changes
+			.drain()
+			.map(|(section, SectionChanges(changes))| {
+				let column = ChunkPosition { x: section.x, z: section.z };
+				let packet = if changes.len() == 1 {
+					// PANICS: there is exactly one change
+					let (relative, state) = changes.into_iter().next().unwrap();
+					Send::BlockChange(send::BlockChange {
+						location: PackedPosition::new(section.x * 16 + i32::from(relative.x), (section.y * 16 + i32::from(relative.y)) as i16, section.z * 16 + i32::from(relative.z)),
+						new_block_state: VarInt(state.id().into()),
+					})
+				} else {
+					Send::MultiBlockChange(send::MultiBlockChange {
+						origin_position: SectionPosition::new(section.x, section.y, section.z),
+						no_trust_edges: false,
+						changes: PrefixedVec::new(changes.into_iter().map(|(relative, state)| MultiBlockChangeEntry::new(relative, state.id().into())).collect()),
+					})
+				};
+				(column, packet)
+			})
+			.collect()
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::{BlockState, ChunkPosition, Column, World};
+	use crate::config::GeneratorConfig;
+	use crate::packets::helpers::game::dimension;
+	use crate::packets::helpers::position::PackedPosition;
+	use crate::packets::play::send::Packet as Send;
+
+	fn world() -> World {
+		let codec: dimension::Codec = serde_json::from_str(include_str!("../../assets/dimension_codec.json")).unwrap();
+		let mut world = World::new(codec.dimension_type("minecraft:overworld").unwrap(), codec.biomes(), None, &GeneratorConfig::Void { biome: "minecraft:plains".to_owned() }, None).unwrap();
+		world.insert_column(ChunkPosition { x: 0, z: 0 }, Column::empty(world.num_sections(), 1));
+		world.insert_column(ChunkPosition { x: -1, z: 0 }, Column::empty(world.num_sections(), 1));
+		world
+	}
+
+	#[test]
+	pub fn get_and_set() {
+		let mut world = world();
+		assert_eq!(world.get_block(PackedPosition::new(3, 64, 5)), Some(BlockState(0)));
+		assert_eq!(world.set_block(PackedPosition::new(3, 64, 5), BlockState(1)), Some(BlockState(0)));
+		assert_eq!(world.get_block(PackedPosition::new(3, 64, 5)), Some(BlockState(1)));
+		assert_eq!(world.set_block(PackedPosition::new(-1, -64, 0), BlockState(1)), Some(BlockState(0)));
+		assert_eq!(world.get_block(PackedPosition::new(-1, -64, 0)), Some(BlockState(1)));
+		// below the world, above the world, and not loaded
+		assert_eq!(world.set_block(PackedPosition::new(0, -65, 0), BlockState(1)), None);
+		assert_eq!(world.get_block(PackedPosition::new(0, 320, 0)), None);
+		assert_eq!(world.get_block(PackedPosition::new(16, 0, 0)), None);
+
+		let column = world.column(ChunkPosition { x: 0, z: 0 }).unwrap();
+		// y = 64 is in the ninth section from the bottom
+		assert_eq!(column.sections()[8].block_count(), 1);
+		assert_eq!(world.set_block(PackedPosition::new(3, 64, 5), BlockState(0)), Some(BlockState(1)));
+		assert_eq!(world.column(ChunkPosition { x: 0, z: 0 }).unwrap().sections()[8].block_count(), 0);
+	}
+
+	#[test]
+	pub fn watching() {
+		let mut world = world();
+		let position = ChunkPosition { x: 5, z: 5 };
+		world.watch_column(position, 1).unwrap();
+		world.watch_column(position, 2).unwrap();
+		world.unwatch_column(position, 1).unwrap();
+		assert!(world.column(position).is_some());
+		assert_eq!(world.viewers(position).collect::<Vec<_>>(), [2]);
+		world.unwatch_column(position, 2).unwrap();
+		assert!(world.column(position).is_none());
+		assert_eq!(world.viewers(position).count(), 0);
+		// changes can't be saved without a world directory, so the column is kept
+		world.watch_column(position, 1).unwrap();
+		world.set_block(PackedPosition::new(80, 0, 80), BlockState(1));
+		world.unwatch_column(position, 1).unwrap();
+		assert_eq!(world.get_block(PackedPosition::new(80, 0, 80)), Some(BlockState(1)));
+	}
+
+	#[test]
+	pub fn changes() {
+		let mut world = world();
+		world.set_block(PackedPosition::new(3, 64, 5), BlockState(1));
+		world.set_block(PackedPosition::new(-1, 0, 0), BlockState(1));
+		world.set_block(PackedPosition::new(-2, 0, 0), BlockState(1));
+		// setting a block to its current state is not a change
+		world.set_block(PackedPosition::new(0, 0, 0), BlockState(0));
+		let mut changes = world.take_changes();
+		changes.sort_by_key(|(column, _)| column.x);
+		assert!(matches!(changes.as_slice(), [(ChunkPosition { x: -1, z: 0 }, Send::MultiBlockChange(_)), (ChunkPosition { x: 0, z: 0 }, Send::BlockChange(_))]));
+		assert!(world.take_changes().is_empty());
+	}
+
+	#[test]
+	pub fn heightmaps() {
+		let mut world = world();
+		world.set_block(PackedPosition::new(3, 0, 5), "stone".parse().unwrap());
+		// rain falls through torches, but they're part of the surface
+		world.set_block(PackedPosition::new(3, 1, 5), "torch".parse().unwrap());
+		let column = world.column(ChunkPosition { x: 0, z: 0 }).unwrap();
+		assert_eq!(column.heights_where(BlockState::is_motion_blocking)[5 * 16 + 3], 65);
+		assert_eq!(column.heights()[5 * 16 + 3], 66);
+		assert_eq!(column.heights()[0], 0);
+	}
+}
@@ -0,0 +1,68 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+fn path(directory: &Path, uuid: uuid::Uuid) -> PathBuf {
+	directory.join("playerdata").join(format!("{}.dat", uuid))
+}
+
+/// The root compound of a player's `playerdata/<uuid>.dat`. `None` if the player hasn't been saved before.
+pub fn load(directory: &Path, uuid: uuid::Uuid) -> anyhow::Result<Option<HashMap<String, nbt::Value>>> {
+	let path = path(directory, uuid);
+	let file = match std::fs::File::open(&path) {
+		Ok(file) => file,
+		Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+		Err(err) => return Err(anyhow::Error::new(err).context(format!("Opening {}", path.display()))),
+	};
+	// `nbt::Blob` doesn't give access to all of its tags, so the root compound is read directly
+	let data = super::anvil::read_root_compound(flate2::read::GzDecoder::new(file)).map_err(|err| err.context(format!("Reading {}", path.display())))?;
+	Ok(Some(data))
+}
+
+/// Writes to a temporary file first and keeps the previous file as `<uuid>.dat_old`, like vanilla
+pub fn save(directory: &Path, uuid: uuid::Uuid, data: &HashMap<String, nbt::Value>) -> anyhow::Result<()> {
+	let path = path(directory, uuid);
+	// PANICS: the path is in the `playerdata` directory
+	std::fs::create_dir_all(path.parent().unwrap())?;
+	let mut blob = nbt::Blob::new();
+	for (key, value) in data {
+		blob.insert(key.as_str(), value.clone())?;
+	}
+	blob.insert("DataVersion", nbt::Value::Int(super::level::DATA_VERSION))?;
+	let new_path = path.with_extension("dat_new");
+	blob.to_gzip_writer(&mut std::fs::File::create(&new_path)?)?;
+	if path.exists() {
+		std::fs::rename(&path, path.with_extension("dat_old"))?;
+	}
+	std::fs::rename(&new_path, &path)?;
+	Ok(())
+}
+
+#[cfg(test)]
+mod test {
+	use super::{load, save};
+	use std::collections::HashMap;
+	use std::io::Write;
+
+	#[test]
+	pub fn round_trip() {
+		let directory = std::env::temp_dir().join(format!("rust-mc-player-data-{}", std::process::id()));
+		let uuid = uuid::Uuid::from_u128(0x0123_4567_89ab_cdef_0123_4567_89ab_cdef);
+		assert!(load(&directory, uuid).unwrap().is_none());
+
+		let data: HashMap<_, _> = [("XpLevel".to_owned(), nbt::Value::Int(30))].into_iter().collect();
+		save(&directory, uuid, &data).unwrap();
+		// saving again keeps the previous file
+		save(&directory, uuid, &data).unwrap();
+		let loaded = load(&directory, uuid).unwrap().unwrap();
+		assert_eq!(loaded.get("XpLevel"), Some(&nbt::Value::Int(30)));
+		assert!(loaded.contains_key("DataVersion"));
+		assert!(directory.join("playerdata/01234567-89ab-cdef-0123-456789abcdef.dat_old").exists());
+
+		// a file that's cut off is an error rather than a crash
+		let mut file = flate2::write::GzEncoder::new(std::fs::File::create(directory.join("playerdata/01234567-89ab-cdef-0123-456789abcdef.dat")).unwrap(), flate2::Compression::default());
+		file.write_all(&[0x0a, 0, 5, b'a']).unwrap();
+		file.finish().unwrap();
+		assert!(load(&directory, uuid).is_err());
+		std::fs::remove_dir_all(&directory).unwrap();
+	}
+}